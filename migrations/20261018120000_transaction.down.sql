@@ -0,0 +1,3 @@
+DROP TABLE transaction;
+
+DROP TYPE transaction_kind;
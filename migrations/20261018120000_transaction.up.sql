@@ -0,0 +1,23 @@
+-- Expenses and incomes recorded by the user
+CREATE TYPE transaction_kind AS ENUM('EXPENSE', 'INCOME');
+
+CREATE TABLE transaction (
+    id UUID PRIMARY KEY,
+    user_id INTEGER NOT NULL,
+    kind transaction_kind NOT NULL,
+    amount BIGINT NOT NULL,
+    description VARCHAR(255) NOT NULL,
+    transaction_date TIMESTAMP NOT NULL,
+    category_id UUID,
+    subcategory_id UUID,
+    credit_card_id UUID,
+    is_active BOOLEAN NOT NULL DEFAULT true,
+    created_at TIMESTAMP NOT NULL DEFAULT(now() at time zone 'utc'),
+    updated_at TIMESTAMP NOT NULL DEFAULT(now() at time zone 'utc'),
+    FOREIGN KEY(user_id) REFERENCES "user"(id),
+    FOREIGN KEY(category_id) REFERENCES category(id),
+    FOREIGN KEY(subcategory_id) REFERENCES subcategory(id),
+    FOREIGN KEY(credit_card_id) REFERENCES credit_card(id)
+);
+CREATE UNIQUE INDEX transaction_id_user_id_idx ON transaction(id, user_id);
+CREATE INDEX transaction_user_id_transaction_date_idx ON transaction(user_id, transaction_date);
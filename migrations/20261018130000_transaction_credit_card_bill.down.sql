@@ -0,0 +1,3 @@
+ALTER TABLE transaction DROP COLUMN credit_card_bill_id;
+
+DROP INDEX credit_card_bill_credit_card_id_start_at_idx;
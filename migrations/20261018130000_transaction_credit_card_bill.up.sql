@@ -0,0 +1,10 @@
+-- Bills are now looked up by billing window, so keep a single bill per window
+DELETE FROM credit_card_bill a
+    USING credit_card_bill b
+    WHERE a.credit_card_id = b.credit_card_id
+        AND a.start_at = b.start_at
+        AND (a.created_at, a.id) > (b.created_at, b.id);
+CREATE UNIQUE INDEX credit_card_bill_credit_card_id_start_at_idx ON credit_card_bill(credit_card_id, start_at);
+
+ALTER TABLE transaction ADD COLUMN credit_card_bill_id UUID;
+ALTER TABLE transaction ADD FOREIGN KEY(credit_card_bill_id) REFERENCES credit_card_bill(id);
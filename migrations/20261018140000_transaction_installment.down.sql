@@ -0,0 +1,5 @@
+DROP INDEX transaction_parent_transaction_id_installment_number_idx;
+
+ALTER TABLE transaction DROP COLUMN installment_count;
+ALTER TABLE transaction DROP COLUMN installment_number;
+ALTER TABLE transaction DROP COLUMN parent_transaction_id;
@@ -0,0 +1,8 @@
+-- Installment purchases: the parent keeps the total, each installment is a
+-- child row assigned to its own credit card bill
+ALTER TABLE transaction ADD COLUMN parent_transaction_id UUID;
+ALTER TABLE transaction ADD COLUMN installment_number SMALLINT NOT NULL DEFAULT 1;
+ALTER TABLE transaction ADD COLUMN installment_count SMALLINT NOT NULL DEFAULT 1;
+ALTER TABLE transaction ADD FOREIGN KEY(parent_transaction_id) REFERENCES transaction(id);
+CREATE UNIQUE INDEX transaction_parent_transaction_id_installment_number_idx
+    ON transaction(parent_transaction_id, installment_number);
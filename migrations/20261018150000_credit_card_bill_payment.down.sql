@@ -0,0 +1,4 @@
+ALTER TABLE credit_card_bill DROP COLUMN paid_at;
+ALTER TABLE credit_card_bill DROP COLUMN paid_amount;
+
+ALTER TABLE credit_card DROP COLUMN due_day;
@@ -0,0 +1,7 @@
+-- Due day of the card and payment information of its bills
+ALTER TABLE credit_card ADD COLUMN due_day SMALLINT;
+UPDATE credit_card SET due_day = ((closing_day + 6) % 31) + 1;
+ALTER TABLE credit_card ALTER COLUMN due_day SET NOT NULL;
+
+ALTER TABLE credit_card_bill ADD COLUMN paid_amount BIGINT;
+ALTER TABLE credit_card_bill ADD COLUMN paid_at TIMESTAMP;
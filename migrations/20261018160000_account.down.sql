@@ -0,0 +1,5 @@
+ALTER TABLE transaction DROP COLUMN account_id;
+
+DROP TABLE account;
+
+DROP TYPE account_type;
@@ -0,0 +1,19 @@
+-- Bank accounts and wallets used as money sources
+CREATE TYPE account_type AS ENUM('CHECKING', 'SAVINGS', 'CASH', 'WALLET');
+
+CREATE TABLE account(
+    id UUID PRIMARY KEY,
+    user_id INTEGER NOT NULL,
+    name VARCHAR(50) NOT NULL,
+    icon_name VARCHAR(50) NOT NULL,
+    account_type account_type NOT NULL,
+    initial_balance BIGINT NOT NULL,
+    is_active BOOLEAN NOT NULL DEFAULT true,
+    created_at TIMESTAMP NOT NULL DEFAULT(now() at time zone 'utc'),
+    updated_at TIMESTAMP NOT NULL DEFAULT(now() at time zone 'utc'),
+    FOREIGN KEY(user_id) REFERENCES "user"(id)
+);
+CREATE UNIQUE INDEX account_id_user_id_idx ON account(id, user_id);
+
+ALTER TABLE transaction ADD COLUMN account_id UUID;
+ALTER TABLE transaction ADD FOREIGN KEY(account_id) REFERENCES account(id);
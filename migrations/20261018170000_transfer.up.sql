@@ -0,0 +1,20 @@
+-- Money moved between accounts, or from an account to a credit card bill
+CREATE TABLE transfer(
+    id UUID PRIMARY KEY,
+    user_id INTEGER NOT NULL,
+    from_account_id UUID NOT NULL,
+    to_account_id UUID,
+    credit_card_bill_id UUID,
+    amount BIGINT NOT NULL,
+    description VARCHAR(255) NOT NULL,
+    transfer_date TIMESTAMP NOT NULL,
+    is_active BOOLEAN NOT NULL DEFAULT true,
+    created_at TIMESTAMP NOT NULL DEFAULT(now() at time zone 'utc'),
+    updated_at TIMESTAMP NOT NULL DEFAULT(now() at time zone 'utc'),
+    FOREIGN KEY(user_id) REFERENCES "user"(id),
+    FOREIGN KEY(from_account_id) REFERENCES account(id),
+    FOREIGN KEY(to_account_id) REFERENCES account(id),
+    FOREIGN KEY(credit_card_bill_id) REFERENCES credit_card_bill(id),
+    CHECK ((to_account_id IS NULL) <> (credit_card_bill_id IS NULL))
+);
+CREATE UNIQUE INDEX transfer_id_user_id_idx ON transfer(id, user_id);
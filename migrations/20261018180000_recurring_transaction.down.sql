@@ -0,0 +1,7 @@
+DROP INDEX transaction_recurring_transaction_id_occurrence_number_idx;
+ALTER TABLE transaction DROP COLUMN occurrence_number;
+ALTER TABLE transaction DROP COLUMN recurring_transaction_id;
+
+DROP TABLE recurring_transaction;
+
+DROP TYPE recurrence_frequency;
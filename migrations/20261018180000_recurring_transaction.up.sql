@@ -0,0 +1,39 @@
+-- Rules that generate transactions periodically (subscriptions, rent, salary)
+CREATE TYPE recurrence_frequency AS ENUM('DAILY', 'WEEKLY', 'MONTHLY', 'YEARLY');
+
+CREATE TABLE recurring_transaction(
+    id UUID PRIMARY KEY,
+    user_id INTEGER NOT NULL,
+    kind transaction_kind NOT NULL,
+    amount BIGINT NOT NULL,
+    description VARCHAR(255) NOT NULL,
+    category_id UUID,
+    subcategory_id UUID,
+    credit_card_id UUID,
+    account_id UUID,
+    frequency recurrence_frequency NOT NULL,
+    frequency_interval SMALLINT NOT NULL,
+    start_at TIMESTAMP NOT NULL,
+    utc_offset INTEGER NOT NULL,
+    end_at TIMESTAMP,
+    occurrence_limit INTEGER,
+    next_occurrence_number INTEGER NOT NULL DEFAULT 0,
+    next_occurrence_at TIMESTAMP,
+    is_active BOOLEAN NOT NULL DEFAULT true,
+    created_at TIMESTAMP NOT NULL DEFAULT(now() at time zone 'utc'),
+    updated_at TIMESTAMP NOT NULL DEFAULT(now() at time zone 'utc'),
+    FOREIGN KEY(user_id) REFERENCES "user"(id),
+    FOREIGN KEY(category_id) REFERENCES category(id),
+    FOREIGN KEY(subcategory_id) REFERENCES subcategory(id),
+    FOREIGN KEY(credit_card_id) REFERENCES credit_card(id),
+    FOREIGN KEY(account_id) REFERENCES account(id)
+);
+CREATE UNIQUE INDEX recurring_transaction_id_user_id_idx ON recurring_transaction(id, user_id);
+CREATE INDEX recurring_transaction_next_occurrence_at_idx
+    ON recurring_transaction(next_occurrence_at) WHERE is_active = true;
+
+ALTER TABLE transaction ADD COLUMN recurring_transaction_id UUID;
+ALTER TABLE transaction ADD COLUMN occurrence_number INTEGER;
+ALTER TABLE transaction ADD FOREIGN KEY(recurring_transaction_id) REFERENCES recurring_transaction(id);
+CREATE UNIQUE INDEX transaction_recurring_transaction_id_occurrence_number_idx
+    ON transaction(recurring_transaction_id, occurrence_number);
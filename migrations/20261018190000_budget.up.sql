@@ -0,0 +1,20 @@
+-- Monthly spending limits per category or subcategory
+CREATE TABLE budget(
+    id UUID PRIMARY KEY,
+    user_id INTEGER NOT NULL,
+    category_id UUID,
+    subcategory_id UUID,
+    amount BIGINT NOT NULL,
+    rollover BOOLEAN NOT NULL DEFAULT false,
+    start_month DATE NOT NULL,
+    is_active BOOLEAN NOT NULL DEFAULT true,
+    created_at TIMESTAMP NOT NULL DEFAULT(now() at time zone 'utc'),
+    updated_at TIMESTAMP NOT NULL DEFAULT(now() at time zone 'utc'),
+    FOREIGN KEY(user_id) REFERENCES "user"(id),
+    FOREIGN KEY(category_id) REFERENCES category(id),
+    FOREIGN KEY(subcategory_id) REFERENCES subcategory(id),
+    CHECK((category_id IS NULL) <> (subcategory_id IS NULL))
+);
+CREATE UNIQUE INDEX budget_id_user_id_idx ON budget(id, user_id);
+CREATE UNIQUE INDEX budget_category_id_idx ON budget(category_id) WHERE is_active = true;
+CREATE UNIQUE INDEX budget_subcategory_id_idx ON budget(subcategory_id) WHERE is_active = true;
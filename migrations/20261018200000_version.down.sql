@@ -0,0 +1,3 @@
+ALTER TABLE credit_card DROP COLUMN version;
+ALTER TABLE subcategory DROP COLUMN version;
+ALTER TABLE category DROP COLUMN version;
@@ -0,0 +1,3 @@
+ALTER TABLE category ADD COLUMN version INTEGER NOT NULL DEFAULT 1;
+ALTER TABLE subcategory ADD COLUMN version INTEGER NOT NULL DEFAULT 1;
+ALTER TABLE credit_card ADD COLUMN version INTEGER NOT NULL DEFAULT 1;
@@ -0,0 +1,12 @@
+DROP INDEX sessions_user_email_idx;
+
+-- Keeps only the latest session of each user
+DELETE FROM sessions s
+    USING sessions newer
+    WHERE newer.user_email = s.user_email AND newer.created_at > s.created_at;
+
+ALTER TABLE sessions
+    DROP COLUMN last_seen_at,
+    DROP COLUMN platform,
+    DROP COLUMN device_name;
+ALTER TABLE sessions ADD CONSTRAINT sessions_user_email_key UNIQUE (user_email);
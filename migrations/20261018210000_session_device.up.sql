@@ -0,0 +1,7 @@
+ALTER TABLE sessions DROP CONSTRAINT sessions_user_email_key;
+ALTER TABLE sessions
+    ADD COLUMN device_name VARCHAR(200) NOT NULL DEFAULT '',
+    ADD COLUMN platform VARCHAR(50) NOT NULL DEFAULT '',
+    ADD COLUMN last_seen_at TIMESTAMP NOT NULL DEFAULT(now() at time zone 'utc');
+
+CREATE INDEX sessions_user_email_idx ON sessions(user_email);
@@ -0,0 +1,7 @@
+CREATE TABLE refresh_token_history (
+    session_id UUID NOT NULL,
+    refresh_token VARCHAR(1000) NOT NULL,
+    used_at TIMESTAMP NOT NULL DEFAULT(now() at time zone 'utc'),
+    PRIMARY KEY(session_id, refresh_token),
+    FOREIGN KEY(session_id) REFERENCES sessions(id) ON DELETE CASCADE
+);
@@ -0,0 +1,9 @@
+CREATE TABLE email_verification (
+    id UUID PRIMARY KEY,
+    user_email VARCHAR(320) NOT NULL,
+    sent_at TIMESTAMP NOT NULL DEFAULT(now() at time zone 'utc'),
+    expires_at TIMESTAMP NOT NULL,
+    is_verified BOOLEAN NOT NULL DEFAULT FALSE
+);
+
+CREATE INDEX email_verification_user_email_idx ON email_verification(user_email);
@@ -0,0 +1,17 @@
+ALTER TABLE "user"
+    ADD COLUMN auth_type auth_type,
+    ADD COLUMN google_user_id VARCHAR(255);
+
+-- Users with both identities go back to the password one
+UPDATE "user" u SET auth_type = 'GOOGLE', google_user_id = i.provider_user_id
+    FROM identity i WHERE i.user_id = u.id AND i.auth_type = 'GOOGLE';
+UPDATE "user" u SET auth_type = 'USERNAMEPASSWORD', google_user_id = NULL
+    FROM identity i WHERE i.user_id = u.id AND i.auth_type = 'USERNAMEPASSWORD';
+
+-- Apple identities are gone once its migration is rolled back, so those
+-- users are left with the password one, recoverable through a reset
+UPDATE "user" SET auth_type = 'USERNAMEPASSWORD' WHERE auth_type IS NULL;
+
+ALTER TABLE "user" ALTER COLUMN auth_type SET NOT NULL;
+
+DROP TABLE identity;
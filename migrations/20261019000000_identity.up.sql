@@ -0,0 +1,15 @@
+CREATE TABLE identity (
+    user_id INTEGER NOT NULL,
+    auth_type auth_type NOT NULL,
+    provider_user_id VARCHAR(255),
+    created_at TIMESTAMP NOT NULL DEFAULT(now() at time zone 'utc'),
+    PRIMARY KEY(user_id, auth_type),
+    FOREIGN KEY(user_id) REFERENCES "user"(id) ON DELETE CASCADE
+);
+
+CREATE UNIQUE INDEX identity_provider_user_id_idx ON identity(auth_type, provider_user_id);
+
+INSERT INTO identity (user_id, auth_type, provider_user_id, created_at)
+    SELECT id, auth_type, google_user_id, created_at FROM "user";
+
+ALTER TABLE "user" DROP COLUMN auth_type, DROP COLUMN google_user_id;
@@ -0,0 +1,8 @@
+DELETE FROM identity WHERE auth_type = 'APPLE';
+
+-- Postgres can't drop an enum value, so the type is recreated without it
+ALTER TYPE auth_type RENAME TO auth_type_old;
+CREATE TYPE auth_type AS ENUM('USERNAMEPASSWORD', 'GOOGLE');
+ALTER TABLE identity
+    ALTER COLUMN auth_type TYPE auth_type USING auth_type::text::auth_type;
+DROP TYPE auth_type_old;
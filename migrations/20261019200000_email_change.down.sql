@@ -0,0 +1,6 @@
+ALTER TABLE sessions
+    DROP CONSTRAINT sessions_user_email_fkey,
+    ADD CONSTRAINT sessions_user_email_fkey
+        FOREIGN KEY(user_email) REFERENCES "user"(email);
+
+ALTER TABLE email_verification DROP COLUMN new_email;
@@ -0,0 +1,8 @@
+-- Set when the verification is for changing the user's email to a new one
+ALTER TABLE email_verification ADD COLUMN new_email VARCHAR(320);
+
+-- Sessions follow the user when the email changes
+ALTER TABLE sessions
+    DROP CONSTRAINT sessions_user_email_fkey,
+    ADD CONSTRAINT sessions_user_email_fkey
+        FOREIGN KEY(user_email) REFERENCES "user"(email) ON UPDATE CASCADE;
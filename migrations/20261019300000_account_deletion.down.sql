@@ -0,0 +1,3 @@
+DROP INDEX user_deletion_scheduled_at_idx;
+
+ALTER TABLE "user" DROP COLUMN deletion_scheduled_at;
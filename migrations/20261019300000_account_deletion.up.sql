@@ -0,0 +1,5 @@
+-- When set, the account and all of its data are erased once this time passes
+ALTER TABLE "user" ADD COLUMN deletion_scheduled_at TIMESTAMP;
+
+CREATE INDEX user_deletion_scheduled_at_idx ON "user"(deletion_scheduled_at)
+    WHERE deletion_scheduled_at IS NOT NULL;
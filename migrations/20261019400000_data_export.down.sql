@@ -0,0 +1,3 @@
+DROP TABLE data_export;
+
+DROP TYPE data_export_status;
@@ -0,0 +1,12 @@
+CREATE TYPE data_export_status AS ENUM('PENDING', 'READY', 'FAILED');
+
+CREATE TABLE data_export (
+    id UUID PRIMARY KEY,
+    user_id INTEGER NOT NULL,
+    status data_export_status NOT NULL DEFAULT 'PENDING',
+    requested_at TIMESTAMP NOT NULL DEFAULT(now() at time zone 'utc'),
+    completed_at TIMESTAMP,
+    FOREIGN KEY(user_id) REFERENCES "user"(id) ON DELETE CASCADE
+);
+
+CREATE INDEX data_export_user_id_idx ON data_export(user_id);
@@ -0,0 +1,10 @@
+-- Failed logins and reset requests, keyed by what they're throttled on
+-- (e.g. "login:email:<email>" or "reset:ip:<ip>")
+CREATE TABLE login_attempt (
+    id UUID PRIMARY KEY,
+    key VARCHAR(400) NOT NULL,
+    attempted_at TIMESTAMP NOT NULL DEFAULT(now() at time zone 'utc')
+);
+
+CREATE INDEX login_attempt_key_attempted_at_idx ON login_attempt(key, attempted_at);
+CREATE INDEX login_attempt_attempted_at_idx ON login_attempt(attempted_at);
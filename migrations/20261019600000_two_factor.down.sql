@@ -0,0 +1,5 @@
+DROP TABLE two_factor_challenge;
+
+DROP TABLE recovery_code;
+
+DROP TABLE totp;
@@ -0,0 +1,33 @@
+CREATE TABLE totp (
+    user_id INTEGER PRIMARY KEY,
+    secret VARCHAR(64) NOT NULL,
+    is_confirmed BOOLEAN NOT NULL DEFAULT false,
+    -- Last time step whose code was accepted, so codes can't be replayed
+    last_used_step BIGINT,
+    created_at TIMESTAMP NOT NULL DEFAULT(now() at time zone 'utc'),
+    FOREIGN KEY(user_id) REFERENCES "user"(id) ON DELETE CASCADE
+);
+
+CREATE TABLE recovery_code (
+    id UUID PRIMARY KEY,
+    user_id INTEGER NOT NULL,
+    code_hash VARCHAR(64) NOT NULL,
+    used_at TIMESTAMP,
+    FOREIGN KEY(user_id) REFERENCES "user"(id) ON DELETE CASCADE
+);
+
+CREATE INDEX recovery_code_user_id_idx ON recovery_code(user_id);
+
+-- Logins waiting for the second factor
+CREATE TABLE two_factor_challenge (
+    id UUID PRIMARY KEY,
+    user_id INTEGER NOT NULL,
+    -- How the first step was done, to answer the second one the same way
+    auth_type auth_type NOT NULL,
+    device_name VARCHAR(200) NOT NULL DEFAULT '',
+    platform VARCHAR(50) NOT NULL DEFAULT '',
+    expires_at TIMESTAMP NOT NULL,
+    attempts INTEGER NOT NULL DEFAULT 0,
+    is_used BOOLEAN NOT NULL DEFAULT false,
+    FOREIGN KEY(user_id) REFERENCES "user"(id) ON DELETE CASCADE
+);
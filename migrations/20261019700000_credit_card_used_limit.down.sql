@@ -0,0 +1 @@
+DROP VIEW credit_card_used_limit;
@@ -0,0 +1,9 @@
+-- Sum of the unpaid bills of each card, counting refunds as credit.
+CREATE VIEW credit_card_used_limit AS
+SELECT
+    b.credit_card_id,
+    SUM(CASE WHEN t.kind = 'INCOME' THEN -t.amount ELSE t.amount END)::BIGINT AS used_limit
+FROM credit_card_bill b
+INNER JOIN transaction t ON t.credit_card_bill_id = b.id AND t.is_active = true
+WHERE b.paid_at IS NULL
+GROUP BY b.credit_card_id;
@@ -0,0 +1,131 @@
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use crate::model::account::{Account, AccountType};
+
+pub async fn upsert_account<'a, T>(
+    account: &Account,
+    con: T,
+) -> Result<(), Box<dyn std::error::Error>>
+where
+    T: sqlx::Executor<'a, Database = sqlx::Postgres>,
+{
+    let _ = sqlx::query!(
+        r#"
+            INSERT INTO account
+                (id, user_id, name, icon_name,
+                account_type, initial_balance)
+            VALUES
+                ($1, $2, $3, $4, $5, $6)
+            ON CONFLICT(id, user_id)
+            DO UPDATE
+                SET name = $3,
+                    icon_name = $4,
+                    account_type = $5,
+                    initial_balance = $6,
+                    updated_at = (now() at time zone 'utc')
+        "#,
+        account.id,
+        account.user_id,
+        account.name,
+        account.icon_name,
+        account.account_type.clone() as AccountType,
+        account.initial_balance
+    )
+    .execute(con)
+    .await?;
+
+    Ok(())
+}
+
+pub async fn delete_account<'a, T>(
+    account_id: Uuid,
+    user_id: i32,
+    con: T,
+) -> Result<(), Box<dyn std::error::Error>>
+where
+    T: sqlx::Executor<'a, Database = sqlx::Postgres>,
+{
+    let _ = sqlx::query!(
+        r#"
+            UPDATE account
+                SET is_active = false,
+                    updated_at = (now() at time zone 'utc')
+                WHERE id = $1 and user_id = $2 and is_active = true
+        "#,
+        account_id,
+        user_id
+    )
+    .execute(con)
+    .await?;
+
+    Ok(())
+}
+
+/// With `updated_since`, only returns the accounts changed after it,
+/// including the ones whose balance moved.
+pub async fn get_accounts_by_user_id<'a, T>(
+    user_id: i32,
+    updated_since: Option<DateTime<Utc>>,
+    con: T,
+) -> Result<Vec<Account>, Box<dyn std::error::Error>>
+where
+    T: sqlx::Executor<'a, Database = sqlx::Postgres>,
+{
+    let rows = sqlx::query!(
+        r#"
+            SELECT
+                a.id,
+                a.user_id,
+                a.name,
+                a.icon_name,
+                a.account_type as "account_type!: AccountType",
+                a.initial_balance,
+                (a.initial_balance + COALESCE((
+                    SELECT SUM(
+                        CASE WHEN t.kind = 'INCOME' THEN t.amount ELSE -t.amount END
+                    )
+                    FROM transaction t
+                    WHERE t.account_id = a.id AND t.is_active = true
+                ), 0) + COALESCE((
+                    SELECT SUM(
+                        CASE WHEN tr.to_account_id = a.id THEN tr.amount ELSE -tr.amount END
+                    )
+                    FROM transfer tr
+                    WHERE (tr.from_account_id = a.id OR tr.to_account_id = a.id)
+                        AND tr.is_active = true
+                ), 0))::BIGINT AS "balance!"
+            FROM account a
+            WHERE a.user_id = $1 AND a.is_active = true
+                AND ($2::timestamp IS NULL
+                    OR a.updated_at > $2
+                    OR EXISTS(
+                        SELECT 1 FROM transaction t
+                        WHERE t.account_id = a.id AND t.updated_at > $2)
+                    OR EXISTS(
+                        SELECT 1 FROM transfer tr
+                        WHERE (tr.from_account_id = a.id OR tr.to_account_id = a.id)
+                            AND tr.updated_at > $2))
+        "#,
+        user_id,
+        updated_since.map(|d| d.naive_utc())
+    )
+    .fetch_all(con)
+    .await?;
+
+    let mut res: Vec<Account> = Vec::new();
+
+    for row in rows {
+        res.push(Account {
+            id: row.id,
+            user_id: row.user_id,
+            name: row.name,
+            icon_name: row.icon_name,
+            account_type: row.account_type,
+            initial_balance: row.initial_balance,
+            balance: row.balance,
+        });
+    }
+
+    Ok(res)
+}
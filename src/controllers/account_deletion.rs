@@ -0,0 +1,156 @@
+use chrono::{DateTime, Utc};
+
+use crate::throttle::{login_email_key, reset_email_key};
+
+/// Schedules the user's account to be erased at `delete_at`.
+pub async fn schedule_user_deletion<'a, T>(
+    user_id: i32,
+    delete_at: DateTime<Utc>,
+    con: T,
+) -> Result<(), Box<dyn std::error::Error>>
+where
+    T: sqlx::Executor<'a, Database = sqlx::Postgres>,
+{
+    let _ = sqlx::query!(
+        r#"
+            UPDATE "user" SET deletion_scheduled_at = $1 WHERE id = $2
+        "#,
+        delete_at.naive_utc(),
+        user_id
+    )
+    .execute(con)
+    .await?;
+
+    Ok(())
+}
+
+/// Cancels the user's scheduled deletion, returning false when none was
+/// scheduled.
+pub async fn cancel_user_deletion<'a, T>(
+    user_id: i32,
+    con: T,
+) -> Result<bool, Box<dyn std::error::Error>>
+where
+    T: sqlx::Executor<'a, Database = sqlx::Postgres>,
+{
+    let res = sqlx::query!(
+        r#"
+            UPDATE "user" SET deletion_scheduled_at = NULL
+            WHERE id = $1 AND deletion_scheduled_at IS NOT NULL
+        "#,
+        user_id
+    )
+    .execute(con)
+    .await?;
+
+    Ok(res.rows_affected() > 0)
+}
+
+/// Returns (user_id, email) of the users whose grace period is over, locking
+/// them so a login can't cancel the deletion while it runs.
+pub async fn lock_users_due_for_deletion<'a, T>(
+    now: DateTime<Utc>,
+    con: T,
+) -> Result<Vec<(i32, String)>, Box<dyn std::error::Error>>
+where
+    T: sqlx::Executor<'a, Database = sqlx::Postgres>,
+{
+    let rows = sqlx::query!(
+        r#"
+            SELECT id, email
+            FROM "user"
+            WHERE deletion_scheduled_at <= $1
+            FOR UPDATE SKIP LOCKED
+        "#,
+        now.naive_utc()
+    )
+    .fetch_all(con)
+    .await?;
+
+    Ok(rows.into_iter().map(|r| (r.id, r.email)).collect())
+}
+
+/// Erases the user and all of their data. Tables are cleared in dependency
+/// order, as most foreign keys don't cascade, so it must run inside a
+/// transaction.
+pub async fn delete_user_data(
+    user_id: i32,
+    email: &str,
+    con: &mut sqlx::PgConnection,
+) -> Result<(), Box<dyn std::error::Error>> {
+    // Refresh token history goes along with the sessions
+    sqlx::query!("DELETE FROM sessions WHERE user_email = $1", email)
+        .execute(&mut *con)
+        .await?;
+    sqlx::query!("DELETE FROM reset_password WHERE user_email = $1", email)
+        .execute(&mut *con)
+        .await?;
+    sqlx::query!(
+        "DELETE FROM email_verification WHERE user_email = $1 OR new_email = $1",
+        email
+    )
+    .execute(&mut *con)
+    .await?;
+    // Attempts by IP aren't tied to the user, only the ones by email
+    sqlx::query!(
+        "DELETE FROM login_attempt WHERE key = $1 OR key = $2",
+        login_email_key(email),
+        reset_email_key(email)
+    )
+    .execute(&mut *con)
+    .await?;
+
+    sqlx::query!("DELETE FROM transfer WHERE user_id = $1", user_id)
+        .execute(&mut *con)
+        .await?;
+    // Installments reference their parent, but a single statement deletes both
+    sqlx::query!("DELETE FROM transaction WHERE user_id = $1", user_id)
+        .execute(&mut *con)
+        .await?;
+    sqlx::query!(
+        "DELETE FROM recurring_transaction WHERE user_id = $1",
+        user_id
+    )
+    .execute(&mut *con)
+    .await?;
+    sqlx::query!("DELETE FROM budget WHERE user_id = $1", user_id)
+        .execute(&mut *con)
+        .await?;
+
+    sqlx::query!(
+        r#"
+            DELETE FROM credit_card_bill
+            WHERE credit_card_id IN (SELECT id FROM credit_card WHERE user_id = $1)
+        "#,
+        user_id
+    )
+    .execute(&mut *con)
+    .await?;
+    sqlx::query!("DELETE FROM credit_card WHERE user_id = $1", user_id)
+        .execute(&mut *con)
+        .await?;
+    sqlx::query!("DELETE FROM account WHERE user_id = $1", user_id)
+        .execute(&mut *con)
+        .await?;
+
+    sqlx::query!(
+        r#"
+            DELETE FROM subcategory
+            WHERE category_id IN (SELECT id FROM category WHERE user_id = $1)
+        "#,
+        user_id
+    )
+    .execute(&mut *con)
+    .await?;
+    sqlx::query!("DELETE FROM category WHERE user_id = $1", user_id)
+        .execute(&mut *con)
+        .await?;
+
+    // Identities and data exports go along with the user, and the export job
+    // removes their archives
+    sqlx::query!(r#"DELETE FROM "user" WHERE id = $1"#, user_id)
+        .execute(&mut *con)
+        .await?;
+
+    Ok(())
+}
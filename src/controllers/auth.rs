@@ -1,24 +1,156 @@
-use crate::model::{user::AuthType, user::User};
+use crate::{jwks::JwksCache, model::user::User};
 use chrono::{TimeZone, Utc};
-use serde::{Deserialize, Serialize};
+use jsonwebtoken::{decode, decode_header, Algorithm, Validation};
+use serde::{de::DeserializeOwned, Deserialize, Deserializer, Serialize};
+
+const GOOGLE_ISSUERS: [&str; 2] = ["accounts.google.com", "https://accounts.google.com"];
+const APPLE_ISSUERS: [&str; 1] = ["https://appleid.apple.com"];
+/// Domain of the addresses Apple gives to users who hide their email.
+const APPLE_PRIVATE_RELAY_DOMAIN: &str = "privaterelay.appleid.com";
 
 #[derive(Deserialize, Serialize, Debug, Clone)]
 pub struct GoogleOauthUserInformation {
     pub aud: String,
+    #[serde(default)]
     pub azp: String,
     pub email: String,
     pub email_verified: bool,
     pub exp: i64,
+    #[serde(default)]
     pub family_name: String,
+    #[serde(default)]
     pub given_name: String,
     pub iat: i64,
     pub iss: String,
+    #[serde(default)]
     pub name: String,
+    #[serde(default)]
     pub nonce: String,
+    #[serde(default)]
     pub picture: String,
     pub sub: String,
 }
 
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct AppleUserInformation {
+    pub aud: String,
+    /// Missing when the user didn't share an email with the app.
+    #[serde(default)]
+    pub email: String,
+    #[serde(default, deserialize_with = "deserialize_bool_or_string")]
+    pub email_verified: bool,
+    #[serde(default, deserialize_with = "deserialize_bool_or_string")]
+    pub is_private_email: bool,
+    pub exp: i64,
+    pub iat: i64,
+    pub iss: String,
+    pub sub: String,
+}
+
+/// Whether the email is one of Apple's private relay addresses, which only
+/// deliver emails sent from domains registered with Apple.
+pub fn is_apple_private_relay_email(email: &str) -> bool {
+    email
+        .to_lowercase()
+        .ends_with(format!("@{}", APPLE_PRIVATE_RELAY_DOMAIN).as_str())
+}
+
+/// Apple sends boolean claims either as booleans or as "true"/"false".
+fn deserialize_bool_or_string<'de, D>(deserializer: D) -> Result<bool, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum BoolOrString {
+        Bool(bool),
+        String(String),
+    }
+
+    match BoolOrString::deserialize(deserializer)? {
+        BoolOrString::Bool(b) => Ok(b),
+        BoolOrString::String(s) => Ok(s == "true"),
+    }
+}
+
+/// Verifies an ID token against the provider's keys, checking its audience,
+/// issuer and expiration. Returns None when the token is invalid.
+async fn verify_id_token<C: DeserializeOwned>(
+    token: &str,
+    client_id: &str,
+    issuers: &[&str],
+    jwks: &JwksCache,
+) -> Result<Option<C>, Box<dyn std::error::Error>> {
+    let kid = match decode_header(token).ok().and_then(|h| h.kid) {
+        Some(k) => k,
+        None => return Ok(None),
+    };
+
+    let key = match jwks.get_key(kid.as_str()).await? {
+        Some(k) => k,
+        None => {
+            log::warn!("ID token signed with an unknown key: {}", kid);
+            return Ok(None);
+        }
+    };
+
+    let mut validation = Validation::new(Algorithm::RS256);
+    validation.set_audience(&[client_id]);
+    validation.set_issuer(issuers);
+
+    match decode::<C>(token, &key, &validation) {
+        Ok(t) => Ok(Some(t.claims)),
+        Err(e) => {
+            log::warn!("Could not verify the ID token: {}", e);
+            Ok(None)
+        }
+    }
+}
+
+/// Verifies the Google ID token against Google's keys, checking its
+/// audience, issuer, expiration and that its email is verified. Returns None
+/// when the token is invalid.
+pub async fn get_google_user_information(
+    token: &str,
+    client_id: &str,
+    jwks: &JwksCache,
+) -> Result<Option<GoogleOauthUserInformation>, Box<dyn std::error::Error>> {
+    let claims: GoogleOauthUserInformation =
+        match verify_id_token(token, client_id, &GOOGLE_ISSUERS, jwks).await? {
+            Some(c) => c,
+            None => return Ok(None),
+        };
+
+    if claims.email.is_empty() || !claims.email_verified {
+        log::warn!("Google ID token without a verified email");
+        return Ok(None);
+    }
+
+    Ok(Some(claims))
+}
+
+/// Verifies the Apple identity token against Apple's keys, the same way as
+/// Google's. The email is optional, as Apple may leave it out after the
+/// first sign in, but when present it has to be verified.
+pub async fn get_apple_user_information(
+    token: &str,
+    client_id: &str,
+    jwks: &JwksCache,
+) -> Result<Option<AppleUserInformation>, Box<dyn std::error::Error>> {
+    let claims: AppleUserInformation =
+        match verify_id_token(token, client_id, &APPLE_ISSUERS, jwks).await? {
+            Some(c) => c,
+            None => return Ok(None),
+        };
+
+    if !claims.email.is_empty() && !claims.email_verified {
+        log::warn!("Apple identity token with an unverified email");
+        return Ok(None);
+    }
+
+    Ok(Some(claims))
+}
+
 pub async fn get_user<'a, T>(email: &str, con: T) -> Result<Option<User>, sqlx::error::Error>
 where
     T: sqlx::Executor<'a, Database = sqlx::Postgres>,
@@ -27,9 +159,7 @@ where
         r#"SELECT 
             id, email, name,
             password, created_at,
-            auth_type as "auth_type!: AuthType", 
-            google_user_id, is_email_verified,
-            is_premium
+            is_email_verified, is_premium
            FROM "user" WHERE email = $1"#,
         email
     )
@@ -48,13 +178,37 @@ where
         name: res.name.clone(),
         password: res.password.clone(),
         created_at: Utc.from_utc_datetime(&res.created_at),
-        google_user_id: res.google_user_id.clone(),
-        auth_type: res.auth_type.clone(),
         is_email_verified: res.is_email_verified,
         is_premium: res.is_premium,
     }));
 }
 
+pub async fn get_user_by_id<'a, T>(user_id: i32, con: T) -> Result<Option<User>, sqlx::error::Error>
+where
+    T: sqlx::Executor<'a, Database = sqlx::Postgres>,
+{
+    let res = sqlx::query!(
+        r#"SELECT
+            id, email, name,
+            password, created_at,
+            is_email_verified, is_premium
+           FROM "user" WHERE id = $1"#,
+        user_id
+    )
+    .fetch_optional(con)
+    .await?;
+
+    Ok(res.map(|r| User {
+        id: r.id,
+        email: r.email,
+        name: r.name,
+        password: r.password,
+        created_at: Utc.from_utc_datetime(&r.created_at),
+        is_email_verified: r.is_email_verified,
+        is_premium: r.is_premium,
+    }))
+}
+
 pub async fn check_email_exists<'a, T>(email: &str, con: T) -> Result<bool, sqlx::error::Error>
 where
     T: sqlx::Executor<'a, Database = sqlx::Postgres>,
@@ -104,6 +258,27 @@ where
     Ok(())
 }
 
+pub async fn update_user_name<'a, T>(
+    user_id: i32,
+    name: &str,
+    con: T,
+) -> Result<(), Box<dyn std::error::Error>>
+where
+    T: sqlx::Executor<'a, Database = sqlx::Postgres>,
+{
+    let _ = sqlx::query!(
+        r#"
+        UPDATE "user" SET name = $1 WHERE id = $2;
+    "#,
+        name,
+        user_id
+    )
+    .execute(con)
+    .await?;
+
+    Ok(())
+}
+
 pub async fn create_user<'a, T>(con: T, usr: &User) -> Result<User, Box<dyn std::error::Error>>
 where
     T: sqlx::Executor<'a, Database = sqlx::Postgres>,
@@ -112,17 +287,14 @@ where
         r#"
             INSERT INTO "user"
                 (email, name, password, created_at,
-                 auth_type, google_user_id,
                  is_email_verified, is_premium)
-            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+            VALUES ($1, $2, $3, $4, $5, $6)
             RETURNING id;
         "#,
         usr.email,
         usr.name,
         usr.password,
         usr.created_at.naive_utc(),
-        usr.auth_type.clone() as AuthType,
-        usr.google_user_id,
         usr.is_email_verified,
         usr.is_premium
     )
@@ -135,11 +307,155 @@ where
         name: usr.name.clone(),
         password: usr.password.clone(),
         created_at: usr.created_at,
-        auth_type: usr.auth_type.clone(),
-        google_user_id: usr.google_user_id.clone(),
         is_email_verified: usr.is_email_verified,
         is_premium: usr.is_premium,
     };
 
     Ok(res)
 }
+
+pub async fn clear_password<'a, T>(user_id: i32, con: T) -> Result<(), Box<dyn std::error::Error>>
+where
+    T: sqlx::Executor<'a, Database = sqlx::Postgres>,
+{
+    let _ = sqlx::query!(
+        r#"
+        UPDATE "user" SET password = NULL WHERE id = $1;
+    "#,
+        user_id
+    )
+    .execute(con)
+    .await?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use actix_web::{web, App, HttpResponse, HttpServer};
+    use jsonwebtoken::{encode, EncodingKey, Header};
+    use openssl::{base64::encode_block, bn::BigNumRef, rsa::Rsa};
+    use serde_json::json;
+
+    fn base64_url(n: &BigNumRef) -> String {
+        encode_block(&n.to_vec())
+            .replace('+', "-")
+            .replace('/', "_")
+            .trim_end_matches('=')
+            .to_string()
+    }
+
+    fn sign(claims: &serde_json::Value, key: &EncodingKey) -> String {
+        let mut header = Header::new(Algorithm::RS256);
+        header.kid = Some(String::from("test-key"));
+        encode(&header, claims, key).unwrap()
+    }
+
+    #[actix_rt::test]
+    async fn test_get_google_user_information() {
+        let rsa = Rsa::generate(2048).unwrap();
+        let jwks = json!({
+            "keys": [{
+                "kty": "RSA",
+                "alg": "RS256",
+                "use": "sig",
+                "kid": "test-key",
+                "n": base64_url(rsa.n()),
+                "e": base64_url(rsa.e())
+            }]
+        });
+
+        // Stub of Google's JWKS endpoint
+        let server = HttpServer::new(move || {
+            let jwks = jwks.clone();
+            App::new().route(
+                "/certs",
+                web::get().to(move || {
+                    let jwks = jwks.clone();
+                    async move { HttpResponse::Ok().json(jwks) }
+                }),
+            )
+        })
+        .workers(1)
+        .bind(("127.0.0.1", 0))
+        .unwrap();
+        let port = server.addrs()[0].port();
+        actix_rt::spawn(server.run());
+
+        let cache = JwksCache::new(format!("http://127.0.0.1:{}/certs", port).as_str());
+        let key = EncodingKey::from_rsa_pem(&rsa.private_key_to_pem().unwrap()).unwrap();
+        let now = Utc::now().timestamp();
+        let mut claims = json!({
+            "aud": "client-id",
+            "email": "user@finly.test",
+            "email_verified": true,
+            "exp": now + 600,
+            "iat": now,
+            "iss": "https://accounts.google.com",
+            "name": "User",
+            "sub": "1234"
+        });
+
+        let info = get_google_user_information(&sign(&claims, &key), "client-id", &cache)
+            .await
+            .unwrap();
+        assert_eq!(info.map(|i| i.sub), Some(String::from("1234")));
+
+        let info = get_google_user_information(&sign(&claims, &key), "other-client", &cache)
+            .await
+            .unwrap();
+        assert!(info.is_none());
+
+        claims["email_verified"] = json!(false);
+        let info = get_google_user_information(&sign(&claims, &key), "client-id", &cache)
+            .await
+            .unwrap();
+        assert!(info.is_none());
+
+        claims["email_verified"] = json!(true);
+        claims["iss"] = json!("https://accounts.example.com");
+        let info = get_google_user_information(&sign(&claims, &key), "client-id", &cache)
+            .await
+            .unwrap();
+        assert!(info.is_none());
+
+        claims["iss"] = json!("accounts.google.com");
+        claims["exp"] = json!(now - 600);
+        let info = get_google_user_information(&sign(&claims, &key), "client-id", &cache)
+            .await
+            .unwrap();
+        assert!(info.is_none());
+    }
+
+    #[test]
+    fn test_apple_user_information_claims() {
+        let info: AppleUserInformation = serde_json::from_value(json!({
+            "aud": "client-id",
+            "email": "abc123@privaterelay.appleid.com",
+            "email_verified": "true",
+            "is_private_email": "true",
+            "exp": 0,
+            "iat": 0,
+            "iss": "https://appleid.apple.com",
+            "sub": "001234.abc"
+        }))
+        .unwrap();
+        assert!(info.email_verified);
+        assert!(info.is_private_email);
+        assert!(is_apple_private_relay_email(info.email.as_str()));
+
+        let info: AppleUserInformation = serde_json::from_value(json!({
+            "aud": "client-id",
+            "exp": 0,
+            "iat": 0,
+            "iss": "https://appleid.apple.com",
+            "sub": "001234.abc"
+        }))
+        .unwrap();
+        assert!(info.email.is_empty());
+        assert!(!info.email_verified);
+        assert!(!is_apple_private_relay_email("user@finly.test"));
+    }
+}
@@ -0,0 +1,179 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use uuid::Uuid;
+
+use crate::model::budget::Budget;
+
+pub async fn upsert_budget<'a, T>(budget: &Budget, con: T) -> Result<(), Box<dyn std::error::Error>>
+where
+    T: sqlx::Executor<'a, Database = sqlx::Postgres>,
+{
+    let _ = sqlx::query!(
+        r#"
+            INSERT INTO budget
+                (id, user_id, category_id, subcategory_id,
+                amount, rollover, start_month)
+            VALUES
+                ($1, $2, $3, $4, $5, $6, $7)
+            ON CONFLICT(id, user_id)
+            DO UPDATE
+                SET category_id = $3,
+                    subcategory_id = $4,
+                    amount = $5,
+                    rollover = $6,
+                    start_month = $7,
+                    updated_at = (now() at time zone 'utc')
+        "#,
+        budget.id,
+        budget.user_id,
+        budget.category_id,
+        budget.subcategory_id,
+        budget.amount,
+        budget.rollover,
+        budget.start_month
+    )
+    .execute(con)
+    .await?;
+
+    Ok(())
+}
+
+pub async fn check_budget_references<'a, T>(
+    budget: &Budget,
+    con: T,
+) -> Result<bool, Box<dyn std::error::Error>>
+where
+    T: sqlx::Executor<'a, Database = sqlx::Postgres>,
+{
+    let res = sqlx::query!(
+        r#"
+            SELECT
+                ($1::uuid IS NULL OR EXISTS(
+                    SELECT 1 FROM category
+                    WHERE id = $1 AND user_id = $3 AND is_active = true))
+                AND ($2::uuid IS NULL OR EXISTS(
+                    SELECT 1 FROM subcategory s
+                    INNER JOIN category c ON c.id = s.category_id
+                    WHERE s.id = $2 AND c.user_id = $3 AND s.is_active = true))
+                AS "is_valid!"
+        "#,
+        budget.category_id,
+        budget.subcategory_id,
+        budget.user_id
+    )
+    .fetch_one(con)
+    .await?;
+
+    Ok(res.is_valid)
+}
+
+pub async fn delete_budget<'a, T>(
+    budget_id: &Uuid,
+    user_id: i32,
+    con: T,
+) -> Result<(), Box<dyn std::error::Error>>
+where
+    T: sqlx::Executor<'a, Database = sqlx::Postgres>,
+{
+    let _ = sqlx::query!(
+        r#"
+            UPDATE budget
+                SET is_active = false,
+                    updated_at = (now() at time zone 'utc')
+                WHERE id = $1 AND user_id = $2 AND is_active = true
+        "#,
+        budget_id,
+        user_id
+    )
+    .execute(con)
+    .await?;
+
+    Ok(())
+}
+
+pub async fn get_budgets_by_user_id<'a, T>(
+    user_id: i32,
+    con: T,
+) -> Result<Vec<Budget>, Box<dyn std::error::Error>>
+where
+    T: sqlx::Executor<'a, Database = sqlx::Postgres>,
+{
+    let rows = sqlx::query!(
+        r#"
+            SELECT
+                id,
+                user_id,
+                category_id,
+                subcategory_id,
+                amount,
+                rollover,
+                start_month
+            FROM budget
+            WHERE user_id = $1 AND is_active = true
+        "#,
+        user_id
+    )
+    .fetch_all(con)
+    .await?;
+
+    let mut res: Vec<Budget> = Vec::new();
+
+    for row in rows {
+        res.push(Budget {
+            id: row.id,
+            user_id: row.user_id,
+            category_id: row.category_id,
+            subcategory_id: row.subcategory_id,
+            amount: row.amount,
+            rollover: row.rollover,
+            start_month: row.start_month,
+        });
+    }
+
+    Ok(res)
+}
+
+/// Returns (budget_id, month, spent) for every month between `from` and `to`
+/// with expenses in the budget's category. A category budget also counts
+/// transactions filed only under one of its subcategories. Months are taken
+/// in the user's offset, given in seconds east of UTC. Installment parents are
+/// skipped, as their installments are counted instead.
+pub async fn get_budget_spending<'a, T>(
+    user_id: i32,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+    utc_offset: i32,
+    con: T,
+) -> Result<Vec<(Uuid, NaiveDate, i64)>, Box<dyn std::error::Error>>
+where
+    T: sqlx::Executor<'a, Database = sqlx::Postgres>,
+{
+    let rows = sqlx::query!(
+        r#"
+            SELECT
+                b.id,
+                date_trunc('month', t.transaction_date + $4::int * interval '1 second')::date
+                    AS "month!",
+                SUM(t.amount)::BIGINT AS "spent!"
+            FROM transaction t
+            LEFT JOIN subcategory s ON s.id = t.subcategory_id
+            INNER JOIN budget b
+                ON b.user_id = t.user_id
+                AND ((b.category_id IS NOT NULL
+                        AND b.category_id IN (t.category_id, s.category_id))
+                    OR (b.subcategory_id IS NOT NULL AND t.subcategory_id = b.subcategory_id))
+            WHERE b.user_id = $1 AND b.is_active = true
+                AND t.is_active = true AND t.kind = 'EXPENSE'
+                AND (t.installment_count = 1 OR t.parent_transaction_id IS NOT NULL)
+                AND t.transaction_date >= $2 AND t.transaction_date < $3
+            GROUP BY 1, 2
+        "#,
+        user_id,
+        from.naive_utc(),
+        to.naive_utc(),
+        utc_offset
+    )
+    .fetch_all(con)
+    .await?;
+
+    Ok(rows.into_iter().map(|r| (r.id, r.month, r.spent)).collect())
+}
@@ -1,18 +1,21 @@
 use std::collections::HashMap;
 
-use chrono::Utc;
+use chrono::{DateTime, Utc};
 use uuid::Uuid;
 
 use crate::model::{category::Category, subcategory::Subcategory};
 
+/// Returns false, leaving the category untouched, when `expected_version`
+/// is given and the stored category is at a different version.
 pub async fn upsert_category<'a, T>(
     category: &Category,
+    expected_version: Option<i32>,
     con: T,
-) -> Result<(), Box<dyn std::error::Error>>
+) -> Result<bool, Box<dyn std::error::Error>>
 where
     T: sqlx::Executor<'a, Database = sqlx::Postgres>,
 {
-    let _ = sqlx::query!(
+    let res = sqlx::query!(
         r#"
         INSERT INTO category
             (id, user_id, name, color, icon_name,
@@ -26,18 +29,21 @@ where
             SET name = $3,
                 color = $4,
                 icon_name = $5,
+                version = category.version + 1,
                 updated_at = (now() at time zone 'utc')
+            WHERE $6::int IS NULL OR category.version = $6
         "#,
         category.id,
         category.user_id,
         category.name,
         category.color,
         category.icon_name,
+        expected_version,
     )
     .execute(con)
     .await?;
 
-    Ok(())
+    Ok(res.rows_affected() > 0)
 }
 
 pub async fn delete_category<'a, T>(
@@ -67,14 +73,17 @@ where
     Ok(())
 }
 
+/// Returns false, leaving the subcategory untouched, when `expected_version`
+/// is given and the stored subcategory is at a different version.
 pub async fn upsert_subcategory<'a, T>(
     subcategory: &Subcategory,
+    expected_version: Option<i32>,
     con: T,
-) -> Result<(), Box<dyn std::error::Error>>
+) -> Result<bool, Box<dyn std::error::Error>>
 where
     T: sqlx::Executor<'a, Database = sqlx::Postgres>,
 {
-    let _ = sqlx::query!(
+    let res = sqlx::query!(
         r#"
         INSERT INTO subcategory
             (id, category_id, name, color, icon_name,
@@ -88,18 +97,21 @@ where
             SET name = $3,
                 color = $4,
                 icon_name = $5,
+                version = subcategory.version + 1,
                 updated_at = (now() at time zone 'utc')
+            WHERE $6::int IS NULL OR subcategory.version = $6
     "#,
         subcategory.id,
         subcategory.category_id,
         subcategory.name,
         subcategory.color,
         subcategory.icon_name,
+        expected_version,
     )
     .execute(con)
     .await?;
 
-    Ok(())
+    Ok(res.rows_affected() > 0)
 }
 
 pub async fn delete_subcategory<'a, T>(
@@ -126,8 +138,46 @@ where
     Ok(())
 }
 
+pub async fn get_category_by_id<'a, T>(
+    category_id: &Uuid,
+    user_id: i32,
+    con: T,
+) -> Result<Option<Category>, Box<dyn std::error::Error>>
+where
+    T: sqlx::Executor<'a, Database = sqlx::Postgres>,
+{
+    let row = sqlx::query!(
+        r#"
+            SELECT
+                id,
+                user_id,
+                name,
+                color,
+                icon_name,
+                version
+            FROM category
+            WHERE id = $1 AND user_id = $2 AND is_active = true
+        "#,
+        category_id,
+        user_id
+    )
+    .fetch_optional(con)
+    .await?;
+
+    Ok(row.map(|r| Category {
+        id: r.id,
+        user_id: r.user_id,
+        name: r.name,
+        color: r.color,
+        icon_name: r.icon_name,
+        version: r.version,
+        subcategories: Vec::new(),
+    }))
+}
+
 pub async fn get_categories_by_user_id<'a, T>(
     user_id: i32,
+    updated_since: Option<DateTime<Utc>>,
     con: T,
 ) -> Result<Vec<Category>, Box<dyn std::error::Error>>
 where
@@ -140,11 +190,14 @@ where
                 user_id,
                 name,
                 color,
-                icon_name
+                icon_name,
+                version
             FROM category
             WHERE user_id = $1 AND is_active = true
+                AND ($2::timestamp IS NULL OR updated_at > $2)
         "#,
-        user_id
+        user_id,
+        updated_since.map(|d| d.naive_utc())
     )
     .fetch_all(con)
     .await?;
@@ -158,6 +211,7 @@ where
             name: row.name,
             color: row.color,
             icon_name: row.icon_name,
+            version: row.version,
             subcategories: Vec::new(),
         };
         res.push(cat);
@@ -166,8 +220,47 @@ where
     Ok(res)
 }
 
+pub async fn get_subcategory_by_id<'a, T>(
+    subcategory_id: &Uuid,
+    user_id: i32,
+    con: T,
+) -> Result<Option<Subcategory>, Box<dyn std::error::Error>>
+where
+    T: sqlx::Executor<'a, Database = sqlx::Postgres>,
+{
+    let row = sqlx::query!(
+        r#"
+            SELECT
+                s.id,
+                s.category_id,
+                s.name,
+                s.color,
+                s.icon_name,
+                s.version
+            FROM subcategory s
+            INNER JOIN category c
+                ON c.id = s.category_id
+            WHERE s.id = $1 AND c.user_id = $2 AND s.is_active = true
+        "#,
+        subcategory_id,
+        user_id
+    )
+    .fetch_optional(con)
+    .await?;
+
+    Ok(row.map(|r| Subcategory {
+        id: r.id,
+        category_id: r.category_id,
+        name: r.name,
+        color: r.color,
+        icon_name: r.icon_name,
+        version: r.version,
+    }))
+}
+
 pub async fn get_subcategories_by_user_id<'a, T>(
     user_id: i32,
+    updated_since: Option<DateTime<Utc>>,
     con: T,
 ) -> Result<HashMap<String, Vec<Subcategory>>, Box<dyn std::error::Error>>
 where
@@ -180,15 +273,18 @@ where
                 s.category_id,
                 s.name,
                 s.color,
-                s.icon_name
+                s.icon_name,
+                s.version
             FROM subcategory s
             INNER JOIN category c
                 ON c.id = s.category_id
             WHERE
                 c.user_id = $1 AND s.is_active = true
                 AND c.is_active = true
+                AND ($2::timestamp IS NULL OR s.updated_at > $2)
         "#,
-        user_id
+        user_id,
+        updated_since.map(|d| d.naive_utc())
     )
     .fetch_all(con)
     .await?;
@@ -202,6 +298,7 @@ where
             name: row.name,
             color: row.color,
             icon_name: row.icon_name,
+            version: row.version,
         };
 
         res.entry(row.category_id.to_string())
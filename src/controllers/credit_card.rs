@@ -1,40 +1,51 @@
-use chrono::{FixedOffset, NaiveDate};
+use chrono::{DateTime, FixedOffset, NaiveDate, Utc};
 use uuid::Uuid;
 
 use crate::model::{credit_card::CreditCard, credit_card_bill::CreditCardBill};
 
+/// Returns false, leaving the card untouched, when `expected_version` is
+/// given and the stored card is at a different version. Without
+/// `update_due_day` an existing card keeps its due day.
 pub async fn upsert_credit_card<'a, T>(
     credit_card: &CreditCard,
+    update_due_day: bool,
+    expected_version: Option<i32>,
     con: T,
-) -> Result<(), Box<dyn std::error::Error>>
+) -> Result<bool, Box<dyn std::error::Error>>
 where
     T: sqlx::Executor<'a, Database = sqlx::Postgres>,
 {
-    let _ = sqlx::query!(
+    let res = sqlx::query!(
         r#"
             INSERT INTO credit_card
                 (id, user_id, name, icon_name,
-                limit_value, closing_day)
+                limit_value, closing_day, due_day)
             VALUES
-                ($1, $2, $3, $4, $5, $6)
+                ($1, $2, $3, $4, $5, $6, $7)
             ON CONFLICT(id, user_id)
             DO UPDATE
                 SET name = $3,
                     icon_name = $4,
                     limit_value = $5,
+                    due_day = CASE WHEN $9 THEN $7 ELSE credit_card.due_day END,
+                    version = credit_card.version + 1,
                     updated_at = (now() at time zone 'utc')
+                WHERE $8::int IS NULL OR credit_card.version = $8
         "#,
         credit_card.id,
         credit_card.user_id,
         credit_card.name,
         credit_card.icon_name,
         credit_card.limit_value,
-        credit_card.closing_day
+        credit_card.closing_day,
+        credit_card.due_day,
+        expected_version,
+        update_due_day
     )
     .execute(con)
     .await?;
 
-    Ok(())
+    Ok(res.rows_affected() > 0)
 }
 
 pub async fn delete_credit_card<'a, T>(
@@ -71,15 +82,19 @@ where
     let res = sqlx::query!(
         r#"
             SELECT
-                id,
+                credit_card.id,
                 user_id,
                 name,
                 icon_name,
                 limit_value,
-                closing_day
+                closing_day,
+                due_day,
+                version,
+                COALESCE(u.used_limit, 0)::BIGINT AS "used_limit!"
             FROM credit_card
+            LEFT JOIN credit_card_used_limit u ON u.credit_card_id = credit_card.id
             WHERE user_id = $1 and is_active = true and 
-            id = $2 LIMIT 1;
+            credit_card.id = $2 LIMIT 1;
         "#,
         user_id,
         credit_card_id
@@ -95,6 +110,9 @@ where
             icon_name: r.icon_name,
             limit_value: r.limit_value,
             closing_day: r.closing_day,
+            due_day: r.due_day,
+            used_limit: r.used_limit,
+            version: r.version,
         }),
         None => None,
     };
@@ -102,8 +120,11 @@ where
     Ok(card)
 }
 
+/// With `updated_since`, only returns the cards changed after it, including
+/// the ones whose used limit moved because of their transactions or bills.
 pub async fn get_credit_cards_by_user_id<'a, T>(
     user_id: i32,
+    updated_since: Option<DateTime<Utc>>,
     con: T,
 ) -> Result<Vec<CreditCard>, Box<dyn std::error::Error>>
 where
@@ -112,16 +133,29 @@ where
     let rows = sqlx::query!(
         r#"
             SELECT
-                id,
+                credit_card.id,
                 user_id,
                 name,
                 icon_name,
                 limit_value,
-                closing_day
+                closing_day,
+                due_day,
+                version,
+                COALESCE(u.used_limit, 0)::BIGINT AS "used_limit!"
             FROM credit_card
+            LEFT JOIN credit_card_used_limit u ON u.credit_card_id = credit_card.id
             WHERE user_id = $1 AND is_active = true
+                AND ($2::timestamp IS NULL
+                    OR updated_at > $2
+                    OR EXISTS(
+                        SELECT 1 FROM transaction t
+                        WHERE t.credit_card_id = credit_card.id AND t.updated_at > $2)
+                    OR EXISTS(
+                        SELECT 1 FROM credit_card_bill b
+                        WHERE b.credit_card_id = credit_card.id AND b.updated_at > $2))
         "#,
-        user_id
+        user_id,
+        updated_since.map(|d| d.naive_utc())
     )
     .fetch_all(con)
     .await?;
@@ -136,13 +170,16 @@ where
             icon_name: row.icon_name.clone(),
             limit_value: row.limit_value,
             closing_day: row.closing_day,
+            due_day: row.due_day,
+            used_limit: row.used_limit,
+            version: row.version,
         });
     }
 
     Ok(res)
 }
 
-pub async fn create_bill_of_date<'a, T>(
+pub async fn get_or_create_bill_of_date<'a, T>(
     credit_card_id: &Uuid,
     credit_card_closing_day: i16,
     dt: NaiveDate,
@@ -154,22 +191,36 @@ where
 {
     let bill = CreditCardBill::of_date(credit_card_id, credit_card_closing_day, dt, offset);
 
-    let _ = sqlx::query!(
+    // The no-op update makes RETURNING yield the existing bill when another
+    // request already created the one for this billing window.
+    let row = sqlx::query!(
         r#"
         INSERT INTO credit_card_bill
             (id, credit_card_id, start_at, end_at)
         VALUES
-            ($1, $2, $3, $4);
+            ($1, $2, $3, $4)
+        ON CONFLICT(credit_card_id, start_at)
+        DO UPDATE
+            SET credit_card_id = EXCLUDED.credit_card_id
+        RETURNING id, credit_card_id, start_at, end_at, paid_amount, paid_at;
     "#,
         bill.id,
         credit_card_id,
         bill.start_at.naive_utc(),
         bill.end_at.naive_utc()
     )
-    .execute(con)
+    .fetch_one(con)
     .await?;
 
-    Ok(bill)
+    Ok(CreditCardBill {
+        id: row.id,
+        credit_card_id: row.credit_card_id,
+        start_at: row.start_at.and_utc(),
+        end_at: row.end_at.and_utc(),
+        total_amount: 0,
+        paid_amount: row.paid_amount,
+        paid_at: row.paid_at.map(|d| d.and_utc()),
+    })
 }
 
 pub async fn get_credit_card_bills<'a, T>(
@@ -186,12 +237,21 @@ where
             b.id,
             b.credit_card_id,
             b.start_at,
-            b.end_at
+            b.end_at,
+            b.paid_amount,
+            b.paid_at,
+            COALESCE(SUM(
+                CASE WHEN t.kind = 'INCOME' THEN -t.amount ELSE t.amount END
+            ), 0)::BIGINT AS "total_amount!"
         FROM credit_card_bill b
         INNER JOIN credit_card c ON
             c.id = b.credit_card_id 
+        LEFT JOIN transaction t ON
+            t.credit_card_bill_id = b.id AND t.is_active = true
         WHERE b.credit_card_id = $1 AND c.user_id = $2
             AND c.is_active = true
+        GROUP BY b.id
+        ORDER BY b.start_at
     "#,
         credit_card_id,
         user_id
@@ -205,8 +265,172 @@ where
             credit_card_id: row.credit_card_id,
             start_at: row.start_at.and_utc(),
             end_at: row.end_at.and_utc(),
+            total_amount: row.total_amount,
+            paid_amount: row.paid_amount,
+            paid_at: row.paid_at.map(|d| d.and_utc()),
         });
     }
 
     Ok(res)
 }
+
+/// Returns the bills of all the user's cards along with the card's due day.
+/// With `updated_since`, only returns the bills changed after it, including
+/// the ones whose total moved because of their transactions.
+pub async fn get_credit_card_bills_by_user_id<'a, T>(
+    user_id: i32,
+    updated_since: Option<DateTime<Utc>>,
+    con: T,
+) -> Result<Vec<(CreditCardBill, i16)>, Box<dyn std::error::Error>>
+where
+    T: sqlx::Executor<'a, Database = sqlx::Postgres>,
+{
+    let rows = sqlx::query!(
+        r#"
+        SELECT
+            b.id,
+            b.credit_card_id,
+            b.start_at,
+            b.end_at,
+            b.paid_amount,
+            b.paid_at,
+            c.due_day,
+            COALESCE(SUM(
+                CASE WHEN t.kind = 'INCOME' THEN -t.amount ELSE t.amount END
+            ), 0)::BIGINT AS "total_amount!"
+        FROM credit_card_bill b
+        INNER JOIN credit_card c ON
+            c.id = b.credit_card_id
+        LEFT JOIN transaction t ON
+            t.credit_card_bill_id = b.id AND t.is_active = true
+        WHERE c.user_id = $1 AND c.is_active = true
+            AND ($2::timestamp IS NULL
+                OR b.updated_at > $2
+                OR EXISTS(
+                    SELECT 1 FROM transaction t2
+                    WHERE t2.credit_card_bill_id = b.id AND t2.updated_at > $2))
+        GROUP BY b.id, c.due_day
+        ORDER BY b.start_at
+    "#,
+        user_id,
+        updated_since.map(|d| d.naive_utc())
+    )
+    .fetch_all(con)
+    .await?;
+
+    let mut res: Vec<(CreditCardBill, i16)> = Vec::new();
+    for row in rows {
+        let bill = CreditCardBill {
+            id: row.id,
+            credit_card_id: row.credit_card_id,
+            start_at: row.start_at.and_utc(),
+            end_at: row.end_at.and_utc(),
+            total_amount: row.total_amount,
+            paid_amount: row.paid_amount,
+            paid_at: row.paid_at.map(|d| d.and_utc()),
+        };
+        res.push((bill, row.due_day));
+    }
+
+    Ok(res)
+}
+
+/// Adds `paid_amount` to what was already paid on the bill. The bill is only
+/// marked as paid once the payments cover its total, so a short payment
+/// leaves it open. Returns false when the bill is not found or was already
+/// paid in full.
+pub async fn pay_credit_card_bill<'a, T>(
+    credit_card_bill_id: &Uuid,
+    user_id: i32,
+    paid_amount: i64,
+    paid_at: DateTime<Utc>,
+    con: T,
+) -> Result<bool, Box<dyn std::error::Error>>
+where
+    T: sqlx::Executor<'a, Database = sqlx::Postgres>,
+{
+    let res = sqlx::query!(
+        r#"
+        UPDATE credit_card_bill b
+            SET paid_amount = COALESCE(b.paid_amount, 0) + $3,
+                paid_at = CASE
+                    WHEN COALESCE(b.paid_amount, 0) + $3 >= (
+                        SELECT COALESCE(SUM(
+                            CASE WHEN t.kind = 'INCOME' THEN -t.amount ELSE t.amount END
+                        ), 0)
+                        FROM transaction t
+                        WHERE t.credit_card_bill_id = b.id AND t.is_active = true)
+                    THEN $4::timestamp
+                    ELSE NULL
+                END,
+                updated_at = (now() at time zone 'utc')
+        FROM credit_card c
+        WHERE c.id = b.credit_card_id AND b.id = $1
+            AND c.user_id = $2 AND c.is_active = true
+            AND b.paid_at IS NULL
+    "#,
+        credit_card_bill_id,
+        user_id,
+        paid_amount,
+        paid_at.naive_utc()
+    )
+    .execute(con)
+    .await?;
+
+    Ok(res.rows_affected() > 0)
+}
+
+pub async fn check_credit_card_bill_exists<'a, T>(
+    credit_card_bill_id: &Uuid,
+    user_id: i32,
+    con: T,
+) -> Result<bool, Box<dyn std::error::Error>>
+where
+    T: sqlx::Executor<'a, Database = sqlx::Postgres>,
+{
+    let row = sqlx::query!(
+        r#"
+        SELECT b.id
+        FROM credit_card_bill b
+        INNER JOIN credit_card c ON c.id = b.credit_card_id
+        WHERE b.id = $1 AND c.user_id = $2 AND c.is_active = true
+    "#,
+        credit_card_bill_id,
+        user_id
+    )
+    .fetch_optional(con)
+    .await?;
+
+    Ok(row.is_some())
+}
+
+/// Takes back a payment of `amount` from the bill, which is no longer paid in
+/// full afterwards.
+pub async fn reopen_credit_card_bill<'a, T>(
+    credit_card_bill_id: &Uuid,
+    amount: i64,
+    user_id: i32,
+    con: T,
+) -> Result<(), Box<dyn std::error::Error>>
+where
+    T: sqlx::Executor<'a, Database = sqlx::Postgres>,
+{
+    let _ = sqlx::query!(
+        r#"
+        UPDATE credit_card_bill b
+            SET paid_amount = NULLIF(GREATEST(COALESCE(b.paid_amount, 0) - $2, 0), 0),
+                paid_at = NULL,
+                updated_at = (now() at time zone 'utc')
+        FROM credit_card c
+        WHERE c.id = b.credit_card_id AND b.id = $1
+            AND c.user_id = $3
+    "#,
+        credit_card_bill_id,
+        amount,
+        user_id
+    )
+    .execute(con)
+    .await?;
+
+    Ok(())
+}
@@ -0,0 +1,352 @@
+use chrono::{DateTime, TimeZone, Utc};
+use serde_json::Value;
+use uuid::Uuid;
+
+use crate::model::data_export::{DataExport, DataExportStatus};
+
+pub async fn create_data_export<'a, T>(
+    export: &DataExport,
+    con: T,
+) -> Result<(), Box<dyn std::error::Error>>
+where
+    T: sqlx::Executor<'a, Database = sqlx::Postgres>,
+{
+    let _ = sqlx::query!(
+        r#"
+            INSERT INTO data_export (id, user_id, status, requested_at)
+            VALUES ($1, $2, $3, $4)
+        "#,
+        export.id,
+        export.user_id,
+        export.status.clone() as DataExportStatus,
+        export.requested_at.naive_utc()
+    )
+    .execute(con)
+    .await?;
+
+    Ok(())
+}
+
+pub async fn get_last_data_export<'a, T>(
+    user_id: i32,
+    con: T,
+) -> Result<Option<DataExport>, Box<dyn std::error::Error>>
+where
+    T: sqlx::Executor<'a, Database = sqlx::Postgres>,
+{
+    let row = sqlx::query!(
+        r#"
+            SELECT
+                id,
+                user_id,
+                status AS "status!: DataExportStatus",
+                requested_at,
+                completed_at
+            FROM data_export
+            WHERE user_id = $1
+            ORDER BY requested_at DESC
+            LIMIT 1
+        "#,
+        user_id
+    )
+    .fetch_optional(con)
+    .await?;
+
+    Ok(row.map(|r| DataExport {
+        id: r.id,
+        user_id: r.user_id,
+        status: r.status,
+        requested_at: Utc.from_utc_datetime(&r.requested_at),
+        completed_at: r.completed_at.map(|c| Utc.from_utc_datetime(&c)),
+    }))
+}
+
+pub async fn get_data_export_by_id<'a, T>(
+    id: &Uuid,
+    con: T,
+) -> Result<Option<DataExport>, Box<dyn std::error::Error>>
+where
+    T: sqlx::Executor<'a, Database = sqlx::Postgres>,
+{
+    let row = sqlx::query!(
+        r#"
+            SELECT
+                id,
+                user_id,
+                status AS "status!: DataExportStatus",
+                requested_at,
+                completed_at
+            FROM data_export
+            WHERE id = $1
+        "#,
+        id
+    )
+    .fetch_optional(con)
+    .await?;
+
+    Ok(row.map(|r| DataExport {
+        id: r.id,
+        user_id: r.user_id,
+        status: r.status,
+        requested_at: Utc.from_utc_datetime(&r.requested_at),
+        completed_at: r.completed_at.map(|c| Utc.from_utc_datetime(&c)),
+    }))
+}
+
+/// Returns (export_id, user_id) of the exports waiting to be built, locking
+/// them so concurrent runs don't build the same export twice.
+pub async fn lock_pending_data_exports<'a, T>(
+    con: T,
+) -> Result<Vec<(Uuid, i32)>, Box<dyn std::error::Error>>
+where
+    T: sqlx::Executor<'a, Database = sqlx::Postgres>,
+{
+    let rows = sqlx::query!(
+        r#"
+            SELECT id, user_id
+            FROM data_export
+            WHERE status = 'PENDING'
+            ORDER BY requested_at
+            FOR UPDATE SKIP LOCKED
+        "#
+    )
+    .fetch_all(con)
+    .await?;
+
+    Ok(rows.into_iter().map(|r| (r.id, r.user_id)).collect())
+}
+
+pub async fn complete_data_export<'a, T>(
+    id: &Uuid,
+    status: DataExportStatus,
+    con: T,
+) -> Result<(), Box<dyn std::error::Error>>
+where
+    T: sqlx::Executor<'a, Database = sqlx::Postgres>,
+{
+    let _ = sqlx::query!(
+        r#"
+            UPDATE data_export
+                SET status = $2,
+                    completed_at = (now() at time zone 'utc')
+                WHERE id = $1
+        "#,
+        id,
+        status as DataExportStatus
+    )
+    .execute(con)
+    .await?;
+
+    Ok(())
+}
+
+/// Deletes the exports completed before `before` and returns the ids of the
+/// ones still available, so the job can remove every other archive.
+pub async fn delete_expired_data_exports(
+    before: DateTime<Utc>,
+    con: &mut sqlx::PgConnection,
+) -> Result<Vec<Uuid>, Box<dyn std::error::Error>> {
+    let _ = sqlx::query!(
+        r#"
+            DELETE FROM data_export WHERE completed_at < $1
+        "#,
+        before.naive_utc()
+    )
+    .execute(&mut *con)
+    .await?;
+
+    let rows = sqlx::query!(
+        r#"
+            SELECT id FROM data_export WHERE status = 'READY'
+        "#
+    )
+    .fetch_all(&mut *con)
+    .await?;
+
+    Ok(rows.into_iter().map(|r| r.id).collect())
+}
+
+/// Returns every record held about the user, grouped by the archive file
+/// they go into. Secrets like password hashes and tokens are left out. New
+/// entities owned by the user must be added here as well.
+pub async fn get_user_data(
+    user_id: i32,
+    con: &mut sqlx::PgConnection,
+) -> Result<Vec<(&'static str, Vec<Value>)>, Box<dyn std::error::Error>> {
+    let mut res: Vec<(&'static str, Vec<Value>)> = Vec::new();
+
+    let profile = sqlx::query_scalar!(
+        r#"SELECT to_jsonb(u) - 'password' AS "row!" FROM "user" u WHERE id = $1"#,
+        user_id
+    )
+    .fetch_all(&mut *con)
+    .await?;
+    let email = match profile.first().and_then(|p| p.get("email")) {
+        Some(Value::String(e)) => e.clone(),
+        _ => return Err("user not found".into()),
+    };
+    res.push(("profile", profile));
+
+    res.push((
+        "identities",
+        sqlx::query_scalar!(
+            r#"SELECT to_jsonb(i) AS "row!" FROM identity i WHERE user_id = $1"#,
+            user_id
+        )
+        .fetch_all(&mut *con)
+        .await?,
+    ));
+    res.push((
+        "sessions",
+        sqlx::query_scalar!(
+            r#"
+                SELECT to_jsonb(s) - 'refresh_token' - 'current_access_token' AS "row!"
+                FROM sessions s WHERE user_email = $1
+            "#,
+            email
+        )
+        .fetch_all(&mut *con)
+        .await?,
+    ));
+    res.push((
+        "email_verifications",
+        sqlx::query_scalar!(
+            r#"
+                SELECT to_jsonb(e) - 'id' AS "row!"
+                FROM email_verification e WHERE user_email = $1
+            "#,
+            email
+        )
+        .fetch_all(&mut *con)
+        .await?,
+    ));
+    res.push((
+        "password_resets",
+        sqlx::query_scalar!(
+            r#"
+                SELECT to_jsonb(r) - 'id' AS "row!"
+                FROM reset_password r WHERE user_email = $1
+            "#,
+            email
+        )
+        .fetch_all(&mut *con)
+        .await?,
+    ));
+    res.push((
+        "two_factor",
+        sqlx::query_scalar!(
+            r#"
+                SELECT to_jsonb(t) - 'secret' - 'last_used_step' AS "row!"
+                FROM totp t WHERE user_id = $1
+            "#,
+            user_id
+        )
+        .fetch_all(&mut *con)
+        .await?,
+    ));
+    res.push((
+        "recovery_codes",
+        sqlx::query_scalar!(
+            r#"
+                SELECT to_jsonb(r) - 'id' - 'code_hash' AS "row!"
+                FROM recovery_code r WHERE user_id = $1
+            "#,
+            user_id
+        )
+        .fetch_all(&mut *con)
+        .await?,
+    ));
+    res.push((
+        "categories",
+        sqlx::query_scalar!(
+            r#"SELECT to_jsonb(c) AS "row!" FROM category c WHERE user_id = $1"#,
+            user_id
+        )
+        .fetch_all(&mut *con)
+        .await?,
+    ));
+    res.push((
+        "subcategories",
+        sqlx::query_scalar!(
+            r#"
+                SELECT to_jsonb(s) AS "row!"
+                FROM subcategory s
+                INNER JOIN category c ON c.id = s.category_id
+                WHERE c.user_id = $1
+            "#,
+            user_id
+        )
+        .fetch_all(&mut *con)
+        .await?,
+    ));
+    res.push((
+        "credit_cards",
+        sqlx::query_scalar!(
+            r#"SELECT to_jsonb(c) AS "row!" FROM credit_card c WHERE user_id = $1"#,
+            user_id
+        )
+        .fetch_all(&mut *con)
+        .await?,
+    ));
+    res.push((
+        "credit_card_bills",
+        sqlx::query_scalar!(
+            r#"
+                SELECT to_jsonb(b) AS "row!"
+                FROM credit_card_bill b
+                INNER JOIN credit_card c ON c.id = b.credit_card_id
+                WHERE c.user_id = $1
+            "#,
+            user_id
+        )
+        .fetch_all(&mut *con)
+        .await?,
+    ));
+    res.push((
+        "accounts",
+        sqlx::query_scalar!(
+            r#"SELECT to_jsonb(a) AS "row!" FROM account a WHERE user_id = $1"#,
+            user_id
+        )
+        .fetch_all(&mut *con)
+        .await?,
+    ));
+    res.push((
+        "transactions",
+        sqlx::query_scalar!(
+            r#"SELECT to_jsonb(t) AS "row!" FROM transaction t WHERE user_id = $1"#,
+            user_id
+        )
+        .fetch_all(&mut *con)
+        .await?,
+    ));
+    res.push((
+        "transfers",
+        sqlx::query_scalar!(
+            r#"SELECT to_jsonb(t) AS "row!" FROM transfer t WHERE user_id = $1"#,
+            user_id
+        )
+        .fetch_all(&mut *con)
+        .await?,
+    ));
+    res.push((
+        "recurring_transactions",
+        sqlx::query_scalar!(
+            r#"SELECT to_jsonb(r) AS "row!" FROM recurring_transaction r WHERE user_id = $1"#,
+            user_id
+        )
+        .fetch_all(&mut *con)
+        .await?,
+    ));
+    res.push((
+        "budgets",
+        sqlx::query_scalar!(
+            r#"SELECT to_jsonb(b) AS "row!" FROM budget b WHERE user_id = $1"#,
+            user_id
+        )
+        .fetch_all(&mut *con)
+        .await?,
+    ));
+
+    Ok(res)
+}
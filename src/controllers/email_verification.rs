@@ -0,0 +1,136 @@
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use uuid::Uuid;
+
+use crate::model::email_verification::EmailVerification;
+
+/// Creates a verification for the user's email or, when `new_email` is
+/// given, for changing it to the new one.
+pub async fn create_email_verification<'a, T>(
+    email: &str,
+    new_email: Option<&str>,
+    con: T,
+) -> Result<EmailVerification, sqlx::error::Error>
+where
+    T: sqlx::Executor<'a, Database = sqlx::Postgres>,
+{
+    let now = Utc::now();
+
+    let rec = EmailVerification {
+        id: Uuid::new_v4(),
+        user_email: String::from(email),
+        sent_at: now,
+        expires_at: now + Duration::days(1),
+        is_verified: false,
+        new_email: new_email.map(String::from),
+    };
+
+    let _ = sqlx::query!(
+        r#"
+        INSERT INTO email_verification
+            (id, user_email, sent_at, expires_at, is_verified, new_email)
+        VALUES ($1, $2, $3, $4, $5, $6);
+    "#,
+        rec.id,
+        rec.user_email,
+        rec.sent_at.naive_utc(),
+        rec.expires_at.naive_utc(),
+        rec.is_verified,
+        rec.new_email
+    )
+    .execute(con)
+    .await?;
+
+    Ok(rec)
+}
+
+pub async fn get_last_email_verification_sent_at<'a, T>(
+    email: &str,
+    con: T,
+) -> Result<Option<DateTime<Utc>>, sqlx::error::Error>
+where
+    T: sqlx::Executor<'a, Database = sqlx::Postgres>,
+{
+    let res = sqlx::query!(
+        r#"
+        SELECT MAX(sent_at) AS sent_at FROM email_verification WHERE user_email = $1;
+    "#,
+        email
+    )
+    .fetch_one(con)
+    .await?;
+
+    Ok(res.sent_at.map(|s| Utc.from_utc_datetime(&s)))
+}
+
+/// Marks the verification as used and the user's email as verified. For an
+/// email change, the user's email is replaced by the new one, which moves
+/// their sessions along. Returns the verified email, or None when the
+/// verification is unknown, expired, was already used or the new email was
+/// taken in the meantime.
+pub async fn verify_email(
+    id: &Uuid,
+    con: &mut sqlx::PgConnection,
+) -> Result<Option<String>, sqlx::error::Error> {
+    let res = sqlx::query!(
+        r#"
+        UPDATE email_verification SET is_verified = true
+        WHERE id = $1 AND is_verified = false
+            AND expires_at > (now() at time zone 'utc')
+        RETURNING user_email, new_email;
+    "#,
+        id
+    )
+    .fetch_optional(&mut *con)
+    .await?;
+
+    let (email, new_email) = match res {
+        Some(r) => (r.user_email, r.new_email),
+        None => return Ok(None),
+    };
+
+    let new_email = match new_email {
+        Some(e) => e,
+        None => {
+            let _ = sqlx::query!(
+                r#"
+                UPDATE "user" SET is_email_verified = true WHERE email = $1;
+            "#,
+                email
+            )
+            .execute(&mut *con)
+            .await?;
+
+            return Ok(Some(email));
+        }
+    };
+
+    let res = sqlx::query!(
+        r#"
+        UPDATE "user" SET email = $2, is_email_verified = true
+        WHERE email = $1
+            AND NOT EXISTS (SELECT 1 FROM "user" WHERE email = $2);
+    "#,
+        email,
+        new_email
+    )
+    .execute(&mut *con)
+    .await?;
+
+    if res.rows_affected() == 0 {
+        return Ok(None);
+    }
+
+    // Reset links sent to the old address shouldn't work anymore
+    let _ = sqlx::query!(
+        r#"
+        UPDATE reset_password SET expires_at = (now() at time zone 'utc')
+        WHERE user_email = $1 AND is_password_reset = false
+            AND expires_at > (now() at time zone 'utc');
+    "#,
+        email
+    )
+    .execute(&mut *con)
+    .await?;
+
+    Ok(Some(new_email))
+}
@@ -0,0 +1,120 @@
+use chrono::{TimeZone, Utc};
+
+use crate::model::{
+    identity::Identity,
+    user::{AuthType, User},
+};
+
+pub async fn create_identity<'a, T>(
+    identity: &Identity,
+    con: T,
+) -> Result<(), Box<dyn std::error::Error>>
+where
+    T: sqlx::Executor<'a, Database = sqlx::Postgres>,
+{
+    let _ = sqlx::query!(
+        r#"
+            INSERT INTO identity
+                (user_id, auth_type, provider_user_id, created_at)
+            VALUES ($1, $2, $3, $4)
+        "#,
+        identity.user_id,
+        identity.auth_type.clone() as AuthType,
+        identity.provider_user_id,
+        identity.created_at.naive_utc()
+    )
+    .execute(con)
+    .await?;
+
+    Ok(())
+}
+
+pub async fn get_identities_by_user_id<'a, T>(
+    user_id: i32,
+    con: T,
+) -> Result<Vec<Identity>, Box<dyn std::error::Error>>
+where
+    T: sqlx::Executor<'a, Database = sqlx::Postgres>,
+{
+    let rows = sqlx::query!(
+        r#"
+            SELECT
+                user_id,
+                auth_type AS "auth_type!: AuthType",
+                provider_user_id,
+                created_at
+            FROM identity
+            WHERE user_id = $1
+            ORDER BY created_at
+        "#,
+        user_id
+    )
+    .fetch_all(con)
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|r| Identity {
+            user_id: r.user_id,
+            auth_type: r.auth_type,
+            provider_user_id: r.provider_user_id,
+            created_at: Utc.from_utc_datetime(&r.created_at),
+        })
+        .collect())
+}
+
+pub async fn get_user_by_identity<'a, T>(
+    auth_type: AuthType,
+    provider_user_id: &str,
+    con: T,
+) -> Result<Option<User>, Box<dyn std::error::Error>>
+where
+    T: sqlx::Executor<'a, Database = sqlx::Postgres>,
+{
+    let res = sqlx::query!(
+        r#"
+            SELECT
+                u.id, u.email, u.name,
+                u.password, u.created_at,
+                u.is_email_verified, u.is_premium
+            FROM "user" u
+            INNER JOIN identity i ON i.user_id = u.id
+            WHERE i.auth_type = $1 AND i.provider_user_id = $2
+        "#,
+        auth_type as AuthType,
+        provider_user_id
+    )
+    .fetch_optional(con)
+    .await?;
+
+    Ok(res.map(|r| User {
+        id: r.id,
+        email: r.email,
+        name: r.name,
+        password: r.password,
+        created_at: Utc.from_utc_datetime(&r.created_at),
+        is_email_verified: r.is_email_verified,
+        is_premium: r.is_premium,
+    }))
+}
+
+pub async fn delete_identity<'a, T>(
+    user_id: i32,
+    auth_type: AuthType,
+    con: T,
+) -> Result<(), Box<dyn std::error::Error>>
+where
+    T: sqlx::Executor<'a, Database = sqlx::Postgres>,
+{
+    let _ = sqlx::query!(
+        r#"
+            DELETE FROM identity WHERE user_id = $1 AND auth_type = $2
+        "#,
+        user_id,
+        auth_type as AuthType
+    )
+    .execute(con)
+    .await?;
+
+    Ok(())
+}
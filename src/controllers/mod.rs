@@ -1,6 +1,17 @@
+pub mod account;
+pub mod account_deletion;
 pub mod auth;
+pub mod budget;
 pub mod category;
 pub mod credit_card;
+pub mod data_export;
+pub mod email_verification;
+pub mod identity;
+pub mod recurring_transaction;
+pub mod report;
 pub mod reset_password;
-pub mod ses;
 pub mod session_mgm;
+pub mod sync;
+pub mod transaction;
+pub mod transfer;
+pub mod two_factor;
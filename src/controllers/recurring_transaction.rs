@@ -0,0 +1,381 @@
+use chrono::{DateTime, FixedOffset, Utc};
+use uuid::Uuid;
+
+use crate::{
+    controllers,
+    model::{
+        recurring_transaction::{RecurrenceFrequency, RecurringTransaction},
+        transaction::{Transaction, TransactionKind},
+    },
+};
+
+/// Returns the number of the next occurrence to be materialized, which is
+/// kept when an existing rule is edited.
+pub async fn upsert_recurring_transaction<'a, T>(
+    rule: &RecurringTransaction,
+    con: T,
+) -> Result<i32, Box<dyn std::error::Error>>
+where
+    T: sqlx::Executor<'a, Database = sqlx::Postgres>,
+{
+    let res = sqlx::query!(
+        r#"
+            INSERT INTO recurring_transaction
+                (id, user_id, kind, amount, description,
+                category_id, subcategory_id, credit_card_id,
+                account_id, frequency, frequency_interval,
+                start_at, utc_offset, end_at, occurrence_limit)
+            VALUES
+                ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15)
+            ON CONFLICT(id, user_id)
+            DO UPDATE
+                SET kind = $3,
+                    amount = $4,
+                    description = $5,
+                    category_id = $6,
+                    subcategory_id = $7,
+                    credit_card_id = $8,
+                    account_id = $9,
+                    frequency = $10,
+                    frequency_interval = $11,
+                    start_at = $12,
+                    utc_offset = $13,
+                    end_at = $14,
+                    occurrence_limit = $15,
+                    updated_at = (now() at time zone 'utc')
+                WHERE recurring_transaction.is_active = true
+            RETURNING next_occurrence_number
+        "#,
+        rule.id,
+        rule.user_id,
+        rule.kind.clone() as TransactionKind,
+        rule.amount,
+        rule.description,
+        rule.category_id,
+        rule.subcategory_id,
+        rule.credit_card_id,
+        rule.account_id,
+        rule.frequency.clone() as RecurrenceFrequency,
+        rule.frequency_interval,
+        rule.start_at.naive_utc(),
+        rule.start_at.offset().local_minus_utc(),
+        rule.end_at.map(|d| d.naive_utc()),
+        rule.occurrence_limit
+    )
+    .fetch_optional(con)
+    .await?;
+
+    match res {
+        Some(r) => Ok(r.next_occurrence_number),
+        None => Err("stopped recurring transactions can't be changed".into()),
+    }
+}
+
+pub async fn set_next_occurrence<'a, T>(
+    rule_id: &Uuid,
+    next_occurrence_number: i32,
+    next_occurrence_at: Option<DateTime<Utc>>,
+    con: T,
+) -> Result<(), Box<dyn std::error::Error>>
+where
+    T: sqlx::Executor<'a, Database = sqlx::Postgres>,
+{
+    let _ = sqlx::query!(
+        r#"
+            UPDATE recurring_transaction
+                SET next_occurrence_number = $2,
+                    next_occurrence_at = $3
+                WHERE id = $1
+        "#,
+        rule_id,
+        next_occurrence_number,
+        next_occurrence_at.map(|d| d.naive_utc())
+    )
+    .execute(con)
+    .await?;
+
+    Ok(())
+}
+
+/// Stops the series. Occurrences already materialized are kept.
+pub async fn stop_recurring_transaction<'a, T>(
+    rule_id: &Uuid,
+    user_id: i32,
+    con: T,
+) -> Result<(), Box<dyn std::error::Error>>
+where
+    T: sqlx::Executor<'a, Database = sqlx::Postgres>,
+{
+    let _ = sqlx::query!(
+        r#"
+            UPDATE recurring_transaction
+                SET is_active = false,
+                    next_occurrence_at = NULL,
+                    updated_at = (now() at time zone 'utc')
+                WHERE id = $1 AND user_id = $2 AND is_active = true
+        "#,
+        rule_id,
+        user_id
+    )
+    .execute(con)
+    .await?;
+
+    Ok(())
+}
+
+pub async fn get_recurring_transaction_by_id<'a, T>(
+    rule_id: &Uuid,
+    user_id: i32,
+    con: T,
+) -> Result<Option<RecurringTransaction>, Box<dyn std::error::Error>>
+where
+    T: sqlx::Executor<'a, Database = sqlx::Postgres>,
+{
+    let res = sqlx::query!(
+        r#"
+            SELECT
+                id,
+                user_id,
+                kind as "kind!: TransactionKind",
+                amount,
+                description,
+                category_id,
+                subcategory_id,
+                credit_card_id,
+                account_id,
+                frequency as "frequency!: RecurrenceFrequency",
+                frequency_interval,
+                start_at,
+                utc_offset,
+                end_at,
+                occurrence_limit,
+                next_occurrence_number
+            FROM recurring_transaction
+            WHERE id = $1 AND user_id = $2 AND is_active = true
+        "#,
+        rule_id,
+        user_id
+    )
+    .fetch_optional(con)
+    .await?;
+
+    let row = match res {
+        Some(r) => r,
+        None => return Ok(None),
+    };
+
+    let offset = FixedOffset::east_opt(row.utc_offset).ok_or("invalid utc offset")?;
+
+    Ok(Some(RecurringTransaction {
+        id: row.id,
+        user_id: row.user_id,
+        kind: row.kind,
+        amount: row.amount,
+        description: row.description,
+        category_id: row.category_id,
+        subcategory_id: row.subcategory_id,
+        credit_card_id: row.credit_card_id,
+        account_id: row.account_id,
+        frequency: row.frequency,
+        frequency_interval: row.frequency_interval,
+        start_at: row.start_at.and_utc().with_timezone(&offset),
+        end_at: row.end_at.map(|d| d.and_utc()),
+        occurrence_limit: row.occurrence_limit,
+        next_occurrence_number: row.next_occurrence_number,
+    }))
+}
+
+pub async fn get_recurring_transactions_by_user_id<'a, T>(
+    user_id: i32,
+    con: T,
+) -> Result<Vec<RecurringTransaction>, Box<dyn std::error::Error>>
+where
+    T: sqlx::Executor<'a, Database = sqlx::Postgres>,
+{
+    let rows = sqlx::query!(
+        r#"
+            SELECT
+                id,
+                user_id,
+                kind as "kind!: TransactionKind",
+                amount,
+                description,
+                category_id,
+                subcategory_id,
+                credit_card_id,
+                account_id,
+                frequency as "frequency!: RecurrenceFrequency",
+                frequency_interval,
+                start_at,
+                utc_offset,
+                end_at,
+                occurrence_limit,
+                next_occurrence_number
+            FROM recurring_transaction
+            WHERE user_id = $1 AND is_active = true
+            ORDER BY start_at
+        "#,
+        user_id
+    )
+    .fetch_all(con)
+    .await?;
+
+    let mut res: Vec<RecurringTransaction> = Vec::new();
+
+    for row in rows {
+        let offset = FixedOffset::east_opt(row.utc_offset).ok_or("invalid utc offset")?;
+
+        res.push(RecurringTransaction {
+            id: row.id,
+            user_id: row.user_id,
+            kind: row.kind,
+            amount: row.amount,
+            description: row.description,
+            category_id: row.category_id,
+            subcategory_id: row.subcategory_id,
+            credit_card_id: row.credit_card_id,
+            account_id: row.account_id,
+            frequency: row.frequency,
+            frequency_interval: row.frequency_interval,
+            start_at: row.start_at.and_utc().with_timezone(&offset),
+            end_at: row.end_at.map(|d| d.and_utc()),
+            occurrence_limit: row.occurrence_limit,
+            next_occurrence_number: row.next_occurrence_number,
+        });
+    }
+
+    Ok(res)
+}
+
+/// Locks the rules with occurrences due until `now` and returns their
+/// (id, user_id). Rules locked by another worker are skipped.
+pub async fn lock_due_recurring_transactions<'a, T>(
+    now: DateTime<Utc>,
+    con: T,
+) -> Result<Vec<(Uuid, i32)>, Box<dyn std::error::Error>>
+where
+    T: sqlx::Executor<'a, Database = sqlx::Postgres>,
+{
+    let rows = sqlx::query!(
+        r#"
+            SELECT id, user_id
+            FROM recurring_transaction
+            WHERE is_active = true AND next_occurrence_at <= $1
+            FOR UPDATE SKIP LOCKED
+        "#,
+        now.naive_utc()
+    )
+    .fetch_all(con)
+    .await?;
+
+    Ok(rows.into_iter().map(|r| (r.id, r.user_id)).collect())
+}
+
+/// Inserts the occurrence unless it was already materialized, so running it
+/// twice for the same occurrence number is harmless. Returns the id of the
+/// occurrence's transaction.
+pub async fn create_occurrence<'a, T>(
+    occurrence: &Transaction,
+    con: T,
+) -> Result<Uuid, Box<dyn std::error::Error>>
+where
+    T: sqlx::Executor<'a, Database = sqlx::Postgres>,
+{
+    let res = sqlx::query!(
+        r#"
+            INSERT INTO transaction
+                (id, user_id, kind, amount, description,
+                transaction_date, category_id, subcategory_id,
+                credit_card_id, credit_card_bill_id, account_id,
+                recurring_transaction_id, occurrence_number)
+            VALUES
+                ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13)
+            ON CONFLICT(recurring_transaction_id, occurrence_number)
+            DO UPDATE
+                SET recurring_transaction_id = EXCLUDED.recurring_transaction_id
+            RETURNING id
+        "#,
+        occurrence.id,
+        occurrence.user_id,
+        occurrence.kind.clone() as TransactionKind,
+        occurrence.amount,
+        occurrence.description,
+        occurrence.transaction_date.naive_utc(),
+        occurrence.category_id,
+        occurrence.subcategory_id,
+        occurrence.credit_card_id,
+        occurrence.credit_card_bill_id,
+        occurrence.account_id,
+        occurrence.recurring_transaction_id,
+        occurrence.occurrence_number
+    )
+    .fetch_one(con)
+    .await?;
+
+    Ok(res.id)
+}
+
+/// Creates the transaction of a single occurrence, assigning it to the
+/// credit card bill of its date. Returns None when the rule has no such
+/// occurrence.
+pub async fn materialize_occurrence(
+    rule: &RecurringTransaction,
+    occurrence_number: i32,
+    con: &mut sqlx::PgConnection,
+) -> Result<Option<Uuid>, Box<dyn std::error::Error>> {
+    let date = match rule.occurrence_date(occurrence_number) {
+        Some(d) => d,
+        None => return Ok(None),
+    };
+
+    let mut occurrence = rule.occurrence(occurrence_number, date.to_utc());
+    if let Some(credit_card_id) = rule.credit_card_id {
+        let card = controllers::credit_card::get_credit_card_by_id(
+            &credit_card_id,
+            rule.user_id,
+            &mut *con,
+        )
+        .await?
+        .ok_or("credit card of recurring transaction not found")?;
+
+        let bill = controllers::credit_card::get_or_create_bill_of_date(
+            &credit_card_id,
+            card.closing_day,
+            date.date_naive(),
+            *date.offset(),
+            &mut *con,
+        )
+        .await?;
+        occurrence.credit_card_bill_id = Some(bill.id);
+    }
+
+    Ok(Some(create_occurrence(&occurrence, &mut *con).await?))
+}
+
+/// Materializes every occurrence of the rule due until `now` and moves the
+/// rule to the following one. Returns how many occurrences were processed.
+pub async fn materialize_due_occurrences(
+    rule: &RecurringTransaction,
+    now: DateTime<Utc>,
+    con: &mut sqlx::PgConnection,
+) -> Result<i32, Box<dyn std::error::Error>> {
+    let mut occurrence_number = rule.next_occurrence_number;
+    while let Some(date) = rule.occurrence_date(occurrence_number) {
+        if date.to_utc() > now {
+            break;
+        }
+
+        materialize_occurrence(rule, occurrence_number, &mut *con).await?;
+        occurrence_number += 1;
+    }
+
+    set_next_occurrence(
+        &rule.id,
+        occurrence_number,
+        rule.occurrence_date(occurrence_number).map(|d| d.to_utc()),
+        &mut *con,
+    )
+    .await?;
+
+    Ok(occurrence_number - rule.next_occurrence_number)
+}
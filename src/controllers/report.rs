@@ -0,0 +1,261 @@
+use chrono::{DateTime, NaiveDate, Utc};
+
+use crate::model::{
+    credit_card_bill::CreditCardBill,
+    report::{CategoryTotal, SubcategoryTotal},
+};
+
+/// Sums income and expense per category and subcategory between `from`
+/// (inclusive) and `to` (exclusive). Installment parents are skipped, as
+/// their installments are counted instead.
+pub async fn get_totals_by_category<'a, T>(
+    user_id: i32,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+    con: T,
+) -> Result<Vec<CategoryTotal>, Box<dyn std::error::Error>>
+where
+    T: sqlx::Executor<'a, Database = sqlx::Postgres>,
+{
+    let rows = sqlx::query!(
+        r#"
+            SELECT
+                t.category_id,
+                c.name AS "category_name?",
+                c.color AS "category_color?",
+                c.icon_name AS "category_icon_name?",
+                t.subcategory_id,
+                s.name AS "subcategory_name?",
+                s.color AS "subcategory_color?",
+                s.icon_name AS "subcategory_icon_name?",
+                SUM(CASE WHEN t.kind = 'INCOME' THEN t.amount ELSE 0 END)::BIGINT AS "income!",
+                SUM(CASE WHEN t.kind = 'EXPENSE' THEN t.amount ELSE 0 END)::BIGINT AS "expense!"
+            FROM transaction t
+            LEFT JOIN category c ON c.id = t.category_id
+            LEFT JOIN subcategory s ON s.id = t.subcategory_id
+            WHERE t.user_id = $1 AND t.is_active = true
+                AND (t.installment_count = 1 OR t.parent_transaction_id IS NOT NULL)
+                AND t.transaction_date >= $2 AND t.transaction_date < $3
+            GROUP BY t.category_id, c.name, c.color, c.icon_name,
+                t.subcategory_id, s.name, s.color, s.icon_name
+            ORDER BY t.category_id, t.subcategory_id
+        "#,
+        user_id,
+        from.naive_utc(),
+        to.naive_utc()
+    )
+    .fetch_all(con)
+    .await?;
+
+    let mut res: Vec<CategoryTotal> = Vec::new();
+
+    for row in rows {
+        // Rows come sorted by category, so a new category starts a new total
+        let total = match res.last_mut() {
+            Some(t) if t.category_id == row.category_id => t,
+            _ => {
+                res.push(CategoryTotal {
+                    category_id: row.category_id,
+                    name: row.category_name,
+                    color: row.category_color,
+                    icon_name: row.category_icon_name,
+                    income: 0,
+                    expense: 0,
+                    subcategories: Vec::new(),
+                });
+                res.last_mut().unwrap()
+            }
+        };
+
+        total.income += row.income;
+        total.expense += row.expense;
+
+        if let (Some(subcategory_id), Some(name), Some(color), Some(icon_name)) = (
+            row.subcategory_id,
+            row.subcategory_name,
+            row.subcategory_color,
+            row.subcategory_icon_name,
+        ) {
+            total.subcategories.push(SubcategoryTotal {
+                subcategory_id,
+                name,
+                color,
+                icon_name,
+                income: row.income,
+                expense: row.expense,
+            });
+        }
+    }
+
+    res.sort_by_key(|t| std::cmp::Reverse(t.expense));
+
+    Ok(res)
+}
+
+/// Returns (month, income, expense) for the months between `from` and `to`
+/// with transactions, taken in the user's offset in seconds east of UTC.
+/// With `skip_billed`, credit card purchases assigned to a bill are left out
+/// so the caller can bucket them by due date instead.
+pub async fn get_monthly_totals<'a, T>(
+    user_id: i32,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+    utc_offset: i32,
+    skip_billed: bool,
+    con: T,
+) -> Result<Vec<(NaiveDate, i64, i64)>, Box<dyn std::error::Error>>
+where
+    T: sqlx::Executor<'a, Database = sqlx::Postgres>,
+{
+    let rows = sqlx::query!(
+        r#"
+            SELECT
+                date_trunc('month', transaction_date + $4::int * interval '1 second')::date
+                    AS "month!",
+                SUM(CASE WHEN kind = 'INCOME' THEN amount ELSE 0 END)::BIGINT AS "income!",
+                SUM(CASE WHEN kind = 'EXPENSE' THEN amount ELSE 0 END)::BIGINT AS "expense!"
+            FROM transaction
+            WHERE user_id = $1 AND is_active = true
+                AND (installment_count = 1 OR parent_transaction_id IS NOT NULL)
+                AND transaction_date >= $2 AND transaction_date < $3
+                AND NOT ($5 AND credit_card_bill_id IS NOT NULL)
+            GROUP BY 1
+        "#,
+        user_id,
+        from.naive_utc(),
+        to.naive_utc(),
+        utc_offset,
+        skip_billed
+    )
+    .fetch_all(con)
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|r| (r.month, r.income, r.expense))
+        .collect())
+}
+
+/// Returns (due_date, income, expense) of the credit card bills closing
+/// between `from` and `to`.
+pub async fn get_bill_totals<'a, T>(
+    user_id: i32,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+    con: T,
+) -> Result<Vec<(NaiveDate, i64, i64)>, Box<dyn std::error::Error>>
+where
+    T: sqlx::Executor<'a, Database = sqlx::Postgres>,
+{
+    let rows = sqlx::query!(
+        r#"
+            SELECT
+                b.id,
+                b.credit_card_id,
+                b.start_at,
+                b.end_at,
+                c.due_day,
+                SUM(CASE WHEN t.kind = 'INCOME' THEN t.amount ELSE 0 END)::BIGINT AS "income!",
+                SUM(CASE WHEN t.kind = 'EXPENSE' THEN t.amount ELSE 0 END)::BIGINT AS "expense!"
+            FROM credit_card_bill b
+            INNER JOIN credit_card c ON c.id = b.credit_card_id
+            INNER JOIN transaction t ON t.credit_card_bill_id = b.id
+            WHERE c.user_id = $1 AND t.is_active = true
+                AND (t.installment_count = 1 OR t.parent_transaction_id IS NOT NULL)
+                AND b.end_at >= $2 AND b.end_at < $3
+            GROUP BY b.id, c.due_day
+        "#,
+        user_id,
+        from.naive_utc(),
+        to.naive_utc()
+    )
+    .fetch_all(con)
+    .await?;
+
+    let mut res: Vec<(NaiveDate, i64, i64)> = Vec::new();
+
+    for row in rows {
+        let bill = CreditCardBill {
+            id: row.id,
+            credit_card_id: row.credit_card_id,
+            start_at: row.start_at.and_utc(),
+            end_at: row.end_at.and_utc(),
+            total_amount: row.expense - row.income,
+            paid_amount: None,
+            paid_at: None,
+        };
+        res.push((bill.due_date(row.due_day), row.income, row.expense));
+    }
+
+    Ok(res)
+}
+
+/// Sum of the initial balances of the user's accounts.
+pub async fn get_accounts_initial_balance<'a, T>(
+    user_id: i32,
+    con: T,
+) -> Result<i64, Box<dyn std::error::Error>>
+where
+    T: sqlx::Executor<'a, Database = sqlx::Postgres>,
+{
+    let res = sqlx::query!(
+        r#"
+            SELECT COALESCE(SUM(initial_balance), 0)::BIGINT AS "initial_balance!"
+            FROM account
+            WHERE user_id = $1 AND is_active = true
+        "#,
+        user_id
+    )
+    .fetch_one(con)
+    .await?;
+
+    Ok(res.initial_balance)
+}
+
+/// Returns (month, change) of the balance across the user's accounts for
+/// every month before `to` with account movements.
+pub async fn get_monthly_balance_changes<'a, T>(
+    user_id: i32,
+    to: DateTime<Utc>,
+    utc_offset: i32,
+    con: T,
+) -> Result<Vec<(NaiveDate, i64)>, Box<dyn std::error::Error>>
+where
+    T: sqlx::Executor<'a, Database = sqlx::Postgres>,
+{
+    let rows = sqlx::query!(
+        r#"
+            SELECT
+                date_trunc('month', m.moved_at + $3::int * interval '1 second')::date
+                    AS "month!",
+                SUM(m.amount)::BIGINT AS "change!"
+            FROM (
+                SELECT
+                    t.transaction_date AS moved_at,
+                    CASE WHEN t.kind = 'INCOME' THEN t.amount ELSE -t.amount END AS amount
+                FROM transaction t
+                INNER JOIN account a ON a.id = t.account_id
+                WHERE t.user_id = $1 AND t.is_active = true AND a.is_active = true
+                UNION ALL
+                SELECT tr.transfer_date, -tr.amount
+                FROM transfer tr
+                INNER JOIN account a ON a.id = tr.from_account_id
+                WHERE tr.user_id = $1 AND tr.is_active = true AND a.is_active = true
+                UNION ALL
+                SELECT tr.transfer_date, tr.amount
+                FROM transfer tr
+                INNER JOIN account a ON a.id = tr.to_account_id
+                WHERE tr.user_id = $1 AND tr.is_active = true AND a.is_active = true
+            ) m
+            WHERE m.moved_at < $2
+            GROUP BY 1
+        "#,
+        user_id,
+        to.naive_utc(),
+        utc_offset
+    )
+    .fetch_all(con)
+    .await?;
+
+    Ok(rows.into_iter().map(|r| (r.month, r.change)).collect())
+}
@@ -3,6 +3,8 @@ use uuid::Uuid;
 
 use crate::model::session::Session;
 
+/// Replaces the user's session on the same device, so logging in again
+/// doesn't pile up sessions. Clients that don't send the device share one.
 pub async fn create_session<'a, T>(
     con: T,
     session: &Session,
@@ -12,11 +14,16 @@ where
 {
     let _ = sqlx::query!(
         r#"
+            WITH replaced AS (
+                DELETE FROM sessions
+                WHERE user_email = $2 AND device_name = $8 AND platform = $9
+            )
             INSERT INTO sessions
                 (id, user_email, created_at, refresh_token,
                  refresh_token_expires_at,
-                 current_access_token, current_access_token_expires_at)
-            VALUES ($1, $2, $3, $4, $5, $6, $7);
+                 current_access_token, current_access_token_expires_at,
+                 device_name, platform, last_seen_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10);
         "#,
         session.id,
         session.user_email,
@@ -24,7 +31,10 @@ where
         session.refresh_token,
         session.refresh_token_expires_at.naive_utc(),
         session.current_access_token,
-        session.current_access_token_expires_at.naive_utc()
+        session.current_access_token_expires_at.naive_utc(),
+        session.device_name,
+        session.platform,
+        session.last_seen_at.naive_utc()
     )
     .execute(con)
     .await?;
@@ -44,7 +54,8 @@ where
             SELECT
                 id, user_email, created_at, refresh_token,
                 refresh_token_expires_at, current_access_token,
-                current_access_token_expires_at
+                current_access_token_expires_at,
+                device_name, platform, last_seen_at
             FROM sessions
             WHERE id = $1
         "#,
@@ -68,15 +79,20 @@ where
         current_access_token: res.current_access_token.clone(),
         current_access_token_expires_at: Utc
             .from_utc_datetime(&res.current_access_token_expires_at),
+        device_name: res.device_name.clone(),
+        platform: res.platform.clone(),
+        last_seen_at: Utc.from_utc_datetime(&res.last_seen_at),
     };
 
     Ok(Some(session))
 }
 
-pub async fn get_session_by_user_email<'a, T>(
+/// Returns the sessions of the user whose refresh token hasn't expired yet,
+/// most recently seen first.
+pub async fn get_sessions_by_user_email<'a, T>(
     con: T,
     user_email: &str,
-) -> Result<Option<Session>, Box<dyn std::error::Error>>
+) -> Result<Vec<Session>, Box<dyn std::error::Error>>
 where
     T: sqlx::Executor<'a, Database = sqlx::Postgres>,
 {
@@ -85,33 +101,37 @@ where
             SELECT
                 id, user_email, created_at, refresh_token,
                 refresh_token_expires_at, current_access_token,
-                current_access_token_expires_at
+                current_access_token_expires_at,
+                device_name, platform, last_seen_at
             FROM sessions
             WHERE user_email = $1
+                AND refresh_token_expires_at > (now() at time zone 'utc')
+            ORDER BY last_seen_at DESC
         "#,
         user_email
     )
     .fetch_all(con)
     .await?;
 
-    if rows.len() == 0 {
-        return Ok(None);
-    }
-
-    let res = rows.get(0).unwrap();
+    let mut res: Vec<Session> = Vec::new();
 
-    let session = Session {
-        id: res.id.clone(),
-        user_email: res.user_email.clone(),
-        created_at: Utc.from_utc_datetime(&res.created_at),
-        refresh_token: res.refresh_token.clone(),
-        refresh_token_expires_at: Utc.from_utc_datetime(&res.refresh_token_expires_at),
-        current_access_token: res.current_access_token.clone(),
-        current_access_token_expires_at: Utc
-            .from_utc_datetime(&res.current_access_token_expires_at),
-    };
+    for row in rows {
+        res.push(Session {
+            id: row.id,
+            user_email: row.user_email,
+            created_at: Utc.from_utc_datetime(&row.created_at),
+            refresh_token: row.refresh_token,
+            refresh_token_expires_at: Utc.from_utc_datetime(&row.refresh_token_expires_at),
+            current_access_token: row.current_access_token,
+            current_access_token_expires_at: Utc
+                .from_utc_datetime(&row.current_access_token_expires_at),
+            device_name: row.device_name,
+            platform: row.platform,
+            last_seen_at: Utc.from_utc_datetime(&row.last_seen_at),
+        });
+    }
 
-    Ok(Some(session))
+    Ok(res)
 }
 
 pub async fn delete_session_by_id<'a, T>(
@@ -133,24 +153,63 @@ where
     Ok(())
 }
 
-pub async fn update_session<'a, T>(
+/// Deletes one of the user's sessions, returning false when there's no
+/// session with that id for the user.
+pub async fn delete_user_session<'a, T>(
     con: T,
-    session: &Session,
+    session_id: &Uuid,
+    user_email: &str,
+) -> Result<bool, Box<dyn std::error::Error>>
+where
+    T: sqlx::Executor<'a, Database = sqlx::Postgres>,
+{
+    let res = sqlx::query!(
+        r#"
+            DELETE FROM sessions WHERE id = $1 AND user_email = $2
+        "#,
+        session_id,
+        user_email
+    )
+    .execute(con)
+    .await?;
+
+    Ok(res.rows_affected() > 0)
+}
+
+/// Deletes all of the user's sessions except the given one.
+pub async fn delete_other_sessions<'a, T>(
+    con: T,
+    user_email: &str,
+    session_id: &Uuid,
 ) -> Result<(), Box<dyn std::error::Error>>
 where
     T: sqlx::Executor<'a, Database = sqlx::Postgres>,
 {
     let _ = sqlx::query!(
         r#"
-            UPDATE sessions
-                SET current_access_token = $1,
-                    current_access_token_expires_at = $2
+            DELETE FROM sessions WHERE user_email = $1 AND id <> $2
+        "#,
+        user_email,
+        session_id
+    )
+    .execute(con)
+    .await?;
 
-            WHERE id = $3
+    Ok(())
+}
+
+pub async fn delete_user_sessions<'a, T>(
+    con: T,
+    user_email: &str,
+) -> Result<(), Box<dyn std::error::Error>>
+where
+    T: sqlx::Executor<'a, Database = sqlx::Postgres>,
+{
+    let _ = sqlx::query!(
+        r#"
+            DELETE FROM sessions WHERE user_email = $1
         "#,
-        session.current_access_token,
-        session.current_access_token_expires_at.naive_utc(),
-        session.id
+        user_email
     )
     .execute(con)
     .await?;
@@ -158,9 +217,32 @@ where
     Ok(())
 }
 
-pub async fn reset_session<'a, T>(
+pub async fn delete_expired_sessions<'a, T>(
     con: T,
-    session: &Session,
+    user_email: &str,
+) -> Result<(), Box<dyn std::error::Error>>
+where
+    T: sqlx::Executor<'a, Database = sqlx::Postgres>,
+{
+    let _ = sqlx::query!(
+        r#"
+            DELETE FROM sessions
+            WHERE user_email = $1
+                AND refresh_token_expires_at <= (now() at time zone 'utc')
+        "#,
+        user_email
+    )
+    .execute(con)
+    .await?;
+
+    Ok(())
+}
+
+/// Updates the session's last seen time, at most once a minute so requests
+/// don't write to the table every time.
+pub async fn touch_session<'a, T>(
+    con: T,
+    session_id: &Uuid,
 ) -> Result<(), Box<dyn std::error::Error>>
 where
     T: sqlx::Executor<'a, Database = sqlx::Postgres>,
@@ -168,22 +250,83 @@ where
     let _ = sqlx::query!(
         r#"
             UPDATE sessions
-                SET current_access_token = $1,
-                    current_access_token_expires_at = $2,
-                    refresh_token = $3,
-                    refresh_token_expires_at = $4,
-                    id = $5
-            WHERE user_email = $6
+                SET last_seen_at = (now() at time zone 'utc')
+            WHERE id = $1
+                AND last_seen_at < (now() at time zone 'utc') - interval '1 minute'
+        "#,
+        session_id
+    )
+    .execute(con)
+    .await?;
+
+    Ok(())
+}
+
+/// Replaces the session's tokens, as long as its refresh token is still
+/// `used_refresh_token`, and keeps the used one to detect its reuse later.
+/// Returns false when the refresh token was already rotated.
+pub async fn rotate_refresh_token(
+    session: &Session,
+    used_refresh_token: &str,
+    con: &mut sqlx::PgConnection,
+) -> Result<bool, Box<dyn std::error::Error>> {
+    let res = sqlx::query!(
+        r#"
+            UPDATE sessions
+                SET refresh_token = $1,
+                    refresh_token_expires_at = $2,
+                    current_access_token = $3,
+                    current_access_token_expires_at = $4
+            WHERE id = $5 AND refresh_token = $6
         "#,
-        session.current_access_token,
-        session.current_access_token_expires_at.naive_utc(),
         session.refresh_token,
         session.refresh_token_expires_at.naive_utc(),
+        session.current_access_token,
+        session.current_access_token_expires_at.naive_utc(),
         session.id,
-        session.user_email
+        used_refresh_token
     )
-    .execute(con)
+    .execute(&mut *con)
     .await?;
 
-    Ok(())
+    if res.rows_affected() == 0 {
+        return Ok(false);
+    }
+
+    let _ = sqlx::query!(
+        r#"
+            INSERT INTO refresh_token_history (session_id, refresh_token)
+            VALUES ($1, $2)
+        "#,
+        session.id,
+        used_refresh_token
+    )
+    .execute(&mut *con)
+    .await?;
+
+    Ok(true)
+}
+
+pub async fn is_refresh_token_reused<'a, T>(
+    con: T,
+    session_id: &Uuid,
+    refresh_token: &str,
+) -> Result<bool, Box<dyn std::error::Error>>
+where
+    T: sqlx::Executor<'a, Database = sqlx::Postgres>,
+{
+    let res = sqlx::query!(
+        r#"
+            SELECT EXISTS(
+                SELECT 1 FROM refresh_token_history
+                WHERE session_id = $1 AND refresh_token = $2
+            ) AS "reused!"
+        "#,
+        session_id,
+        refresh_token
+    )
+    .fetch_one(con)
+    .await?;
+
+    Ok(res.reused)
 }
@@ -0,0 +1,56 @@
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+/// Returns (kind, id) of everything the user deleted after `since`, so
+/// clients can drop their local copies. Bills are never deleted.
+pub async fn get_deleted_since<'a, T>(
+    user_id: i32,
+    since: DateTime<Utc>,
+    con: T,
+) -> Result<Vec<(String, Uuid)>, Box<dyn std::error::Error>>
+where
+    T: sqlx::Executor<'a, Database = sqlx::Postgres>,
+{
+    let rows = sqlx::query!(
+        r#"
+            SELECT 'category' AS "kind!", id AS "id!"
+            FROM category
+            WHERE user_id = $1 AND is_active = false AND updated_at > $2
+            UNION ALL
+            SELECT 'subcategory', s.id
+            FROM subcategory s
+            INNER JOIN category c ON c.id = s.category_id
+            WHERE c.user_id = $1 AND s.is_active = false AND s.updated_at > $2
+            UNION ALL
+            SELECT 'credit_card', id
+            FROM credit_card
+            WHERE user_id = $1 AND is_active = false AND updated_at > $2
+            UNION ALL
+            SELECT 'transaction', id
+            FROM transaction
+            WHERE user_id = $1 AND is_active = false AND updated_at > $2
+            UNION ALL
+            SELECT 'account', id
+            FROM account
+            WHERE user_id = $1 AND is_active = false AND updated_at > $2
+            UNION ALL
+            SELECT 'transfer', id
+            FROM transfer
+            WHERE user_id = $1 AND is_active = false AND updated_at > $2
+            UNION ALL
+            SELECT 'budget', id
+            FROM budget
+            WHERE user_id = $1 AND is_active = false AND updated_at > $2
+            UNION ALL
+            SELECT 'recurring_transaction', id
+            FROM recurring_transaction
+            WHERE user_id = $1 AND is_active = false AND updated_at > $2
+        "#,
+        user_id,
+        since.naive_utc()
+    )
+    .fetch_all(con)
+    .await?;
+
+    Ok(rows.into_iter().map(|r| (r.kind, r.id)).collect())
+}
@@ -0,0 +1,271 @@
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use crate::model::transaction::{Transaction, TransactionKind};
+
+pub async fn upsert_transaction<'a, T>(
+    transaction: &Transaction,
+    con: T,
+) -> Result<(), Box<dyn std::error::Error>>
+where
+    T: sqlx::Executor<'a, Database = sqlx::Postgres>,
+{
+    let res = sqlx::query!(
+        r#"
+            INSERT INTO transaction
+                (id, user_id, kind, amount, description,
+                transaction_date, category_id, subcategory_id,
+                credit_card_id, credit_card_bill_id, installment_count,
+                account_id)
+            VALUES
+                ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12)
+            ON CONFLICT(id, user_id)
+            DO UPDATE
+                SET kind = $3,
+                    amount = $4,
+                    description = $5,
+                    transaction_date = $6,
+                    category_id = $7,
+                    subcategory_id = $8,
+                    credit_card_id = $9,
+                    credit_card_bill_id = $10,
+                    installment_count = $11,
+                    account_id = $12,
+                    updated_at = (now() at time zone 'utc')
+                WHERE transaction.parent_transaction_id IS NULL
+        "#,
+        transaction.id,
+        transaction.user_id,
+        transaction.kind.clone() as TransactionKind,
+        transaction.amount,
+        transaction.description,
+        transaction.transaction_date.naive_utc(),
+        transaction.category_id,
+        transaction.subcategory_id,
+        transaction.credit_card_id,
+        transaction.credit_card_bill_id,
+        transaction.installment_count,
+        transaction.account_id
+    )
+    .execute(con)
+    .await?;
+
+    if res.rows_affected() == 0 {
+        return Err("installments can only be changed through their parent transaction".into());
+    }
+
+    Ok(())
+}
+
+pub async fn upsert_installment<'a, T>(
+    installment: &Transaction,
+    con: T,
+) -> Result<(), Box<dyn std::error::Error>>
+where
+    T: sqlx::Executor<'a, Database = sqlx::Postgres>,
+{
+    let _ = sqlx::query!(
+        r#"
+            INSERT INTO transaction
+                (id, user_id, kind, amount, description,
+                transaction_date, category_id, subcategory_id,
+                credit_card_id, credit_card_bill_id,
+                parent_transaction_id, installment_number,
+                installment_count, account_id)
+            VALUES
+                ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14)
+            ON CONFLICT(parent_transaction_id, installment_number)
+            DO UPDATE
+                SET kind = $3,
+                    amount = $4,
+                    description = $5,
+                    transaction_date = $6,
+                    category_id = $7,
+                    subcategory_id = $8,
+                    credit_card_id = $9,
+                    credit_card_bill_id = $10,
+                    installment_count = $13,
+                    account_id = $14,
+                    is_active = true,
+                    updated_at = (now() at time zone 'utc')
+        "#,
+        installment.id,
+        installment.user_id,
+        installment.kind.clone() as TransactionKind,
+        installment.amount,
+        installment.description,
+        installment.transaction_date.naive_utc(),
+        installment.category_id,
+        installment.subcategory_id,
+        installment.credit_card_id,
+        installment.credit_card_bill_id,
+        installment.parent_transaction_id,
+        installment.installment_number,
+        installment.installment_count,
+        installment.account_id
+    )
+    .execute(con)
+    .await?;
+
+    Ok(())
+}
+
+pub async fn delete_installments_after<'a, T>(
+    parent_transaction_id: &Uuid,
+    user_id: i32,
+    installment_number: i16,
+    con: T,
+) -> Result<(), Box<dyn std::error::Error>>
+where
+    T: sqlx::Executor<'a, Database = sqlx::Postgres>,
+{
+    let _ = sqlx::query!(
+        r#"
+            UPDATE transaction
+                SET is_active = false,
+                    updated_at = (now() at time zone 'utc')
+                WHERE parent_transaction_id = $1 AND user_id = $2
+                    AND installment_number > $3 AND is_active = true
+        "#,
+        parent_transaction_id,
+        user_id,
+        installment_number
+    )
+    .execute(con)
+    .await?;
+
+    Ok(())
+}
+
+pub async fn check_transaction_references<'a, T>(
+    transaction: &Transaction,
+    con: T,
+) -> Result<bool, Box<dyn std::error::Error>>
+where
+    T: sqlx::Executor<'a, Database = sqlx::Postgres>,
+{
+    let res = sqlx::query!(
+        r#"
+            SELECT
+                ($1::uuid IS NULL OR EXISTS(
+                    SELECT 1 FROM category
+                    WHERE id = $1 AND user_id = $4 AND is_active = true))
+                AND ($2::uuid IS NULL OR EXISTS(
+                    SELECT 1 FROM subcategory s
+                    INNER JOIN category c ON c.id = s.category_id
+                    WHERE s.id = $2 AND c.user_id = $4 AND s.is_active = true
+                        AND ($1::uuid IS NULL OR s.category_id = $1)))
+                AND ($3::uuid IS NULL OR EXISTS(
+                    SELECT 1 FROM credit_card
+                    WHERE id = $3 AND user_id = $4 AND is_active = true))
+                AND ($5::uuid IS NULL OR EXISTS(
+                    SELECT 1 FROM account
+                    WHERE id = $5 AND user_id = $4 AND is_active = true))
+                AS "is_valid!"
+        "#,
+        transaction.category_id,
+        transaction.subcategory_id,
+        transaction.credit_card_id,
+        transaction.user_id,
+        transaction.account_id
+    )
+    .fetch_one(con)
+    .await?;
+
+    Ok(res.is_valid)
+}
+
+pub async fn delete_transaction<'a, T>(
+    transaction_id: &Uuid,
+    user_id: i32,
+    con: T,
+) -> Result<(), Box<dyn std::error::Error>>
+where
+    T: sqlx::Executor<'a, Database = sqlx::Postgres>,
+{
+    let _ = sqlx::query!(
+        r#"
+            UPDATE transaction
+                SET is_active = false,
+                    updated_at = (now() at time zone 'utc')
+                WHERE (id = $1 OR parent_transaction_id = $1)
+                    AND user_id = $2 AND is_active = true
+        "#,
+        transaction_id,
+        user_id
+    )
+    .execute(con)
+    .await?;
+
+    Ok(())
+}
+
+pub async fn get_transactions_by_user_id<'a, T>(
+    user_id: i32,
+    from: Option<DateTime<Utc>>,
+    to: Option<DateTime<Utc>>,
+    updated_since: Option<DateTime<Utc>>,
+    con: T,
+) -> Result<Vec<Transaction>, Box<dyn std::error::Error>>
+where
+    T: sqlx::Executor<'a, Database = sqlx::Postgres>,
+{
+    let rows = sqlx::query!(
+        r#"
+            SELECT
+                id,
+                user_id,
+                kind as "kind!: TransactionKind",
+                amount,
+                description,
+                transaction_date,
+                category_id,
+                subcategory_id,
+                credit_card_id,
+                credit_card_bill_id,
+                account_id,
+                parent_transaction_id,
+                installment_number,
+                installment_count,
+                recurring_transaction_id,
+                occurrence_number
+            FROM transaction
+            WHERE user_id = $1 AND is_active = true
+                AND ($2::timestamp IS NULL OR transaction_date >= $2)
+                AND ($3::timestamp IS NULL OR transaction_date < $3)
+                AND ($4::timestamp IS NULL OR updated_at > $4)
+            ORDER BY transaction_date DESC
+        "#,
+        user_id,
+        from.map(|d| d.naive_utc()),
+        to.map(|d| d.naive_utc()),
+        updated_since.map(|d| d.naive_utc())
+    )
+    .fetch_all(con)
+    .await?;
+
+    let mut res: Vec<Transaction> = Vec::new();
+
+    for row in rows {
+        res.push(Transaction {
+            id: row.id,
+            user_id: row.user_id,
+            kind: row.kind,
+            amount: row.amount,
+            description: row.description,
+            transaction_date: row.transaction_date.and_utc(),
+            category_id: row.category_id,
+            subcategory_id: row.subcategory_id,
+            credit_card_id: row.credit_card_id,
+            credit_card_bill_id: row.credit_card_bill_id,
+            account_id: row.account_id,
+            parent_transaction_id: row.parent_transaction_id,
+            installment_number: row.installment_number,
+            installment_count: row.installment_count,
+            recurring_transaction_id: row.recurring_transaction_id,
+            occurrence_number: row.occurrence_number,
+        });
+    }
+
+    Ok(res)
+}
@@ -0,0 +1,138 @@
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use crate::model::transfer::Transfer;
+
+pub async fn create_transfer<'a, T>(
+    transfer: &Transfer,
+    con: T,
+) -> Result<(), Box<dyn std::error::Error>>
+where
+    T: sqlx::Executor<'a, Database = sqlx::Postgres>,
+{
+    let _ = sqlx::query!(
+        r#"
+            INSERT INTO transfer
+                (id, user_id, from_account_id, to_account_id,
+                credit_card_bill_id, amount, description,
+                transfer_date)
+            VALUES
+                ($1, $2, $3, $4, $5, $6, $7, $8)
+        "#,
+        transfer.id,
+        transfer.user_id,
+        transfer.from_account_id,
+        transfer.to_account_id,
+        transfer.credit_card_bill_id,
+        transfer.amount,
+        transfer.description,
+        transfer.transfer_date.naive_utc()
+    )
+    .execute(con)
+    .await?;
+
+    Ok(())
+}
+
+pub async fn check_transfer_accounts<'a, T>(
+    transfer: &Transfer,
+    con: T,
+) -> Result<bool, Box<dyn std::error::Error>>
+where
+    T: sqlx::Executor<'a, Database = sqlx::Postgres>,
+{
+    let res = sqlx::query!(
+        r#"
+            SELECT
+                EXISTS(
+                    SELECT 1 FROM account
+                    WHERE id = $1 AND user_id = $3 AND is_active = true)
+                AND ($2::uuid IS NULL OR EXISTS(
+                    SELECT 1 FROM account
+                    WHERE id = $2 AND user_id = $3 AND is_active = true))
+                AS "is_valid!"
+        "#,
+        transfer.from_account_id,
+        transfer.to_account_id,
+        transfer.user_id
+    )
+    .fetch_one(con)
+    .await?;
+
+    Ok(res.is_valid)
+}
+
+/// Returns the bill paid by the transfer and the amount, if any, so the
+/// caller can take the payment back.
+pub async fn delete_transfer<'a, T>(
+    transfer_id: &Uuid,
+    user_id: i32,
+    con: T,
+) -> Result<Option<(Uuid, i64)>, Box<dyn std::error::Error>>
+where
+    T: sqlx::Executor<'a, Database = sqlx::Postgres>,
+{
+    let res = sqlx::query!(
+        r#"
+            UPDATE transfer
+                SET is_active = false,
+                    updated_at = (now() at time zone 'utc')
+                WHERE id = $1 AND user_id = $2 AND is_active = true
+            RETURNING credit_card_bill_id, amount
+        "#,
+        transfer_id,
+        user_id
+    )
+    .fetch_optional(con)
+    .await?;
+
+    Ok(res.and_then(|r| r.credit_card_bill_id.map(|id| (id, r.amount))))
+}
+
+pub async fn get_transfers_by_user_id<'a, T>(
+    user_id: i32,
+    updated_since: Option<DateTime<Utc>>,
+    con: T,
+) -> Result<Vec<Transfer>, Box<dyn std::error::Error>>
+where
+    T: sqlx::Executor<'a, Database = sqlx::Postgres>,
+{
+    let rows = sqlx::query!(
+        r#"
+            SELECT
+                id,
+                user_id,
+                from_account_id,
+                to_account_id,
+                credit_card_bill_id,
+                amount,
+                description,
+                transfer_date
+            FROM transfer
+            WHERE user_id = $1 AND is_active = true
+                AND ($2::timestamp IS NULL OR updated_at > $2)
+            ORDER BY transfer_date DESC
+        "#,
+        user_id,
+        updated_since.map(|d| d.naive_utc())
+    )
+    .fetch_all(con)
+    .await?;
+
+    let mut res: Vec<Transfer> = Vec::new();
+
+    for row in rows {
+        res.push(Transfer {
+            id: row.id,
+            user_id: row.user_id,
+            from_account_id: row.from_account_id,
+            to_account_id: row.to_account_id,
+            credit_card_bill_id: row.credit_card_bill_id,
+            amount: row.amount,
+            description: row.description,
+            transfer_date: row.transfer_date.and_utc(),
+        });
+    }
+
+    Ok(res)
+}
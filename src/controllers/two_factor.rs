@@ -0,0 +1,260 @@
+use chrono::{TimeZone, Utc};
+use uuid::Uuid;
+
+use crate::model::{
+    two_factor::{Totp, TwoFactorChallenge},
+    user::AuthType,
+};
+
+/// Starts an enrollment with a new secret, replacing any unconfirmed one.
+/// Returns false when the user already has 2FA enabled.
+pub async fn upsert_totp_enrollment<'a, T>(
+    user_id: i32,
+    secret: &str,
+    con: T,
+) -> Result<bool, Box<dyn std::error::Error>>
+where
+    T: sqlx::Executor<'a, Database = sqlx::Postgres>,
+{
+    let res = sqlx::query!(
+        r#"
+            INSERT INTO totp (user_id, secret)
+            VALUES ($1, $2)
+            ON CONFLICT(user_id)
+            DO UPDATE
+                SET secret = $2,
+                    last_used_step = NULL,
+                    created_at = (now() at time zone 'utc')
+                WHERE totp.is_confirmed = false
+        "#,
+        user_id,
+        secret
+    )
+    .execute(con)
+    .await?;
+
+    Ok(res.rows_affected() > 0)
+}
+
+pub async fn get_totp<'a, T>(
+    user_id: i32,
+    con: T,
+) -> Result<Option<Totp>, Box<dyn std::error::Error>>
+where
+    T: sqlx::Executor<'a, Database = sqlx::Postgres>,
+{
+    let row = sqlx::query!(
+        r#"
+            SELECT secret, is_confirmed, last_used_step
+            FROM totp
+            WHERE user_id = $1
+        "#,
+        user_id
+    )
+    .fetch_optional(con)
+    .await?;
+
+    Ok(row.map(|r| Totp {
+        secret: r.secret,
+        is_confirmed: r.is_confirmed,
+        last_used_step: r.last_used_step,
+    }))
+}
+
+/// Marks the step's code as used, confirming the enrollment if it wasn't.
+/// Returns false when a code from the same or a later step was used first.
+pub async fn use_totp_step<'a, T>(
+    user_id: i32,
+    step: i64,
+    con: T,
+) -> Result<bool, Box<dyn std::error::Error>>
+where
+    T: sqlx::Executor<'a, Database = sqlx::Postgres>,
+{
+    let res = sqlx::query!(
+        r#"
+            UPDATE totp
+                SET last_used_step = $2,
+                    is_confirmed = true
+                WHERE user_id = $1
+                    AND (last_used_step IS NULL OR last_used_step < $2)
+        "#,
+        user_id,
+        step
+    )
+    .execute(con)
+    .await?;
+
+    Ok(res.rows_affected() > 0)
+}
+
+pub async fn delete_totp(
+    user_id: i32,
+    con: &mut sqlx::PgConnection,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let _ = sqlx::query!(
+        r#"
+            DELETE FROM recovery_code WHERE user_id = $1
+        "#,
+        user_id
+    )
+    .execute(&mut *con)
+    .await?;
+
+    let _ = sqlx::query!(
+        r#"
+            DELETE FROM totp WHERE user_id = $1
+        "#,
+        user_id
+    )
+    .execute(&mut *con)
+    .await?;
+
+    Ok(())
+}
+
+/// Replaces the user's recovery codes, given as hashes.
+pub async fn replace_recovery_codes(
+    user_id: i32,
+    code_hashes: &[String],
+    con: &mut sqlx::PgConnection,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let _ = sqlx::query!(
+        r#"
+            DELETE FROM recovery_code WHERE user_id = $1
+        "#,
+        user_id
+    )
+    .execute(&mut *con)
+    .await?;
+
+    for hash in code_hashes {
+        let _ = sqlx::query!(
+            r#"
+                INSERT INTO recovery_code (id, user_id, code_hash)
+                VALUES ($1, $2, $3)
+            "#,
+            Uuid::new_v4(),
+            user_id,
+            hash
+        )
+        .execute(&mut *con)
+        .await?;
+    }
+
+    Ok(())
+}
+
+/// Marks the recovery code as used, returning false when the user has no
+/// unused code with that hash.
+pub async fn use_recovery_code<'a, T>(
+    user_id: i32,
+    code_hash: &str,
+    con: T,
+) -> Result<bool, Box<dyn std::error::Error>>
+where
+    T: sqlx::Executor<'a, Database = sqlx::Postgres>,
+{
+    let res = sqlx::query!(
+        r#"
+            UPDATE recovery_code SET used_at = (now() at time zone 'utc')
+            WHERE id = (
+                SELECT id FROM recovery_code
+                WHERE user_id = $1 AND code_hash = $2 AND used_at IS NULL
+                LIMIT 1
+            )
+        "#,
+        user_id,
+        code_hash
+    )
+    .execute(con)
+    .await?;
+
+    Ok(res.rows_affected() > 0)
+}
+
+pub async fn create_two_factor_challenge<'a, T>(
+    challenge: &TwoFactorChallenge,
+    con: T,
+) -> Result<(), Box<dyn std::error::Error>>
+where
+    T: sqlx::Executor<'a, Database = sqlx::Postgres>,
+{
+    let _ = sqlx::query!(
+        r#"
+            INSERT INTO two_factor_challenge
+                (id, user_id, device_name, platform, expires_at, attempts, is_used, auth_type)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+        "#,
+        challenge.id,
+        challenge.user_id,
+        challenge.device_name,
+        challenge.platform,
+        challenge.expires_at.naive_utc(),
+        challenge.attempts,
+        challenge.is_used,
+        challenge.auth_type.clone() as AuthType
+    )
+    .execute(con)
+    .await?;
+
+    Ok(())
+}
+
+/// Counts an attempt at the challenge and returns it, or None when it's
+/// unknown, expired, used or out of attempts.
+pub async fn take_two_factor_challenge_attempt<'a, T>(
+    id: &Uuid,
+    max_attempts: i32,
+    con: T,
+) -> Result<Option<TwoFactorChallenge>, Box<dyn std::error::Error>>
+where
+    T: sqlx::Executor<'a, Database = sqlx::Postgres>,
+{
+    let row = sqlx::query!(
+        r#"
+            UPDATE two_factor_challenge
+                SET attempts = attempts + 1
+                WHERE id = $1 AND is_used = false AND attempts < $2
+                    AND expires_at > (now() at time zone 'utc')
+                RETURNING id, user_id, auth_type AS "auth_type!: AuthType",
+                    device_name, platform, expires_at, attempts, is_used
+        "#,
+        id,
+        max_attempts
+    )
+    .fetch_optional(con)
+    .await?;
+
+    Ok(row.map(|r| TwoFactorChallenge {
+        id: r.id,
+        user_id: r.user_id,
+        auth_type: r.auth_type,
+        device_name: r.device_name,
+        platform: r.platform,
+        expires_at: Utc.from_utc_datetime(&r.expires_at),
+        attempts: r.attempts,
+        is_used: r.is_used,
+    }))
+}
+
+/// Returns false when the challenge was already used by another request.
+pub async fn complete_two_factor_challenge<'a, T>(
+    id: &Uuid,
+    con: T,
+) -> Result<bool, Box<dyn std::error::Error>>
+where
+    T: sqlx::Executor<'a, Database = sqlx::Postgres>,
+{
+    let res = sqlx::query!(
+        r#"
+            UPDATE two_factor_challenge SET is_used = true
+            WHERE id = $1 AND is_used = false
+        "#,
+        id
+    )
+    .execute(con)
+    .await?;
+
+    Ok(res.rows_affected() > 0)
+}
@@ -0,0 +1,86 @@
+use std::path::PathBuf;
+
+use async_trait::async_trait;
+use chrono::Utc;
+
+use super::{Email, EmailConfig, EmailError, EmailSender};
+
+/// Development transport. Writes each email to a file in `directory`, or to
+/// the log when there's no directory.
+pub struct FileEmailSender {
+    directory: Option<PathBuf>,
+    config: EmailConfig,
+}
+
+impl FileEmailSender {
+    pub fn new(directory: Option<PathBuf>, config: EmailConfig) -> Self {
+        FileEmailSender { directory, config }
+    }
+}
+
+#[async_trait]
+impl EmailSender for FileEmailSender {
+    async fn send(&self, email: &Email) -> Result<(), EmailError> {
+        let (subject, body) = self.config.render(email);
+        let message = format!(
+            "From: {}\nTo: {}\nSubject: {}\n\n{}\n",
+            self.config.from, email.to, subject, body
+        );
+
+        match &self.directory {
+            Some(dir) => {
+                let file_name = format!(
+                    "{}_{}.eml",
+                    Utc::now().format("%Y%m%dT%H%M%S%.6f"),
+                    email.to
+                );
+                std::fs::write(dir.join(file_name), message)?;
+            }
+            None => log::info!("[EMAIL]\n{}", message),
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::email::EmailTemplate;
+
+    #[tokio::test]
+    async fn test_writes_email_to_directory() {
+        let dir = std::env::temp_dir().join(format!("finly-email-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let sender = FileEmailSender::new(
+            Some(dir.clone()),
+            EmailConfig {
+                from: String::from("no-reply@finly.test"),
+                locale: String::from("EN"),
+                reset_password_template: String::from("ResetPasswordTemplate"),
+                verify_email_template: String::from("VerifyEmailTemplate"),
+            },
+        );
+        let email = Email::new(
+            "user@finly.test",
+            EmailTemplate::VerifyEmail,
+            "https://finly.test/auth/verify_email?t=abc",
+        );
+        sender.send(&email).await.unwrap();
+
+        let files: Vec<PathBuf> = std::fs::read_dir(&dir)
+            .unwrap()
+            .map(|f| f.unwrap().path())
+            .collect();
+        assert_eq!(files.len(), 1);
+
+        let content = std::fs::read_to_string(&files[0]).unwrap();
+        assert!(content.contains("To: user@finly.test"));
+        assert!(content.contains("Subject: Verify your email"));
+        assert!(content.contains("https://finly.test/auth/verify_email?t=abc"));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
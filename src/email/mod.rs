@@ -0,0 +1,86 @@
+pub mod file;
+pub mod ses;
+pub mod smtp;
+
+use async_trait::async_trait;
+
+pub type EmailError = Box<dyn std::error::Error + Send + Sync>;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EmailTemplate {
+    ResetPassword,
+    VerifyEmail,
+}
+
+pub struct Email {
+    pub to: String,
+    pub template: EmailTemplate,
+    pub link: String,
+}
+
+impl Email {
+    pub fn new(to: &str, template: EmailTemplate, link: &str) -> Self {
+        Email {
+            to: String::from(to),
+            template,
+            link: String::from(link),
+        }
+    }
+}
+
+#[async_trait]
+pub trait EmailSender: Send + Sync {
+    async fn send(&self, email: &Email) -> Result<(), EmailError>;
+}
+
+pub struct EmailConfig {
+    pub from: String,
+    /// Suffix of the provider's template names, like "PTBR".
+    pub locale: String,
+    pub reset_password_template: String,
+    pub verify_email_template: String,
+}
+
+impl EmailConfig {
+    /// Name of the template stored in the provider, for the configured locale.
+    pub fn template_name(&self, template: EmailTemplate) -> String {
+        let name = match template {
+            EmailTemplate::ResetPassword => &self.reset_password_template,
+            EmailTemplate::VerifyEmail => &self.verify_email_template,
+        };
+
+        format!("{}_{}", name, self.locale)
+    }
+
+    /// Subject and plain text body, for the transports without stored
+    /// templates. Unknown locales fall back to Portuguese.
+    pub fn render(&self, email: &Email) -> (String, String) {
+        let english = self.locale.eq_ignore_ascii_case("EN");
+
+        let (subject, body) = match (email.template, english) {
+            (EmailTemplate::ResetPassword, false) => (
+                "Redefinição de senha",
+                "Para redefinir sua senha, acesse o link abaixo:\n\n{link}\n\n\
+                Se você não solicitou a redefinição, ignore este e-mail.",
+            ),
+            (EmailTemplate::ResetPassword, true) => (
+                "Reset your password",
+                "To reset your password, open the link below:\n\n{link}\n\n\
+                If you didn't ask for it, ignore this email.",
+            ),
+            (EmailTemplate::VerifyEmail, false) => (
+                "Confirme seu e-mail",
+                "Para confirmar seu e-mail, acesse o link abaixo:\n\n{link}",
+            ),
+            (EmailTemplate::VerifyEmail, true) => (
+                "Verify your email",
+                "To verify your email, open the link below:\n\n{link}",
+            ),
+        };
+
+        (
+            String::from(subject),
+            body.replace("{link}", email.link.as_str()),
+        )
+    }
+}
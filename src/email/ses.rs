@@ -0,0 +1,85 @@
+use async_trait::async_trait;
+use aws_config::BehaviorVersion;
+use aws_sdk_sesv2::types::{Destination, EmailContent, Template};
+use serde_json::json;
+
+use super::{Email, EmailConfig, EmailError, EmailSender, EmailTemplate};
+
+pub struct SesEmailSender {
+    client: aws_sdk_sesv2::Client,
+    config: EmailConfig,
+}
+
+impl SesEmailSender {
+    pub async fn new(config: EmailConfig) -> Self {
+        let aws_config = aws_config::load_defaults(BehaviorVersion::v2024_03_28()).await;
+
+        SesEmailSender {
+            client: aws_sdk_sesv2::Client::new(&aws_config),
+            config,
+        }
+    }
+}
+
+#[async_trait]
+impl EmailSender for SesEmailSender {
+    async fn send(&self, email: &Email) -> Result<(), EmailError> {
+        let mut dest: Destination = Destination::builder().build();
+        dest.to_addresses = Some(vec![email.to.clone()]);
+
+        let template_data = match email.template {
+            EmailTemplate::ResetPassword => json!({ "resetPasswordLink": email.link }),
+            EmailTemplate::VerifyEmail => json!({ "verifyEmailLink": email.link }),
+        };
+
+        let email_template = Template::builder()
+            .template_name(self.config.template_name(email.template))
+            .template_data(template_data.to_string())
+            .build();
+
+        let email_content = EmailContent::builder().template(email_template).build();
+
+        self.client
+            .send_email()
+            .from_email_address(self.config.from.as_str())
+            .destination(dest)
+            .content(email_content)
+            .send()
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    // Sends a real email through SES, run it with `cargo test -- --ignored`
+    #[tokio::test]
+    #[ignore]
+    async fn test_send_email() {
+        let sender = SesEmailSender::new(EmailConfig {
+            from: String::from("no-reply@dev.finly.digital"),
+            locale: String::from("PTBR"),
+            reset_password_template: String::from("ResetPasswordTemplate"),
+            verify_email_template: String::from("VerifyEmailTemplate"),
+        })
+        .await;
+        let email = Email::new(
+            "elissonlima0@gmail.com",
+            EmailTemplate::ResetPassword,
+            "https://g1.globo.com",
+        );
+
+        match sender.send(&email).await {
+            Ok(_) => {
+                println!("It worked!");
+            }
+            Err(e) => {
+                println!("error! {}", e);
+            }
+        }
+    }
+}
@@ -0,0 +1,150 @@
+use actix_web::{
+    http::{header::ContentType, StatusCode},
+    web, HttpMessage, HttpRequest, HttpResponse,
+};
+use serde_json::json;
+use sqlx::Acquire;
+use uuid::Uuid;
+
+use crate::{
+    controllers::{self, auth::get_user},
+    handlers::{macros, util::build_status_code_for_multiple_input},
+    model::account::Account,
+    request_types::account::{DeleteAccountReq, ListAccountsRes, UpsertAccountReq},
+    state,
+};
+
+pub async fn upsert_account(
+    req: HttpRequest,
+    body: web::Json<Vec<UpsertAccountReq>>,
+    app_state: web::Data<state::AppState>,
+) -> HttpResponse {
+    let ext = req.extensions();
+    let session = macros::get_session!(ext);
+    let mut con = macros::get_database_connection!(app_state);
+    let user = macros::unwrap_opt_or_unauthorize!(macros::run_async_unwrap!(
+        get_user(session.user_email.as_str(), &mut *con,),
+        "an error occurred when tried to get user from database"
+    ));
+
+    let mut tx = macros::begin_transaction!(con);
+    let mut not_created: Vec<Uuid> = Vec::new();
+
+    for account_req in body.iter() {
+        let account = Account {
+            id: account_req.id,
+            user_id: user.id,
+            name: account_req.name.clone(),
+            icon_name: account_req.icon_name.clone(),
+            account_type: account_req.account_type.clone(),
+            initial_balance: account_req.initial_balance,
+            balance: account_req.initial_balance,
+        };
+
+        if account.name.is_empty() || account.icon_name.is_empty() {
+            not_created.push(account.id);
+            continue;
+        }
+        let mut sp = macros::begin_transaction!(tx);
+
+        macros::run_async_or!(controllers::account::upsert_account(&account, &mut *sp), {
+            not_created.push(account.id);
+            continue;
+        });
+
+        macros::commit_transaction!(sp);
+    }
+
+    macros::commit_transaction!(tx);
+
+    let mut success = true;
+    let mut message = String::from("ok");
+    let status_code = build_status_code_for_multiple_input(
+        body.len(),
+        not_created.len(),
+        &mut message,
+        &mut success,
+    );
+
+    HttpResponse::build(status_code)
+        .insert_header(ContentType::json())
+        .body(
+            json!({
+                "success": success,
+                "message": message,
+                "errors": not_created
+            })
+            .to_string(),
+        )
+}
+
+pub async fn delete_account(
+    req: HttpRequest,
+    body: web::Json<Vec<DeleteAccountReq>>,
+    app_state: web::Data<state::AppState>,
+) -> HttpResponse {
+    let ext = req.extensions();
+    let session = macros::get_session!(ext);
+    let mut con = macros::get_database_connection!(app_state);
+    let user = macros::unwrap_opt_or_unauthorize!(macros::run_async_unwrap!(
+        get_user(session.user_email.as_str(), &mut *con,),
+        "an error occurred when tried to get user from database"
+    ));
+
+    let mut tx = macros::begin_transaction!(con);
+    let mut not_deleted: Vec<Uuid> = Vec::new();
+
+    for req in body.iter() {
+        macros::run_async_or!(
+            controllers::account::delete_account(req.account_id, user.id, &mut *tx),
+            {
+                not_deleted.push(req.account_id);
+            }
+        )
+    }
+
+    macros::commit_transaction!(tx);
+    let mut success = true;
+    let mut message = String::from("ok");
+    let status_code = build_status_code_for_multiple_input(
+        body.len(),
+        not_deleted.len(),
+        &mut message,
+        &mut success,
+    );
+
+    HttpResponse::build(status_code)
+        .insert_header(ContentType::json())
+        .body(
+            json!({
+                "success": success,
+                "message": message,
+                "errors": not_deleted
+            })
+            .to_string(),
+        )
+}
+
+pub async fn list_account(req: HttpRequest, app_state: web::Data<state::AppState>) -> HttpResponse {
+    let ext = req.extensions();
+    let session = macros::get_session!(ext);
+    let mut con = macros::get_database_connection!(app_state);
+    let user = macros::unwrap_opt_or_unauthorize!(macros::run_async_unwrap!(
+        get_user(session.user_email.as_str(), &mut *con,),
+        "an error occurred when tried to get user from database"
+    ));
+
+    let accounts = macros::run_async_unwrap!(
+        controllers::account::get_accounts_by_user_id(user.id, None, &mut *con),
+        "an error occurred when tried to get the accounts from DB"
+    );
+
+    let mut res: Vec<ListAccountsRes> = Vec::new();
+    for account in accounts {
+        res.push(ListAccountsRes::from(account));
+    }
+
+    HttpResponse::build(StatusCode::OK)
+        .insert_header(ContentType::json())
+        .body(json!(res).to_string())
+}
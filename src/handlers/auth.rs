@@ -1,70 +1,117 @@
-use std::process::{Command, Stdio};
-
 use actix_web::http::header::ContentType;
 use actix_web::http::StatusCode;
 use actix_web::{web, HttpMessage, HttpRequest, HttpResponse};
 use chrono::{DateTime, Duration, Utc};
 use serde_json::json;
-use uuid::Uuid;
+use sqlx::Acquire;
 
+use crate::controllers::account_deletion::cancel_user_deletion;
 use crate::controllers::auth::*;
+use crate::controllers::email_verification::{
+    create_email_verification, get_last_email_verification_sent_at, verify_email as do_verify_email,
+};
+use crate::controllers::identity::{
+    create_identity, get_identities_by_user_id, get_user_by_identity,
+};
 use crate::controllers::session_mgm::{
-    create_session, get_session_by_session_id, get_session_by_user_email, reset_session,
-    update_session,
+    create_session, delete_expired_sessions, delete_session_by_id, rotate_refresh_token,
+};
+use crate::controllers::two_factor::{
+    complete_two_factor_challenge, create_two_factor_challenge, get_totp,
+    take_two_factor_challenge_attempt, use_recovery_code, use_totp_step,
 };
-use crate::jwt::generate_token;
+use crate::email::{Email, EmailError, EmailTemplate};
+use crate::jwt::{generate_token, generate_token_hs256, verify_token_hs256};
+use crate::model::identity::Identity;
 use crate::model::session::Session;
+use crate::model::two_factor::TwoFactorChallenge;
 use crate::model::user::{AuthType, User};
-use crate::request_types::auth::{CreateUserReq, GoogleSignInReq, LoginUserReq};
+use crate::request_types::auth::{
+    is_device_info_valid, AppleSignInReq, CreateUserReq, GoogleSignInReq, LoginUserReq,
+    ResendVerificationEmailReq, VerifyEmailReq,
+};
+use crate::request_types::two_factor::LoginTwoFactorReq;
 use crate::state;
+use crate::throttle::LoginAttempt;
+use crate::totp::{hash_recovery_code, verify_code};
 
 use super::macros;
 use super::util::{
-    build_conflict_response, build_error_response, build_method_not_allowed,
-    build_unauthorized_response,
+    build_conflict_response, build_error_response, build_message_page, build_method_not_allowed,
+    build_too_many_requests, build_unauthorized_response, client_ip,
 };
 
+const REFRESH_TOKEN_TTL_DAYS: i64 = 1;
+const ACCESS_TOKEN_TTL_MINUTES: i64 = 15;
+const VERIFICATION_EMAIL_INTERVAL_MINUTES: i64 = 2;
+const TWO_FACTOR_CHALLENGE_TTL_MINUTES: i64 = 5;
+const TWO_FACTOR_CHALLENGE_MAX_ATTEMPTS: i32 = 5;
+
+/// Rotates the session's refresh token, extending the session for another
+/// `REFRESH_TOKEN_TTL_DAYS` from now, and issues a new access token.
 pub async fn refresh_token(
     req: HttpRequest,
     app_state: web::Data<state::AppState>,
 ) -> HttpResponse {
     let ext = req.extensions();
-    let claims = macros::unwrap_opt_or_error!(
-        ext.get::<crate::jwt::TokenClaims>(),
-        "it wasn't possible to get TokenClaims from Request Object"
-    );
-
-    let session_id = macros::uuid_from_str!(claims.sub.as_str());
-    let mut db = macros::get_database_connection!(app_state);
-    let mut session = macros::unwrap_opt_or_unauthorize!(macros::run_async_unwrap!(
-        get_session_by_session_id(&mut *db, &session_id),
-        "an error ocurred when tried to get session by id"
-    ));
+    let session = macros::get_session!(ext);
 
     if !session.is_refresh_token_valid() {
         return build_unauthorized_response(None);
     }
 
-    if !session.is_current_access_token_valid() {
-        let now: DateTime<Utc> = Utc::now().into();
-        let access_token_exp = now + Duration::minutes(15);
-
-        let access_token = macros::unwrap_res_or_error!(
-            generate_token(
-                session.id.to_string().as_str(),
-                &app_state.jwt_encoding_key,
-                access_token_exp,
-            ),
-            "an error occurred while generating access_token"
-        );
+    let now: DateTime<Utc> = Utc::now();
+    let refresh_token_exp = now + Duration::days(REFRESH_TOKEN_TTL_DAYS);
+    let refresh_token = macros::unwrap_res_or_error!(
+        generate_token(
+            session.id.to_string().as_str(),
+            &app_state.jwt_encoding_key,
+            refresh_token_exp,
+        ),
+        "an error occurred while generating refresh_token"
+    );
 
-        session.current_access_token = access_token;
-        session.current_access_token_expires_at = access_token_exp;
+    let access_token_exp = now + Duration::minutes(ACCESS_TOKEN_TTL_MINUTES);
+    let access_token = macros::unwrap_res_or_error!(
+        generate_token(
+            session.id.to_string().as_str(),
+            &app_state.jwt_encoding_key,
+            access_token_exp,
+        ),
+        "an error occurred while generating access_token"
+    );
+
+    let rotated = Session {
+        id: session.id,
+        user_email: session.user_email.clone(),
+        created_at: session.created_at,
+        refresh_token,
+        refresh_token_expires_at: refresh_token_exp,
+        current_access_token: access_token,
+        current_access_token_expires_at: access_token_exp,
+        device_name: session.device_name.clone(),
+        platform: session.platform.clone(),
+        last_seen_at: now,
+    };
+
+    let mut db = macros::get_database_connection!(app_state);
+    let mut tx = macros::begin_transaction!(db);
 
+    let is_rotated = macros::run_async_unwrap!(
+        rotate_refresh_token(&rotated, session.refresh_token.as_str(), &mut tx),
+        "error while trying to rotate the refresh token"
+    );
+
+    macros::commit_transaction!(tx);
+
+    // Another request rotated the same token first, so it's being reused
+    if !is_rotated {
+        log::warn!("Refresh token reused, revoking session {}", session.id);
         macros::run_async_unwrap!(
-            update_session(&mut *db, &session),
-            "error while trying update session"
+            delete_session_by_id(&mut *db, &session.id),
+            "an error occurred when tried to delete session"
         );
+        return build_unauthorized_response(None);
     }
 
     HttpResponse::build(StatusCode::OK)
@@ -72,35 +119,70 @@ pub async fn refresh_token(
         .body(
             json!({
             "success": true,
-            "access_token": session.current_access_token,
-            "access_token_exp": session.current_access_token_expires_at.to_rfc3339()})
+            "access_token": rotated.current_access_token,
+            "access_token_exp": rotated.current_access_token_expires_at.to_rfc3339(),
+            "refresh_token": rotated.refresh_token,
+            "refresh_token_exp": rotated.refresh_token_expires_at.to_rfc3339()})
             .to_string(),
         )
 }
 
 pub async fn login_user(
+    http_req: HttpRequest,
     req: web::Json<LoginUserReq>,
     app_state: web::Data<state::AppState>,
 ) -> HttpResponse {
+    if !is_device_info_valid(req.device_name.as_deref(), req.platform.as_deref()) {
+        return HttpResponse::build(StatusCode::BAD_REQUEST)
+            .insert_header(ContentType::json())
+            .body(
+                json!({ "success": false, "message": "invalid device_name or platform"})
+                    .to_string(),
+            );
+    }
+
+    let ip = client_ip(&http_req);
+    // Recorded as a failure until the tokens are issued
+    let attempt = macros::run_async_unwrap!(
+        app_state
+            .throttle
+            .check_login(req.email.as_str(), ip.as_str()),
+        "an error occurred when tried to check the login attempts"
+    );
+    if let Some(retry_at) = attempt.retry_at {
+        log::warn!("login throttled for {} from {}", req.email, ip);
+        return build_too_many_requests("too many failed login attempts", retry_at);
+    }
+
     //Get DB Connection for Transational Database
     let mut con = macros::get_database_connection!(app_state);
 
-    let db_user = macros::unwrap_opt_or_unauthorize!(
-        macros::run_async_unwrap!(
-            get_user(req.email.as_str(), &mut *con),
-            "an error occurred when tried to retrieve user from database"
-        ),
-        "can't find user on DB: Invalid email or password",
-        "incorrect email or password"
+    let db_user = match macros::run_async_unwrap!(
+        get_user(req.email.as_str(), &mut *con),
+        "an error occurred when tried to retrieve user from database"
+    ) {
+        Some(u) => u,
+        None => {
+            log::warn!("can't find user on DB: Invalid email or password");
+            return build_unauthorized_response(Some(String::from("incorrect email or password")));
+        }
+    };
+
+    let identities = macros::run_async_unwrap!(
+        get_identities_by_user_id(db_user.id, &mut *con),
+        "an error occurred when tried to retrieve user identities from database"
     );
 
-    if db_user.auth_type != AuthType::UsernamePassword {
+    if !identities
+        .iter()
+        .any(|i| i.auth_type == AuthType::UsernamePassword)
+    {
         return build_method_not_allowed(Some(String::from(
             "auth method is not username and password",
         )));
     }
 
-    let pwd = db_user.password.unwrap_or_else(|| String::from(""));
+    let pwd = db_user.password.clone().unwrap_or_default();
     let is_pwd_valid = macros::unwrap_res_or_error!(
         bcrypt::verify(req.password.as_str(), pwd.as_str()),
         "an error occurred while verifying user's password"
@@ -110,25 +192,222 @@ pub async fn login_user(
         return build_unauthorized_response(Some(String::from("incorrect email or password")));
     }
 
-    let session_req = macros::run_async_unwrap!(
-        get_session_by_user_email(&mut *con, db_user.email.as_str()),
-        "an error ocurred when tried to get session by user email"
+    let session = Session::build(
+        db_user.email.as_str(),
+        req.device_name.as_deref().unwrap_or_default(),
+        req.platform.as_deref().unwrap_or_default(),
     );
 
-    let mut new_session = false;
-    let mut refresh_session = false;
-    let mut session = session_req.unwrap_or_else(|| {
-        new_session = true;
-        Session::build(db_user.email.as_str())
-    });
+    complete_login(
+        &db_user,
+        AuthType::UsernamePassword,
+        StatusCode::OK,
+        session,
+        Some(&attempt),
+        &app_state,
+        &mut con,
+    )
+    .await
+}
+
+/// Second step of the login for users with 2FA enabled. Accepts a code from
+/// the authenticator app or one of the recovery codes.
+pub async fn login_two_factor(
+    http_req: HttpRequest,
+    req: web::Json<LoginTwoFactorReq>,
+    app_state: web::Data<state::AppState>,
+) -> HttpResponse {
+    let claims = match verify_token_hs256(req.challenge_token.as_str()) {
+        Ok(c) => c,
+        Err(e) => {
+            log::warn!("Error while trying to verify 2FA challenge token: {}", e);
+            return build_unauthorized_response(Some(String::from("invalid challenge token")));
+        }
+    };
+    let challenge_id = macros::uuid_from_str!(claims.sub.as_str());
 
-    if !new_session {
-        session.id = Uuid::new_v4();
-        refresh_session = true;
+    let mut con = macros::get_database_connection!(app_state);
+
+    let challenge = match macros::run_async_unwrap!(
+        take_two_factor_challenge_attempt(
+            &challenge_id,
+            TWO_FACTOR_CHALLENGE_MAX_ATTEMPTS,
+            &mut *con
+        ),
+        "an error occurred when tried to get the 2FA challenge"
+    ) {
+        Some(c) => c,
+        None => {
+            return build_unauthorized_response(Some(String::from(
+                "challenge expired, log in again",
+            )));
+        }
+    };
+
+    let db_user = macros::unwrap_opt_or_unauthorize!(macros::run_async_unwrap!(
+        get_user_by_id(challenge.user_id, &mut *con),
+        "an error occurred when tried to retrieve user from database"
+    ));
+
+    // A wrong code is a failed login for the account, like a wrong password
+    let ip = client_ip(&http_req);
+    let attempt = macros::run_async_unwrap!(
+        app_state
+            .throttle
+            .check_login(db_user.email.as_str(), ip.as_str()),
+        "an error occurred when tried to check the login attempts"
+    );
+    if let Some(retry_at) = attempt.retry_at {
+        log::warn!("2FA login throttled for {} from {}", db_user.email, ip);
+        return build_too_many_requests("too many failed login attempts", retry_at);
+    }
+
+    let totp = match macros::run_async_unwrap!(
+        get_totp(db_user.id, &mut *con),
+        "an error occurred when tried to get the user's 2FA enrollment"
+    ) {
+        Some(t) if t.is_confirmed => t,
+        // 2FA was disabled in the meantime, so the first step was enough
+        _ => {
+            return build_unauthorized_response(Some(String::from(
+                "challenge expired, log in again",
+            )));
+        }
+    };
+
+    let step = macros::unwrap_res_or_error!(
+        verify_code(
+            totp.secret.as_str(),
+            req.code.as_str(),
+            Utc::now(),
+            totp.last_used_step
+        ),
+        "an error occurred while verifying the 2FA code"
+    );
+    let is_code_valid = match step {
+        Some(step) => macros::run_async_unwrap!(
+            use_totp_step(db_user.id, step, &mut *con),
+            "an error occurred when tried to mark the 2FA code as used"
+        ),
+        None => {
+            let code_hash = macros::unwrap_res_or_error!(
+                hash_recovery_code(req.code.as_str()),
+                "an error occurred while hashing the recovery code"
+            );
+            macros::run_async_unwrap!(
+                use_recovery_code(db_user.id, code_hash.as_str(), &mut *con),
+                "an error occurred when tried to use the recovery code"
+            )
+        }
+    };
+
+    if !is_code_valid {
+        return build_unauthorized_response(Some(String::from("invalid code")));
+    }
+
+    let is_completed = macros::run_async_unwrap!(
+        complete_two_factor_challenge(&challenge.id, &mut *con),
+        "an error occurred when tried to complete the 2FA challenge"
+    );
+    if !is_completed {
+        return build_unauthorized_response(Some(String::from("challenge expired, log in again")));
+    }
+
+    let session = Session::build(
+        db_user.email.as_str(),
+        challenge.device_name.as_str(),
+        challenge.platform.as_str(),
+    );
+
+    issue_login_tokens(
+        &db_user,
+        challenge.auth_type,
+        StatusCode::OK,
+        session,
+        Some(&attempt),
+        &app_state,
+        &mut con,
+    )
+    .await
+}
+
+/// Finishes a login whose first step passed with the given auth method.
+/// With 2FA on, a challenge is returned instead of the tokens, which are
+/// only issued by login_two_factor.
+async fn complete_login(
+    db_user: &User,
+    auth_type: AuthType,
+    status: StatusCode,
+    session: Session,
+    attempt: Option<&LoginAttempt>,
+    app_state: &state::AppState,
+    con: &mut sqlx::PgConnection,
+) -> HttpResponse {
+    let totp = macros::run_async_unwrap!(
+        get_totp(db_user.id, &mut *con),
+        "an error occurred when tried to get the user's 2FA enrollment"
+    );
+
+    if totp.is_some_and(|t| t.is_confirmed) {
+        let challenge = TwoFactorChallenge::build(
+            db_user.id,
+            auth_type,
+            session.device_name.as_str(),
+            session.platform.as_str(),
+            Duration::minutes(TWO_FACTOR_CHALLENGE_TTL_MINUTES),
+        );
+
+        macros::run_async_unwrap!(
+            create_two_factor_challenge(&challenge, &mut *con),
+            "an error occurred when tried to create the 2FA challenge"
+        );
+
+        let challenge_token = macros::unwrap_res_or_error!(
+            generate_token_hs256(challenge.id.to_string().as_str(), challenge.expires_at),
+            "an error occurred while generating the 2FA challenge token"
+        );
+
+        return HttpResponse::build(StatusCode::OK)
+            .insert_header(ContentType::json())
+            .body(
+                json!({
+                    "two_factor_required": true,
+                    "challenge_token": challenge_token,
+                    "challenge_exp": challenge.expires_at.to_rfc3339(),
+                    "success": true})
+                .to_string(),
+            );
     }
 
+    issue_login_tokens(db_user, auth_type, status, session, attempt, app_state, con).await
+}
+
+/// Creates the session for a user that passed every login step and returns
+/// its tokens, along with the user as seen by the given auth method. The
+/// login attempt, if throttled, stops counting as a failure.
+async fn issue_login_tokens(
+    db_user: &User,
+    auth_type: AuthType,
+    status: StatusCode,
+    mut session: Session,
+    attempt: Option<&LoginAttempt>,
+    app_state: &state::AppState,
+    con: &mut sqlx::PgConnection,
+) -> HttpResponse {
+    // Logging in during the grace period keeps the account
+    let deletion_cancelled = macros::run_async_unwrap!(
+        cancel_user_deletion(db_user.id, &mut *con),
+        "an error occurred when tried to cancel the user deletion"
+    );
+
+    // Sessions are kept per device, so other devices stay logged in
+    macros::run_async_unwrap!(
+        delete_expired_sessions(&mut *con, db_user.email.as_str()),
+        "an error ocurred when tried to delete expired sessions"
+    );
+
     let now: DateTime<Utc> = Utc::now().into();
-    let refresh_token_exp = now + Duration::days(1);
+    let refresh_token_exp = now + Duration::days(REFRESH_TOKEN_TTL_DAYS);
     let refresh_token = macros::unwrap_res_or_error!(
         generate_token(
             session.id.to_string().as_str(),
@@ -141,7 +420,7 @@ pub async fn login_user(
     session.refresh_token = refresh_token;
     session.refresh_token_expires_at = refresh_token_exp;
 
-    let access_token_exp = now + Duration::minutes(15);
+    let access_token_exp = now + Duration::minutes(ACCESS_TOKEN_TTL_MINUTES);
     let access_token = macros::unwrap_res_or_error!(
         generate_token(
             session.id.to_string().as_str(),
@@ -154,35 +433,41 @@ pub async fn login_user(
     session.current_access_token = access_token;
     session.current_access_token_expires_at = access_token_exp;
 
-    if new_session {
-        macros::run_async_unwrap!(
-            create_session(&mut *con, &session),
-            "error attempting create session on database"
-        );
-    } else if refresh_session {
+    macros::run_async_unwrap!(
+        create_session(&mut *con, &session),
+        "error attempting create session on database"
+    );
+
+    if let Some(attempt) = attempt {
         macros::run_async_unwrap!(
-            reset_session(&mut *con, &session),
-            "error attempting update session on database"
+            app_state.throttle.clear_login(attempt),
+            "an error occurred when tried to clear the login attempts"
         );
     }
 
-    return HttpResponse::build(StatusCode::OK)
+    let mut user = json!({
+        "id": db_user.id,
+        "name": db_user.name,
+        "email": db_user.email,
+        "created_at": db_user.created_at.to_rfc3339(),
+        "auth_type": auth_type,
+        "is_email_verified": db_user.is_email_verified,
+        "is_premium": db_user.is_premium
+    });
+    if auth_type == AuthType::Apple {
+        user["is_private_email"] = json!(is_apple_private_relay_email(db_user.email.as_str()));
+    }
+
+    return HttpResponse::build(status)
         .insert_header(ContentType::json())
         .body(
             json!({
-                "user": {
-                    "id": db_user.id,
-                    "name": db_user.name,
-                    "email": db_user.email,
-                    "created_at": db_user.created_at.to_rfc3339(),
-                    "auth_type": db_user.auth_type,
-                    "is_email_verified": db_user.is_email_verified,
-                    "is_premium": db_user.is_premium
-                },
+                "user": user,
                 "access_token": session.current_access_token,
                 "access_token_exp": session.current_access_token_expires_at.to_rfc3339(),
                 "refresh_token": session.refresh_token,
                 "refresh_token_exp": session.refresh_token_expires_at.to_rfc3339(),
+                "deletion_cancelled": deletion_cancelled,
                 "success": true})
             .to_string(),
         );
@@ -208,10 +493,27 @@ pub async fn post_new_user(
         "an error ocurred when tried to create user object"
     );
 
-    let _ = macros::run_async_unwrap!(
-        create_user(&mut *con, &usr_obj),
+    let mut tx = macros::begin_transaction!(con);
+    let usr = macros::run_async_unwrap!(
+        create_user(&mut *tx, &usr_obj),
         "an error occurred when tried to insert user on the database"
     );
+    macros::run_async_unwrap!(
+        create_identity(
+            &Identity::new(usr.id, AuthType::UsernamePassword, None),
+            &mut *tx
+        ),
+        "an error occurred when tried to insert the user identity on the database"
+    );
+    macros::commit_transaction!(tx);
+
+    // The user can ask for another email if this one fails
+    if let Err(e) = send_verification(usr_obj.email.as_str(), None, &app_state, &mut con).await {
+        log::error!(
+            "an error occurred when tried to send the verification email: {}",
+            e
+        );
+    }
 
     return HttpResponse::build(StatusCode::CREATED)
         .insert_header(ContentType::json())
@@ -228,155 +530,307 @@ pub async fn google_signin(
     req: web::Json<GoogleSignInReq>,
     app_state: web::Data<state::AppState>,
 ) -> HttpResponse {
-    let oauth_res = match Command::new("sh")
-        .arg("-c")
-        .arg("google_oauth_api_client")
-        .env("GOOGLE_OAUTH_EXEC_INPUT_TOKEN", req.token.as_str())
-        .env(
-            "GOOGLE_OAUTH_EXEC_CLIENT_ID",
-            app_state.google_oauth_client_id.as_str(),
-        )
-        .stdout(Stdio::piped())
-        .output()
+    if !is_device_info_valid(req.device_name.as_deref(), req.platform.as_deref()) {
+        return HttpResponse::build(StatusCode::BAD_REQUEST)
+            .insert_header(ContentType::json())
+            .body(
+                json!({ "success": false, "message": "invalid device_name or platform"})
+                    .to_string(),
+            );
+    }
+
+    let oauth_user_data = match get_google_user_information(
+        req.token.as_str(),
+        app_state.google_oauth_client_id.as_str(),
+        &app_state.google_jwks,
+    )
+    .await
     {
-        Ok(c) => {
-            let o = std::str::from_utf8(&c.stdout).unwrap_or_else(|_| "");
-            let e = std::str::from_utf8(&c.stderr).unwrap_or_else(|_| "");
-            let status_code = c.status.code().unwrap_or_else(|| -1);
-            if status_code == 0 {
-                Some(String::from(o))
-            } else {
-                log::warn!("Could not get google_oauth user information: {}", e);
-                None
-            }
-        }
+        Ok(Some(o)) => o,
+        Ok(None) => return build_unauthorized_response(Some("invalid token".to_string())),
         Err(e) => {
             log::error!(
-                "An error occurred when tried to run google_oauth_api_client {}",
+                "An error occurred when tried to get the google oauth user information: {}",
                 e
             );
             return build_error_response();
         }
     };
 
-    if oauth_res.is_none() {
-        return build_unauthorized_response(Some("invalid token".to_string()));
-    }
+    let mut con = macros::get_database_connection!(app_state);
 
-    let oauth_user_data: GoogleOauthUserInformation = match serde_json::from_str(
-        oauth_res.unwrap().as_str(),
+    let mut new_user = false;
+    let usr = match macros::run_async_unwrap!(
+        get_user_by_identity(AuthType::Google, oauth_user_data.sub.as_str(), &mut *con),
+        "error trying to get user from database"
     ) {
-        Ok(o) => o,
+        Some(u) => u,
+        None => {
+            //An account with the same email has to link google first
+            let email_exists = macros::run_async_unwrap!(
+                check_email_exists(oauth_user_data.email.as_str(), &mut *con),
+                "an error occurred when tried to check if email exists on the db"
+            );
+            if email_exists {
+                return build_method_not_allowed(Some("invalid auth method".to_string()));
+            }
+
+            new_user = true;
+            let mut tx = macros::begin_transaction!(con);
+            let usr = macros::run_async_unwrap!(
+                create_user(&mut *tx, &User::from_google(&oauth_user_data)),
+                "an error ocurred when tried to insert a new user on the database"
+            );
+            macros::run_async_unwrap!(
+                create_identity(
+                    &Identity::new(usr.id, AuthType::Google, Some(oauth_user_data.sub)),
+                    &mut *tx
+                ),
+                "an error occurred when tried to insert the user identity on the database"
+            );
+            macros::commit_transaction!(tx);
+
+            usr
+        }
+    };
+
+    let status = if new_user {
+        StatusCode::CREATED
+    } else {
+        StatusCode::OK
+    };
+    let session = Session::build(
+        usr.email.as_str(),
+        req.device_name.as_deref().unwrap_or_default(),
+        req.platform.as_deref().unwrap_or_default(),
+    );
+
+    complete_login(
+        &usr,
+        AuthType::Google,
+        status,
+        session,
+        None,
+        &app_state,
+        &mut con,
+    )
+    .await
+}
+
+pub async fn apple_signin(
+    req: web::Json<AppleSignInReq>,
+    app_state: web::Data<state::AppState>,
+) -> HttpResponse {
+    if !is_device_info_valid(req.device_name.as_deref(), req.platform.as_deref()) {
+        return HttpResponse::build(StatusCode::BAD_REQUEST)
+            .insert_header(ContentType::json())
+            .body(
+                json!({ "success": false, "message": "invalid device_name or platform"})
+                    .to_string(),
+            );
+    }
+
+    let apple_user_data = match get_apple_user_information(
+        req.token.as_str(),
+        app_state.apple_client_id.as_str(),
+        &app_state.apple_jwks,
+    )
+    .await
+    {
+        Ok(Some(a)) => a,
+        Ok(None) => return build_unauthorized_response(Some("invalid token".to_string())),
         Err(e) => {
-            log::error!("An error occurred when tried to convert the google oauth information to object: {}", e);
+            log::error!(
+                "An error occurred when tried to get the apple user information: {}",
+                e
+            );
             return build_error_response();
         }
     };
 
     let mut con = macros::get_database_connection!(app_state);
 
-    //CHECK IF THERE'S A USER WITH SAME EMAIL
     let mut new_user = false;
-    let mut usr = macros::run_async_unwrap!(
-        get_user(oauth_user_data.email.as_str(), &mut *con),
+    let usr = match macros::run_async_unwrap!(
+        get_user_by_identity(AuthType::Apple, apple_user_data.sub.as_str(), &mut *con),
         "error trying to get user from database"
+    ) {
+        Some(u) => u,
+        None => {
+            // Apple may leave the email out, but an account can't be created without it
+            if apple_user_data.email.is_empty() {
+                return HttpResponse::build(StatusCode::BAD_REQUEST)
+                    .insert_header(ContentType::json())
+                    .body(json!({ "success": false, "message": "email is required"}).to_string());
+            }
+
+            //An account with the same email has to link apple first
+            let email_exists = macros::run_async_unwrap!(
+                check_email_exists(apple_user_data.email.as_str(), &mut *con),
+                "an error occurred when tried to check if email exists on the db"
+            );
+            if email_exists {
+                return build_method_not_allowed(Some("invalid auth method".to_string()));
+            }
+
+            let name = req.name.as_deref().map(str::trim).unwrap_or_default();
+
+            new_user = true;
+            let mut tx = macros::begin_transaction!(con);
+            let usr = macros::run_async_unwrap!(
+                create_user(&mut *tx, &User::from_apple(&apple_user_data, name)),
+                "an error ocurred when tried to insert a new user on the database"
+            );
+            macros::run_async_unwrap!(
+                create_identity(
+                    &Identity::new(usr.id, AuthType::Apple, Some(apple_user_data.sub)),
+                    &mut *tx
+                ),
+                "an error occurred when tried to insert the user identity on the database"
+            );
+            macros::commit_transaction!(tx);
+
+            usr
+        }
+    };
+
+    let status = if new_user {
+        StatusCode::CREATED
+    } else {
+        StatusCode::OK
+    };
+    let session = Session::build(
+        usr.email.as_str(),
+        req.device_name.as_deref().unwrap_or_default(),
+        req.platform.as_deref().unwrap_or_default(),
+    );
+
+    complete_login(
+        &usr,
+        AuthType::Apple,
+        status,
+        session,
+        None,
+        &app_state,
+        &mut con,
     )
-    .unwrap_or_else(|| {
-        new_user = true;
-        User::from_google(oauth_user_data)
-    });
+    .await
+}
 
-    if usr.auth_type != AuthType::Google {
-        return build_method_not_allowed(Some("invalid auth method".to_string()));
-    }
+/// Sends the verification link for the user's email or, when `new_email`
+/// is given, for changing it to the new one, which gets the link instead.
+pub(crate) async fn send_verification(
+    email: &str,
+    new_email: Option<&str>,
+    app_state: &state::AppState,
+    con: &mut sqlx::PgConnection,
+) -> Result<(), EmailError> {
+    let rec = create_email_verification(email, new_email, &mut *con).await?;
+    let token = generate_token_hs256(rec.id.to_string().as_str(), rec.expires_at)?;
+    let verification_link = format!(
+        "{}/auth/verify_email?t={}",
+        app_state.public_base_url, token
+    );
 
-    if new_user {
-        let usr_database = macros::run_async_unwrap!(
-            create_user(&mut *con, &usr),
-            "an error ocurred when tried to insert a new user on the database"
-        );
-        usr = usr_database.clone();
-    }
+    app_state
+        .email_sender
+        .send(&Email::new(
+            new_email.unwrap_or(email),
+            EmailTemplate::VerifyEmail,
+            verification_link.as_str(),
+        ))
+        .await?;
+
+    Ok(())
+}
 
-    //GENERATE REFRESH_TOKEN AND ACCESS_TOKEN
-    let session_req = macros::run_async_unwrap!(
-        get_session_by_user_email(&mut *con, usr.email.as_str()),
-        "an error ocurred when tried to get session by user email"
+pub async fn verify_email(
+    token: web::Query<VerifyEmailReq>,
+    app_state: web::Data<state::AppState>,
+) -> HttpResponse {
+    let claims = match verify_token_hs256(token.t.as_str()) {
+        Ok(c) => c,
+        Err(e) => {
+            log::warn!(
+                "Error while trying to verify token claims for email verification: {}",
+                e
+            );
+            return build_message_page(
+                StatusCode::UNAUTHORIZED,
+                "N&atilde;o autorizado",
+                "Link expirado. Solicite um novo e-mail de verifica&ccedil;&atilde;o.",
+            );
+        }
+    };
+
+    let verification_id = macros::uuid_from_str!(claims.sub.as_str());
+    let mut con = macros::get_database_connection!(app_state);
+    let mut tx = macros::begin_transaction!(con);
+
+    let email = macros::run_async_unwrap!(
+        do_verify_email(&verification_id, &mut tx),
+        "an error occurred when tried to verify the email"
     );
 
-    let mut new_session = false;
-    let mut refresh_session = false;
-    let mut session = session_req.unwrap_or_else(|| {
-        new_session = true;
-        Session::build(usr.email.as_str())
-    });
+    macros::commit_transaction!(tx);
 
-    if !new_session {
-        session.id = Uuid::new_v4();
-        refresh_session = true;
+    if email.is_none() {
+        return build_message_page(
+            StatusCode::UNAUTHORIZED,
+            "N&atilde;o autorizado",
+            "Link expirado. Solicite um novo e-mail de verifica&ccedil;&atilde;o.",
+        );
     }
 
-    let now: DateTime<Utc> = Utc::now().into();
-    let refresh_token_exp = now + Duration::days(1);
-    let refresh_token = macros::unwrap_res_or_error!(
-        generate_token(
-            session.id.to_string().as_str(),
-            &app_state.jwt_encoding_key,
-            refresh_token_exp.clone(),
-        ),
-        "an error occurred while generating refresh_token"
-    );
+    build_message_page(
+        StatusCode::OK,
+        "E-mail verificado",
+        "Seu e-mail foi verificado com sucesso.",
+    )
+}
 
-    session.refresh_token = refresh_token;
-    session.refresh_token_expires_at = refresh_token_exp;
+pub async fn resend_verification_email(
+    req: web::Json<ResendVerificationEmailReq>,
+    app_state: web::Data<state::AppState>,
+) -> HttpResponse {
+    let mut con = macros::get_database_connection!(app_state);
 
-    let access_token_exp = now + Duration::minutes(15);
-    let access_token = macros::unwrap_res_or_error!(
-        generate_token(
-            session.id.to_string().as_str(),
-            &app_state.jwt_encoding_key,
-            access_token_exp,
-        ),
-        "an error occurred while generating access_token"
+    let usr = macros::run_async_unwrap!(
+        get_user(req.email.as_str(), &mut *con),
+        "an error occurred when tried to retrieve user from database"
     );
 
-    session.current_access_token = access_token;
-    session.current_access_token_expires_at = access_token_exp;
+    //Send CREATED to avoid requests to check whether the email
+    //exists on DB or not.
+    let usr = match usr {
+        Some(u) if !u.is_email_verified => u,
+        _ => {
+            return HttpResponse::build(StatusCode::CREATED)
+                .insert_header(ContentType::json())
+                .body(json!({"success": true, "message": "email sent"}).to_string());
+        }
+    };
 
-    if new_session {
-        macros::run_async_unwrap!(
-            create_session(&mut *con, &session),
-            "error attempting create session on database"
-        );
-    } else if refresh_session {
+    let last_sent_at = macros::run_async_unwrap!(
+        get_last_email_verification_sent_at(usr.email.as_str(), &mut *con),
+        "an error occurred when tried to get the last verification email"
+    );
+
+    // Answered like a sent email too, or a 429 would tell the email is
+    // registered
+    let is_sent_recently = last_sent_at.is_some_and(|sent_at| {
+        sent_at + Duration::minutes(VERIFICATION_EMAIL_INTERVAL_MINUTES) > Utc::now()
+    });
+    if is_sent_recently {
+        log::warn!("verification email to {} sent recently", usr.email);
+    } else {
         macros::run_async_unwrap!(
-            reset_session(&mut *con, &session),
-            "error attempting update session on database"
+            send_verification(usr.email.as_str(), None, &app_state, &mut con),
+            "an error occurred when tried to send the verification email"
         );
     }
 
-    let mut sts = StatusCode::OK;
-    if new_user {
-        sts = StatusCode::CREATED
-    }
-    HttpResponse::build(sts)
+    HttpResponse::build(StatusCode::CREATED)
         .insert_header(ContentType::json())
-        .body(
-            json!({
-                "user": {
-                    "id": usr.id,
-                    "name": usr.name,
-                    "email": usr.email,
-                    "created_at": usr.created_at.to_rfc3339(),
-                    "auth_type": usr.auth_type,
-                    "is_email_verified": usr.is_email_verified,
-                    "is_premium": usr.is_premium
-                },
-                "access_token": session.current_access_token,
-                "access_token_exp": session.current_access_token_expires_at.to_rfc3339(),
-                "refresh_token": session.refresh_token,
-                "refresh_token_exp": session.refresh_token_expires_at.to_rfc3339(),
-                "success": true})
-            .to_string(),
-        )
+        .body(json!({"success": true, "message": "email sent"}).to_string())
 }
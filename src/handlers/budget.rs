@@ -0,0 +1,253 @@
+use std::collections::HashMap;
+
+use actix_web::{
+    http::{header::ContentType, StatusCode},
+    web, HttpMessage, HttpRequest, HttpResponse,
+};
+use chrono::{Datelike, FixedOffset, Months, NaiveDate, Utc};
+use serde_json::json;
+use sqlx::Acquire;
+use uuid::Uuid;
+
+use crate::{
+    controllers::{self, auth::get_user},
+    handlers::{
+        macros,
+        util::{build_status_code_for_multiple_input, local_midnight, parse_month},
+    },
+    model::budget::Budget,
+    request_types::budget::{DeleteBudgetReq, ListBudgetsReq, ListBudgetsRes, UpsertBudgetReq},
+    state,
+};
+
+pub async fn upsert_budget(
+    req: HttpRequest,
+    body: web::Json<Vec<UpsertBudgetReq>>,
+    app_state: web::Data<state::AppState>,
+) -> HttpResponse {
+    let ext = req.extensions();
+    let session = macros::get_session!(ext);
+    let mut con = macros::get_database_connection!(app_state);
+    let user = macros::unwrap_opt_or_unauthorize!(macros::run_async_unwrap!(
+        get_user(session.user_email.as_str(), &mut *con,),
+        "an error occurred when tried to get user from database"
+    ));
+
+    let current_month = Utc::now().date_naive().with_day(1).unwrap();
+
+    let mut tx = macros::begin_transaction!(con);
+    let mut not_created: Vec<Uuid> = Vec::new();
+
+    for budget_req in body.iter() {
+        let start_month = match budget_req.start_month.as_ref().map(|m| parse_month(m)) {
+            Some(Ok(m)) => m,
+            Some(Err(_)) => {
+                not_created.push(budget_req.id);
+                continue;
+            }
+            None => current_month,
+        };
+
+        let budget = Budget {
+            id: budget_req.id,
+            user_id: user.id,
+            category_id: budget_req.category_id,
+            subcategory_id: budget_req.subcategory_id,
+            amount: budget_req.amount,
+            rollover: budget_req.rollover.unwrap_or(false),
+            start_month,
+        };
+
+        if budget.amount <= 0 || budget.category_id.is_some() == budget.subcategory_id.is_some() {
+            not_created.push(budget.id);
+            continue;
+        }
+
+        let mut sp = macros::begin_transaction!(tx);
+
+        let references_valid = macros::run_async_or!(
+            controllers::budget::check_budget_references(&budget, &mut *sp),
+            false
+        );
+        if !references_valid {
+            not_created.push(budget.id);
+            continue;
+        }
+
+        // A second active budget for the same category or subcategory is
+        // refused by its unique index
+        macros::run_async_or!(controllers::budget::upsert_budget(&budget, &mut *sp), {
+            not_created.push(budget.id);
+            continue;
+        });
+
+        macros::commit_transaction!(sp);
+    }
+
+    macros::commit_transaction!(tx);
+
+    let mut success = true;
+    let mut message = String::from("ok");
+    let status_code = build_status_code_for_multiple_input(
+        body.len(),
+        not_created.len(),
+        &mut message,
+        &mut success,
+    );
+
+    HttpResponse::build(status_code)
+        .insert_header(ContentType::json())
+        .body(
+            json!({
+                "success": success,
+                "message": message,
+                "errors": not_created
+            })
+            .to_string(),
+        )
+}
+
+pub async fn delete_budget(
+    req: HttpRequest,
+    body: web::Json<Vec<DeleteBudgetReq>>,
+    app_state: web::Data<state::AppState>,
+) -> HttpResponse {
+    let ext = req.extensions();
+    let session = macros::get_session!(ext);
+    let mut con = macros::get_database_connection!(app_state);
+    let user = macros::unwrap_opt_or_unauthorize!(macros::run_async_unwrap!(
+        get_user(session.user_email.as_str(), &mut *con,),
+        "an error occurred when tried to get user from database"
+    ));
+
+    let mut tx = macros::begin_transaction!(con);
+    let mut not_deleted: Vec<Uuid> = Vec::new();
+
+    for req in body.iter() {
+        macros::run_async_or!(
+            controllers::budget::delete_budget(&req.budget_id, user.id, &mut *tx),
+            {
+                not_deleted.push(req.budget_id);
+            }
+        )
+    }
+
+    macros::commit_transaction!(tx);
+
+    let mut success = true;
+    let mut message = String::from("ok");
+    let status_code = build_status_code_for_multiple_input(
+        body.len(),
+        not_deleted.len(),
+        &mut message,
+        &mut success,
+    );
+
+    HttpResponse::build(status_code)
+        .insert_header(ContentType::json())
+        .body(
+            json!({
+                "success": success,
+                "message": message,
+                "errors": not_deleted
+            })
+            .to_string(),
+        )
+}
+
+pub async fn list_budget(
+    req: HttpRequest,
+    query: web::Query<ListBudgetsReq>,
+    app_state: web::Data<state::AppState>,
+) -> HttpResponse {
+    let ext = req.extensions();
+    let session = macros::get_session!(ext);
+
+    let offset = match FixedOffset::east_opt(query.utc_offset.unwrap_or(0)) {
+        Some(o) => o,
+        None => {
+            return HttpResponse::build(StatusCode::BAD_REQUEST)
+                .insert_header(ContentType::json())
+                .body(json!({ "success": false, "message": "invalid utc offset"}).to_string());
+        }
+    };
+
+    let month = match query.month.as_ref().map(|m| parse_month(m)) {
+        Some(Ok(m)) => m,
+        Some(Err(_)) => {
+            return HttpResponse::build(StatusCode::BAD_REQUEST)
+                .insert_header(ContentType::json())
+                .body(json!({ "success": false, "message": "invalid month"}).to_string());
+        }
+        None => Utc::now()
+            .with_timezone(&offset)
+            .date_naive()
+            .with_day(1)
+            .unwrap(),
+    };
+    let next_month = macros::unwrap_opt_or_error!(month.checked_add_months(Months::new(1)));
+
+    let mut con = macros::get_database_connection!(app_state);
+    let user = macros::unwrap_opt_or_unauthorize!(macros::run_async_unwrap!(
+        get_user(session.user_email.as_str(), &mut *con,),
+        "an error occurred when tried to get user from database"
+    ));
+
+    let budgets: Vec<Budget> = macros::run_async_unwrap!(
+        controllers::budget::get_budgets_by_user_id(user.id, &mut *con),
+        "an error occurred when tried to get the budgets from DB"
+    )
+    .into_iter()
+    .filter(|b| b.start_month <= month)
+    .collect();
+
+    // Budgets with rollover need the spending since they started
+    let first_month = budgets
+        .iter()
+        .filter(|b| b.rollover)
+        .map(|b| b.start_month)
+        .min()
+        .unwrap_or(month);
+
+    let spending = macros::run_async_unwrap!(
+        controllers::budget::get_budget_spending(
+            user.id,
+            local_midnight(first_month, offset),
+            local_midnight(next_month, offset),
+            offset.local_minus_utc(),
+            &mut *con
+        ),
+        "an error occurred when tried to get the budget spending from DB"
+    );
+
+    let mut spent_by_budget: HashMap<Uuid, HashMap<NaiveDate, i64>> = HashMap::new();
+    for (budget_id, spent_month, spent) in spending {
+        spent_by_budget
+            .entry(budget_id)
+            .or_default()
+            .insert(spent_month, spent);
+    }
+
+    let mut res: Vec<ListBudgetsRes> = Vec::new();
+    for budget in budgets {
+        let spent_by_month = spent_by_budget.remove(&budget.id).unwrap_or_default();
+        let rolled_over = budget.rolled_over_into(month, &spent_by_month);
+        let spent = spent_by_month.get(&month).copied().unwrap_or(0);
+
+        res.push(ListBudgetsRes {
+            id: budget.id,
+            category_id: budget.category_id,
+            subcategory_id: budget.subcategory_id,
+            start_month: budget.start_month.format("%Y-%m").to_string(),
+            rollover: budget.rollover,
+            limit: budget.amount,
+            rolled_over,
+            spent,
+            remaining: budget.amount + rolled_over - spent,
+        });
+    }
+
+    HttpResponse::build(StatusCode::OK)
+        .insert_header(ContentType::json())
+        .body(json!(res).to_string())
+}
@@ -4,13 +4,17 @@ use actix_web::{
 };
 use serde_json::json;
 use sqlx::Acquire;
+use uuid::Uuid;
 
 use crate::{
     controllers::{self, auth::get_user},
-    handlers::macros,
+    handlers::{macros, util::build_status_code_for_multiple_input},
     model::{category::Category, subcategory::Subcategory},
-    request_types::category::{
-        DeleteCategoryReq, DeleteSubcategoryReq, UpsertCategoryReq, UpsertSubcategoryReq,
+    request_types::{
+        category::{
+            DeleteCategoryReq, DeleteSubcategoryReq, UpsertCategoryReq, UpsertSubcategoryReq,
+        },
+        conflict::ConflictRes,
     },
     state,
 };
@@ -29,6 +33,8 @@ pub async fn upsert_category(
     ));
 
     let mut tx = macros::begin_transaction!(con);
+    let mut not_created: Vec<Uuid> = Vec::new();
+    let mut conflicts: Vec<ConflictRes<Category>> = Vec::new();
 
     for rec in body.iter() {
         let cat = Category {
@@ -37,20 +43,55 @@ pub async fn upsert_category(
             name: rec.name.clone(),
             color: rec.color.clone(),
             icon_name: rec.icon_name.clone(),
+            version: 0,
             subcategories: Vec::new(),
         };
 
-        macros::run_async_unwrap!(
-            controllers::category::upsert_category(&cat, &mut *tx),
-            "an error occurred when tried to create category"
+        let mut sp = macros::begin_transaction!(tx);
+
+        let written = macros::run_async_or!(
+            controllers::category::upsert_category(&cat, rec.version, &mut *sp),
+            {
+                not_created.push(cat.id);
+                continue;
+            }
         );
+        if !written {
+            let server = macros::run_async_or!(
+                controllers::category::get_category_by_id(&cat.id, user.id, &mut *sp),
+                {
+                    not_created.push(cat.id);
+                    continue;
+                }
+            );
+            conflicts.push(ConflictRes { id: cat.id, server });
+        }
+
+        macros::commit_transaction!(sp);
     }
 
     macros::commit_transaction!(tx);
 
-    HttpResponse::build(StatusCode::CREATED)
+    let mut success = true;
+    let mut message = String::from("ok");
+    let status_code = build_status_code_for_multiple_input(
+        body.len(),
+        not_created.len() + conflicts.len(),
+        &mut message,
+        &mut success,
+    );
+
+    HttpResponse::build(status_code)
         .insert_header(ContentType::json())
-        .body(json!({"success": true, "message":"ok"}).to_string())
+        .body(
+            json!({
+                "success": success,
+                "message": message,
+                "errors": not_created,
+                "conflicts": conflicts
+            })
+            .to_string(),
+        )
 }
 
 pub async fn delete_category(
@@ -83,11 +124,21 @@ pub async fn delete_category(
 }
 
 pub async fn upsert_subcategory(
+    req: HttpRequest,
     body: web::Json<Vec<UpsertSubcategoryReq>>,
     app_state: web::Data<state::AppState>,
 ) -> HttpResponse {
+    let ext = req.extensions();
+    let session = macros::get_session!(ext);
     let mut con = macros::get_database_connection!(app_state);
+    let user = macros::unwrap_opt_or_unauthorize!(macros::run_async_unwrap!(
+        get_user(session.user_email.as_str(), &mut *con,),
+        "an error occurred when tried to get user from database"
+    ));
+
     let mut tx = macros::begin_transaction!(con);
+    let mut not_created: Vec<Uuid> = Vec::new();
+    let mut conflicts: Vec<ConflictRes<Subcategory>> = Vec::new();
 
     for rec in body.iter() {
         let sub = Subcategory {
@@ -96,19 +147,68 @@ pub async fn upsert_subcategory(
             name: rec.name.clone(),
             icon_name: rec.icon_name.clone(),
             color: rec.color.clone(),
+            version: 0,
         };
 
-        macros::run_async_unwrap!(
-            controllers::category::upsert_subcategory(&sub, &mut *tx),
-            "an error occurred when tried to upsert subcategory"
+        let mut sp = macros::begin_transaction!(tx);
+
+        // The subcategory is only reachable through its category, so that's
+        // the one that has to belong to the user
+        let category = macros::run_async_or!(
+            controllers::category::get_category_by_id(&sub.category_id, user.id, &mut *sp),
+            {
+                not_created.push(sub.id);
+                continue;
+            }
+        );
+        if category.is_none() {
+            not_created.push(sub.id);
+            continue;
+        }
+
+        let written = macros::run_async_or!(
+            controllers::category::upsert_subcategory(&sub, rec.version, &mut *sp),
+            {
+                not_created.push(sub.id);
+                continue;
+            }
         );
+        if !written {
+            let server = macros::run_async_or!(
+                controllers::category::get_subcategory_by_id(&sub.id, user.id, &mut *sp),
+                {
+                    not_created.push(sub.id);
+                    continue;
+                }
+            );
+            conflicts.push(ConflictRes { id: sub.id, server });
+        }
+
+        macros::commit_transaction!(sp);
     }
 
     macros::commit_transaction!(tx);
 
-    HttpResponse::build(StatusCode::OK)
+    let mut success = true;
+    let mut message = String::from("ok");
+    let status_code = build_status_code_for_multiple_input(
+        body.len(),
+        not_created.len() + conflicts.len(),
+        &mut message,
+        &mut success,
+    );
+
+    HttpResponse::build(status_code)
         .insert_header(ContentType::json())
-        .body(json!({"success": true, "message":"ok"}).to_string())
+        .body(
+            json!({
+                "success": success,
+                "message": message,
+                "errors": not_created,
+                "conflicts": conflicts
+            })
+            .to_string(),
+        )
 }
 
 pub async fn delete_subcategory(
@@ -149,11 +249,11 @@ pub async fn list_category(
     ));
 
     let categories = macros::run_async_unwrap!(
-        controllers::category::get_categories_by_user_id(user.id, &mut *con),
+        controllers::category::get_categories_by_user_id(user.id, None, &mut *con),
         "an error occurred when tried to get the categories from DB"
     );
     let mut subcategories = macros::run_async_unwrap!(
-        controllers::category::get_subcategories_by_user_id(user.id, &mut *con),
+        controllers::category::get_subcategories_by_user_id(user.id, None, &mut *con),
         "an error occurred when tried to get the subcategories from DB"
     );
 
@@ -2,18 +2,25 @@ use actix_web::{
     http::{header::ContentType, StatusCode},
     web, HttpMessage, HttpRequest, HttpResponse,
 };
-use chrono::DateTime;
+use chrono::{DateTime, Utc};
 use serde_json::json;
 use sqlx::Acquire;
 use uuid::Uuid;
 
 use crate::{
     controllers::{self, auth::get_user},
-    handlers::{macros, util::build_status_code_for_multiple_input},
-    model::credit_card::CreditCard,
-    request_types::credit_card::{
-        CreateBillAtDateReq, CreateBillRes, DeleteCreditCardReq, ListCreditCardBillsReq,
-        ListCreditCardsRes, UpsertCreditCardReq,
+    handlers::{
+        macros,
+        util::{build_conflict_response, build_status_code_for_multiple_input},
+    },
+    model::{credit_card::CreditCard, transfer::Transfer},
+    request_types::{
+        conflict::ConflictRes,
+        credit_card::{
+            CreateBillAtDateReq, CreateBillRes, DeleteCreditCardReq, GetCreditCardReq,
+            ListCreditCardBillsReq, ListCreditCardBillsRes, ListCreditCardsRes,
+            PayCreditCardBillReq, UpsertCreditCardReq,
+        },
     },
     state,
 };
@@ -33,6 +40,7 @@ pub async fn upsert_credit_card(
 
     let mut tx = macros::begin_transaction!(con);
     let mut not_created: Vec<Uuid> = Vec::new();
+    let mut conflicts: Vec<ConflictRes<ListCreditCardsRes>> = Vec::new();
 
     for card_req in body.iter() {
         let card = CreditCard {
@@ -42,6 +50,11 @@ pub async fn upsert_credit_card(
             icon_name: card_req.icon_name.clone(),
             limit_value: card_req.limit_value,
             closing_day: card_req.closing_day,
+            due_day: card_req
+                .due_day
+                .unwrap_or_else(|| CreditCard::default_due_day(card_req.closing_day)),
+            used_limit: 0,
+            version: 0,
         };
 
         if card.name.is_empty()
@@ -49,16 +62,37 @@ pub async fn upsert_credit_card(
             || card.limit_value < 0
             || card.closing_day < 0
             || card.closing_day > 31
+            || card.due_day < 1
+            || card.due_day > 31
         {
             not_created.push(card.id.clone());
             continue;
         }
-        macros::run_async_or!(
-            controllers::credit_card::upsert_credit_card(&card, &mut *tx),
+        let written = macros::run_async_or!(
+            controllers::credit_card::upsert_credit_card(
+                &card,
+                card_req.due_day.is_some(),
+                card_req.version,
+                &mut *tx
+            ),
             {
                 not_created.push(card.id.clone());
+                continue;
             }
         );
+        if !written {
+            let server = macros::run_async_or!(
+                controllers::credit_card::get_credit_card_by_id(&card.id, user.id, &mut *tx),
+                {
+                    not_created.push(card.id);
+                    continue;
+                }
+            );
+            conflicts.push(ConflictRes {
+                id: card.id,
+                server: server.map(ListCreditCardsRes::from),
+            });
+        }
     }
 
     macros::commit_transaction!(tx);
@@ -67,7 +101,7 @@ pub async fn upsert_credit_card(
     let mut message = String::from("ok");
     let status_code = build_status_code_for_multiple_input(
         body.len(),
-        not_created.len(),
+        not_created.len() + conflicts.len(),
         &mut message,
         &mut success,
     );
@@ -78,7 +112,8 @@ pub async fn upsert_credit_card(
             json!({
                 "success": success,
                 "message": message,
-                "errors": not_created
+                "errors": not_created,
+                "conflicts": conflicts
             })
             .to_string(),
         )
@@ -144,19 +179,13 @@ pub async fn list_credit_card(
     ));
 
     let cards = macros::run_async_unwrap!(
-        controllers::credit_card::get_credit_cards_by_user_id(user.id, &mut *con),
+        controllers::credit_card::get_credit_cards_by_user_id(user.id, None, &mut *con),
         "an error occurred when tried to get the credit cards from DB"
     );
 
     let mut res: Vec<ListCreditCardsRes> = Vec::new();
     for card in cards {
-        res.push(ListCreditCardsRes {
-            id: card.id.clone(),
-            name: card.name.clone(),
-            icon_name: card.icon_name.clone(),
-            limit_value: card.limit_value,
-            closing_day: card.closing_day,
-        });
+        res.push(ListCreditCardsRes::from(card));
     }
 
     HttpResponse::build(StatusCode::OK)
@@ -164,6 +193,40 @@ pub async fn list_credit_card(
         .body(json!(res).to_string())
 }
 
+pub async fn get_credit_card(
+    req: HttpRequest,
+    query: web::Query<GetCreditCardReq>,
+    app_state: web::Data<state::AppState>,
+) -> HttpResponse {
+    let ext = req.extensions();
+    let session = macros::get_session!(ext);
+    let mut con = macros::get_database_connection!(app_state);
+    let user = macros::unwrap_opt_or_unauthorize!(macros::run_async_unwrap!(
+        get_user(session.user_email.as_str(), &mut *con,),
+        "an error occurred when tried to get user from database"
+    ));
+
+    let has_card = macros::run_async_unwrap!(
+        controllers::credit_card::get_credit_card_by_id(&query.credit_card_id, user.id, &mut *con),
+        "an error occurred when tried to get the credit card from DB"
+    );
+
+    let card = match has_card {
+        Some(c) => c,
+        None => {
+            return HttpResponse::build(StatusCode::NOT_FOUND)
+                .insert_header(ContentType::json())
+                .body(json!({ "success": false, "message": "credit card not found"}).to_string());
+        }
+    };
+
+    let res = ListCreditCardsRes::from(card);
+
+    HttpResponse::build(StatusCode::OK)
+        .insert_header(ContentType::json())
+        .body(json!(res).to_string())
+}
+
 pub async fn create_bill_of_date(
     req: HttpRequest,
     body: web::Json<CreateBillAtDateReq>,
@@ -201,7 +264,7 @@ pub async fn create_bill_of_date(
     };
 
     let bill = macros::run_async_unwrap!(
-        controllers::credit_card::create_bill_of_date(
+        controllers::credit_card::get_or_create_bill_of_date(
             &body.credit_card_id,
             card.closing_day,
             date.date_naive(),
@@ -236,22 +299,129 @@ pub async fn list_credit_card_bills(
         "an error occurred when tried to get user from database"
     ));
 
+    let has_card = macros::run_async_unwrap!(
+        controllers::credit_card::get_credit_card_by_id(&body.credit_card_id, user.id, &mut *con),
+        "an error occurred when tried to check if credit card id exists for user"
+    );
+
+    let card = match has_card {
+        Some(c) => c,
+        None => {
+            return HttpResponse::build(StatusCode::NOT_FOUND)
+                .insert_header(ContentType::json())
+                .body(json!({ "success": false, "message": "credit card not found"}).to_string());
+        }
+    };
+
     let bills = macros::run_async_unwrap!(
         controllers::credit_card::get_credit_card_bills(&body.credit_card_id, user.id, &mut *con),
         "an error occurred when tried to get bills from database"
     );
-    let mut res: Vec<CreateBillRes> = Vec::new();
+    let now = Utc::now();
+    let mut res: Vec<ListCreditCardBillsRes> = Vec::new();
 
     for bill in bills {
-        res.push(CreateBillRes {
-            id: bill.id,
-            credit_card_id: bill.credit_card_id,
-            start_at: bill.start_at.to_rfc3339(),
-            end_at: bill.end_at.to_rfc3339(),
-        });
+        res.push(ListCreditCardBillsRes::new(bill, card.due_day, now));
     }
 
     HttpResponse::build(StatusCode::OK)
         .insert_header(ContentType::json())
         .body(json!(res).to_string())
 }
+
+pub async fn pay_credit_card_bill(
+    req: HttpRequest,
+    body: web::Json<PayCreditCardBillReq>,
+    app_state: web::Data<state::AppState>,
+) -> HttpResponse {
+    let ext = req.extensions();
+    let session = macros::get_session!(ext);
+
+    let date = match DateTime::parse_from_rfc3339(body.date.as_str()) {
+        Ok(d) => d,
+        Err(_) => {
+            return HttpResponse::build(StatusCode::BAD_REQUEST)
+                .insert_header(ContentType::json())
+                .body(json!({ "success": false, "message": "invalid date"}).to_string());
+        }
+    };
+
+    if body.amount <= 0 {
+        return HttpResponse::build(StatusCode::BAD_REQUEST)
+            .insert_header(ContentType::json())
+            .body(json!({ "success": false, "message": "invalid amount"}).to_string());
+    }
+
+    let mut con = macros::get_database_connection!(app_state);
+    let user = macros::unwrap_opt_or_unauthorize!(macros::run_async_unwrap!(
+        get_user(session.user_email.as_str(), &mut *con,),
+        "an error occurred when tried to get user from database"
+    ));
+
+    let mut tx = macros::begin_transaction!(con);
+
+    let updated = macros::run_async_unwrap!(
+        controllers::credit_card::pay_credit_card_bill(
+            &body.credit_card_bill_id,
+            user.id,
+            body.amount,
+            date.to_utc(),
+            &mut *tx,
+        ),
+        "an error occurred when tried to mark the bill as paid"
+    );
+
+    if !updated {
+        let exists = macros::run_async_unwrap!(
+            controllers::credit_card::check_credit_card_bill_exists(
+                &body.credit_card_bill_id,
+                user.id,
+                &mut *tx,
+            ),
+            "an error occurred when tried to get the bill from database"
+        );
+        if exists {
+            return build_conflict_response(Some(String::from("bill already paid")));
+        }
+
+        return HttpResponse::build(StatusCode::NOT_FOUND)
+            .insert_header(ContentType::json())
+            .body(json!({ "success": false, "message": "bill not found"}).to_string());
+    }
+
+    // Paying from an account is recorded as a transfer to the bill, so the
+    // account balance goes down without counting as an expense.
+    if let Some(account_id) = body.account_id {
+        let transfer = Transfer {
+            id: Uuid::new_v4(),
+            user_id: user.id,
+            from_account_id: account_id,
+            to_account_id: None,
+            credit_card_bill_id: Some(body.credit_card_bill_id),
+            amount: body.amount,
+            description: String::from(""),
+            transfer_date: date.to_utc(),
+        };
+
+        let account_valid = macros::run_async_unwrap!(
+            controllers::transfer::check_transfer_accounts(&transfer, &mut *tx),
+            "an error occurred when tried to check the paying account"
+        );
+        if !account_valid {
+            return HttpResponse::build(StatusCode::NOT_FOUND)
+                .insert_header(ContentType::json())
+                .body(json!({ "success": false, "message": "account not found"}).to_string());
+        }
+
+        macros::run_async_unwrap!(
+            controllers::transfer::create_transfer(&transfer, &mut *tx),
+            "an error occurred when tried to create the bill payment transfer"
+        );
+    }
+
+    macros::commit_transaction!(tx);
+
+    HttpResponse::build(StatusCode::OK)
+        .insert_header(ContentType::json())
+        .body(json!({"success": true, "message": "ok"}).to_string())
+}
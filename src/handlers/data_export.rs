@@ -0,0 +1,169 @@
+use std::fs;
+
+use actix_web::{
+    http::{
+        header::{ContentDisposition, ContentType, DispositionParam, DispositionType},
+        StatusCode,
+    },
+    web, HttpMessage, HttpRequest, HttpResponse,
+};
+use chrono::{Duration, Utc};
+use serde_json::json;
+
+use crate::{
+    controllers::{
+        auth::get_user,
+        data_export::{create_data_export, get_data_export_by_id, get_last_data_export},
+    },
+    handlers::{macros, util::build_message_page},
+    jobs::data_export::{archive_path, DATA_EXPORT_TTL_DAYS},
+    jwt::{generate_token_hs256, verify_token_hs256},
+    model::data_export::{DataExport, DataExportStatus},
+    request_types::data_export::DownloadDataExportReq,
+    state,
+};
+
+const DATA_EXPORT_LINK_TTL_MINUTES: i64 = 60;
+
+/// Returns a signed download link for the user's latest archive. When there
+/// is none, or it has expired or failed, a new one is requested and built by
+/// the data export job, so the client should ask again later.
+pub async fn request_data_export(
+    req: HttpRequest,
+    app_state: web::Data<state::AppState>,
+) -> HttpResponse {
+    let ext = req.extensions();
+    let session = macros::get_session!(ext);
+    let mut con = macros::get_database_connection!(app_state);
+    let user = macros::unwrap_opt_or_unauthorize!(macros::run_async_unwrap!(
+        get_user(session.user_email.as_str(), &mut *con,),
+        "an error occurred when tried to get user from database"
+    ));
+
+    let last = macros::run_async_unwrap!(
+        get_last_data_export(user.id, &mut *con),
+        "an error occurred when tried to get the last data export from DB"
+    );
+
+    let now = Utc::now();
+    match last {
+        Some(e) if e.status == DataExportStatus::Pending => {
+            return HttpResponse::build(StatusCode::ACCEPTED)
+                .insert_header(ContentType::json())
+                .body(
+                    json!({
+                        "success": true,
+                        "status": e.status,
+                        "requested_at": e.requested_at.to_rfc3339()
+                    })
+                    .to_string(),
+                );
+        }
+        Some(e) if e.status == DataExportStatus::Ready => {
+            let expires_at =
+                e.completed_at.unwrap_or(e.requested_at) + Duration::days(DATA_EXPORT_TTL_DAYS);
+
+            if expires_at > now {
+                let link_exp =
+                    expires_at.min(now + Duration::minutes(DATA_EXPORT_LINK_TTL_MINUTES));
+                let token = macros::unwrap_res_or_error!(
+                    generate_token_hs256(e.id.to_string().as_str(), link_exp),
+                    "an error occurred while generating the data export token"
+                );
+
+                return HttpResponse::build(StatusCode::OK)
+                    .insert_header(ContentType::json())
+                    .body(
+                        json!({
+                            "success": true,
+                            "status": e.status,
+                            "requested_at": e.requested_at.to_rfc3339(),
+                            "url": format!(
+                                "{}/export/download?t={}",
+                                app_state.public_base_url, token
+                            ),
+                            "url_exp": link_exp.to_rfc3339(),
+                            "expires_at": expires_at.to_rfc3339()
+                        })
+                        .to_string(),
+                    );
+            }
+        }
+        _ => (),
+    }
+
+    let export = DataExport::new(user.id);
+    macros::run_async_unwrap!(
+        create_data_export(&export, &mut *con),
+        "an error occurred when tried to insert the data export on the database"
+    );
+
+    HttpResponse::build(StatusCode::ACCEPTED)
+        .insert_header(ContentType::json())
+        .body(
+            json!({
+                "success": true,
+                "status": export.status,
+                "requested_at": export.requested_at.to_rfc3339()
+            })
+            .to_string(),
+        )
+}
+
+/// Serves the archive behind a link from `request_data_export`. It's opened
+/// from outside the app, so the signed token replaces the session.
+pub async fn download_data_export(
+    token: web::Query<DownloadDataExportReq>,
+    app_state: web::Data<state::AppState>,
+) -> HttpResponse {
+    let claims = match verify_token_hs256(token.t.as_str()) {
+        Ok(c) => c,
+        Err(e) => {
+            log::warn!(
+                "Error while trying to verify token claims for data export: {}",
+                e
+            );
+            return build_message_page(
+                StatusCode::UNAUTHORIZED,
+                "N&atilde;o autorizado",
+                "Link expirado. Solicite uma nova exporta&ccedil;&atilde;o pelo app.",
+            );
+        }
+    };
+
+    let export_id = macros::uuid_from_str!(claims.sub.as_str());
+    let mut con = macros::get_database_connection!(app_state);
+
+    let export = macros::run_async_unwrap!(
+        get_data_export_by_id(&export_id, &mut *con),
+        "an error occurred when tried to get the data export from DB"
+    );
+
+    let archive = match export {
+        Some(e) if e.status == DataExportStatus::Ready => {
+            fs::read(archive_path(app_state.data_export_dir.as_path(), &e.id)).ok()
+        }
+        _ => None,
+    };
+    let archive = match archive {
+        Some(a) => a,
+        None => {
+            return build_message_page(
+                StatusCode::NOT_FOUND,
+                "Exporta&ccedil;&atilde;o n&atilde;o encontrada",
+                "Exporta&ccedil;&atilde;o expirada. Solicite uma nova pelo app.",
+            );
+        }
+    };
+
+    HttpResponse::build(StatusCode::OK)
+        .content_type("application/zip")
+        .insert_header(ContentDisposition {
+            disposition: DispositionType::Attachment,
+            parameters: vec![DispositionParam::Filename(format!(
+                "finly-export-{}.zip",
+                Utc::now().format("%Y-%m-%d")
+            ))],
+        })
+        .body(archive)
+}
@@ -1,9 +1,19 @@
+pub mod account;
 pub mod auth;
+pub mod budget;
 pub mod category;
 pub mod credit_card;
+pub mod data_export;
 pub mod html;
 pub mod macros;
+pub mod recurring_transaction;
+pub mod report;
 pub mod reset_password;
 pub mod session_mgm;
 pub mod static_content;
+pub mod sync;
+pub mod transaction;
+pub mod transfer;
+pub mod two_factor;
+pub mod user;
 pub mod util;
@@ -0,0 +1,380 @@
+use actix_web::{
+    http::{header::ContentType, StatusCode},
+    web, HttpMessage, HttpRequest, HttpResponse,
+};
+use chrono::{DateTime, Utc};
+use serde_json::json;
+use sqlx::Acquire;
+use uuid::Uuid;
+
+use crate::{
+    controllers::{self, auth::get_user},
+    handlers::{
+        macros,
+        util::{build_status_code_for_multiple_input, parse_optional_date},
+    },
+    model::recurring_transaction::RecurringTransaction,
+    request_types::recurring_transaction::{
+        DeleteRecurringTransactionReq, ListRecurringTransactionsRes, RecurringOccurrenceReq,
+        UpsertRecurringTransactionReq,
+    },
+    state,
+};
+
+pub async fn upsert_recurring_transaction(
+    req: HttpRequest,
+    body: web::Json<Vec<UpsertRecurringTransactionReq>>,
+    app_state: web::Data<state::AppState>,
+) -> HttpResponse {
+    let ext = req.extensions();
+    let session = macros::get_session!(ext);
+    let mut con = macros::get_database_connection!(app_state);
+    let user = macros::unwrap_opt_or_unauthorize!(macros::run_async_unwrap!(
+        get_user(session.user_email.as_str(), &mut *con,),
+        "an error occurred when tried to get user from database"
+    ));
+
+    let mut tx = macros::begin_transaction!(con);
+    let mut not_created: Vec<Uuid> = Vec::new();
+
+    for rule_req in body.iter() {
+        let (start_at, end_at) = match (
+            DateTime::parse_from_rfc3339(rule_req.start_date.as_str()),
+            parse_optional_date(&rule_req.end_date),
+        ) {
+            (Ok(s), Ok(e)) => (s, e),
+            _ => {
+                not_created.push(rule_req.id);
+                continue;
+            }
+        };
+
+        let mut rule = RecurringTransaction {
+            id: rule_req.id,
+            user_id: user.id,
+            kind: rule_req.kind.clone(),
+            amount: rule_req.amount,
+            description: rule_req.description.clone(),
+            category_id: rule_req.category_id,
+            subcategory_id: rule_req.subcategory_id,
+            credit_card_id: rule_req.credit_card_id,
+            account_id: rule_req.account_id,
+            frequency: rule_req.frequency.clone(),
+            frequency_interval: rule_req.interval.unwrap_or(1),
+            start_at,
+            end_at,
+            occurrence_limit: rule_req.occurrence_limit,
+            next_occurrence_number: 0,
+        };
+
+        if rule.amount <= 0
+            || rule.description.is_empty()
+            || rule.frequency_interval < 1
+            || rule.occurrence_limit.is_some_and(|l| l < 1)
+            || rule.end_at.is_some_and(|e| e < rule.start_at.to_utc())
+            || (rule.credit_card_id.is_some() && rule.account_id.is_some())
+        {
+            not_created.push(rule.id);
+            continue;
+        }
+
+        let references_valid = macros::run_async_or!(
+            controllers::transaction::check_transaction_references(
+                &rule.occurrence(0, rule.start_at.to_utc()),
+                &mut *tx
+            ),
+            false
+        );
+        if !references_valid {
+            not_created.push(rule.id);
+            continue;
+        }
+
+        rule.next_occurrence_number = macros::run_async_or!(
+            controllers::recurring_transaction::upsert_recurring_transaction(&rule, &mut *tx),
+            {
+                not_created.push(rule.id);
+                continue;
+            }
+        );
+
+        macros::run_async_or!(
+            controllers::recurring_transaction::materialize_due_occurrences(
+                &rule,
+                Utc::now(),
+                &mut tx
+            ),
+            {
+                not_created.push(rule.id);
+                continue;
+            }
+        );
+    }
+
+    macros::commit_transaction!(tx);
+
+    let mut success = true;
+    let mut message = String::from("ok");
+    let status_code = build_status_code_for_multiple_input(
+        body.len(),
+        not_created.len(),
+        &mut message,
+        &mut success,
+    );
+
+    HttpResponse::build(status_code)
+        .insert_header(ContentType::json())
+        .body(
+            json!({
+                "success": success,
+                "message": message,
+                "errors": not_created
+            })
+            .to_string(),
+        )
+}
+
+pub async fn delete_recurring_transaction(
+    req: HttpRequest,
+    body: web::Json<Vec<DeleteRecurringTransactionReq>>,
+    app_state: web::Data<state::AppState>,
+) -> HttpResponse {
+    let ext = req.extensions();
+    let session = macros::get_session!(ext);
+    let mut con = macros::get_database_connection!(app_state);
+    let user = macros::unwrap_opt_or_unauthorize!(macros::run_async_unwrap!(
+        get_user(session.user_email.as_str(), &mut *con,),
+        "an error occurred when tried to get user from database"
+    ));
+
+    let mut tx = macros::begin_transaction!(con);
+    let mut not_deleted: Vec<Uuid> = Vec::new();
+
+    for req in body.iter() {
+        macros::run_async_or!(
+            controllers::recurring_transaction::stop_recurring_transaction(
+                &req.recurring_transaction_id,
+                user.id,
+                &mut *tx
+            ),
+            {
+                not_deleted.push(req.recurring_transaction_id);
+            }
+        )
+    }
+
+    macros::commit_transaction!(tx);
+
+    let mut success = true;
+    let mut message = String::from("ok");
+    let status_code = build_status_code_for_multiple_input(
+        body.len(),
+        not_deleted.len(),
+        &mut message,
+        &mut success,
+    );
+
+    HttpResponse::build(status_code)
+        .insert_header(ContentType::json())
+        .body(
+            json!({
+                "success": success,
+                "message": message,
+                "errors": not_deleted
+            })
+            .to_string(),
+        )
+}
+
+pub async fn list_recurring_transaction(
+    req: HttpRequest,
+    app_state: web::Data<state::AppState>,
+) -> HttpResponse {
+    let ext = req.extensions();
+    let session = macros::get_session!(ext);
+    let mut con = macros::get_database_connection!(app_state);
+    let user = macros::unwrap_opt_or_unauthorize!(macros::run_async_unwrap!(
+        get_user(session.user_email.as_str(), &mut *con,),
+        "an error occurred when tried to get user from database"
+    ));
+
+    let rules = macros::run_async_unwrap!(
+        controllers::recurring_transaction::get_recurring_transactions_by_user_id(
+            user.id, &mut *con
+        ),
+        "an error occurred when tried to get the recurring transactions from DB"
+    );
+
+    let mut res: Vec<ListRecurringTransactionsRes> = Vec::new();
+    for rule in rules {
+        let next_occurrence_date = rule
+            .occurrence_date(rule.next_occurrence_number)
+            .map(|d| d.to_rfc3339());
+
+        res.push(ListRecurringTransactionsRes {
+            id: rule.id,
+            kind: rule.kind,
+            amount: rule.amount,
+            description: rule.description,
+            category_id: rule.category_id,
+            subcategory_id: rule.subcategory_id,
+            credit_card_id: rule.credit_card_id,
+            account_id: rule.account_id,
+            frequency: rule.frequency,
+            interval: rule.frequency_interval,
+            start_date: rule.start_at.to_rfc3339(),
+            end_date: rule.end_at.map(|d| d.to_rfc3339()),
+            occurrence_limit: rule.occurrence_limit,
+            next_occurrence_number: rule.next_occurrence_number,
+            next_occurrence_date,
+        });
+    }
+
+    HttpResponse::build(StatusCode::OK)
+        .insert_header(ContentType::json())
+        .body(json!(res).to_string())
+}
+
+/// Materializes a single occurrence ahead of time and returns its
+/// transaction, so it can be edited through /transaction without touching
+/// the rest of the series.
+pub async fn materialize_recurring_occurrence(
+    req: HttpRequest,
+    body: web::Json<RecurringOccurrenceReq>,
+    app_state: web::Data<state::AppState>,
+) -> HttpResponse {
+    let ext = req.extensions();
+    let session = macros::get_session!(ext);
+    let mut con = macros::get_database_connection!(app_state);
+    let user = macros::unwrap_opt_or_unauthorize!(macros::run_async_unwrap!(
+        get_user(session.user_email.as_str(), &mut *con,),
+        "an error occurred when tried to get user from database"
+    ));
+
+    let mut tx = macros::begin_transaction!(con);
+
+    let rule = macros::run_async_unwrap!(
+        controllers::recurring_transaction::get_recurring_transaction_by_id(
+            &body.recurring_transaction_id,
+            user.id,
+            &mut *tx
+        ),
+        "an error occurred when tried to get the recurring transaction from DB"
+    );
+
+    let transaction_id = match rule {
+        Some(rule) => macros::run_async_unwrap!(
+            controllers::recurring_transaction::materialize_occurrence(
+                &rule,
+                body.occurrence_number,
+                &mut tx
+            ),
+            "an error occurred when tried to materialize the occurrence"
+        ),
+        None => None,
+    };
+
+    let transaction_id = match transaction_id {
+        Some(id) => id,
+        None => {
+            return HttpResponse::build(StatusCode::NOT_FOUND)
+                .insert_header(ContentType::json())
+                .body(json!({ "success": false, "message": "occurrence not found"}).to_string());
+        }
+    };
+
+    macros::commit_transaction!(tx);
+
+    HttpResponse::build(StatusCode::CREATED)
+        .insert_header(ContentType::json())
+        .body(
+            json!({
+                "success": true,
+                "message": "ok",
+                "transaction_id": transaction_id
+            })
+            .to_string(),
+        )
+}
+
+pub async fn skip_recurring_occurrence(
+    req: HttpRequest,
+    body: web::Json<Vec<RecurringOccurrenceReq>>,
+    app_state: web::Data<state::AppState>,
+) -> HttpResponse {
+    let ext = req.extensions();
+    let session = macros::get_session!(ext);
+    let mut con = macros::get_database_connection!(app_state);
+    let user = macros::unwrap_opt_or_unauthorize!(macros::run_async_unwrap!(
+        get_user(session.user_email.as_str(), &mut *con,),
+        "an error occurred when tried to get user from database"
+    ));
+
+    let mut tx = macros::begin_transaction!(con);
+    let mut not_skipped: Vec<RecurringOccurrenceReq> = Vec::new();
+
+    for req in body.iter() {
+        let rule = macros::run_async_or!(
+            controllers::recurring_transaction::get_recurring_transaction_by_id(
+                &req.recurring_transaction_id,
+                user.id,
+                &mut *tx
+            ),
+            None
+        );
+        let rule = match rule {
+            Some(r) => r,
+            None => {
+                not_skipped.push(req.clone());
+                continue;
+            }
+        };
+
+        // The skipped occurrence is kept as an inactive transaction, so the
+        // job won't materialize it again.
+        let transaction_id = macros::run_async_or!(
+            controllers::recurring_transaction::materialize_occurrence(
+                &rule,
+                req.occurrence_number,
+                &mut tx
+            ),
+            None
+        );
+        let transaction_id = match transaction_id {
+            Some(id) => id,
+            None => {
+                not_skipped.push(req.clone());
+                continue;
+            }
+        };
+
+        macros::run_async_or!(
+            controllers::transaction::delete_transaction(&transaction_id, user.id, &mut *tx),
+            {
+                not_skipped.push(req.clone());
+            }
+        );
+    }
+
+    macros::commit_transaction!(tx);
+
+    let mut success = true;
+    let mut message = String::from("ok");
+    let status_code = build_status_code_for_multiple_input(
+        body.len(),
+        not_skipped.len(),
+        &mut message,
+        &mut success,
+    );
+
+    HttpResponse::build(status_code)
+        .insert_header(ContentType::json())
+        .body(
+            json!({
+                "success": success,
+                "message": message,
+                "errors": not_skipped
+            })
+            .to_string(),
+        )
+}
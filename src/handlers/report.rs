@@ -0,0 +1,166 @@
+use actix_web::{
+    http::{header::ContentType, StatusCode},
+    web, HttpMessage, HttpRequest, HttpResponse,
+};
+use std::collections::HashMap;
+
+use chrono::{DateTime, Datelike, Days, FixedOffset, Months, NaiveDate, Utc};
+use serde_json::json;
+
+use crate::{
+    controllers::{self, auth::get_user},
+    handlers::{macros, util::local_midnight},
+    model::report::{CashflowMonth, MAX_CASHFLOW_MONTHS},
+    request_types::report::{CashflowReq, ReportByCategoryReq},
+    state,
+};
+
+pub async fn report_by_category(
+    req: HttpRequest,
+    query: web::Query<ReportByCategoryReq>,
+    app_state: web::Data<state::AppState>,
+) -> HttpResponse {
+    let ext = req.extensions();
+    let session = macros::get_session!(ext);
+
+    let (from, to) = match (
+        DateTime::parse_from_rfc3339(query.from.as_str()),
+        DateTime::parse_from_rfc3339(query.to.as_str()),
+    ) {
+        (Ok(f), Ok(t)) if f.date_naive() <= t.date_naive() => (f, t),
+        _ => {
+            return HttpResponse::build(StatusCode::BAD_REQUEST)
+                .insert_header(ContentType::json())
+                .body(json!({ "success": false, "message": "invalid date"}).to_string());
+        }
+    };
+
+    let from_utc = local_midnight(from.date_naive(), *from.offset());
+    let to_utc = local_midnight(
+        macros::unwrap_opt_or_error!(to.date_naive().checked_add_days(Days::new(1))),
+        *to.offset(),
+    );
+
+    let mut con = macros::get_database_connection!(app_state);
+    let user = macros::unwrap_opt_or_unauthorize!(macros::run_async_unwrap!(
+        get_user(session.user_email.as_str(), &mut *con,),
+        "an error occurred when tried to get user from database"
+    ));
+
+    let totals = macros::run_async_unwrap!(
+        controllers::report::get_totals_by_category(user.id, from_utc, to_utc, &mut *con),
+        "an error occurred when tried to get the category totals from DB"
+    );
+
+    HttpResponse::build(StatusCode::OK)
+        .insert_header(ContentType::json())
+        .body(json!(totals).to_string())
+}
+
+pub async fn report_cashflow(
+    req: HttpRequest,
+    query: web::Query<CashflowReq>,
+    app_state: web::Data<state::AppState>,
+) -> HttpResponse {
+    let ext = req.extensions();
+    let session = macros::get_session!(ext);
+
+    let months_count = query.months.unwrap_or(12);
+    let offset = match FixedOffset::east_opt(query.utc_offset.unwrap_or(0)) {
+        Some(o) if (1..=MAX_CASHFLOW_MONTHS).contains(&months_count) => o,
+        _ => {
+            return HttpResponse::build(StatusCode::BAD_REQUEST)
+                .insert_header(ContentType::json())
+                .body(json!({ "success": false, "message": "invalid parameters"}).to_string());
+        }
+    };
+    let by_bill_due = query.by_bill_due.unwrap_or(false);
+
+    let current_month = Utc::now()
+        .with_timezone(&offset)
+        .date_naive()
+        .with_day(1)
+        .unwrap();
+    let first_month = macros::unwrap_opt_or_error!(
+        current_month.checked_sub_months(Months::new(months_count - 1))
+    );
+    let months: Vec<NaiveDate> = (0..months_count)
+        .map(|i| first_month + Months::new(i))
+        .collect();
+
+    let from_utc = local_midnight(first_month, offset);
+    let to_utc = local_midnight(current_month + Months::new(1), offset);
+
+    let mut con = macros::get_database_connection!(app_state);
+    let user = macros::unwrap_opt_or_unauthorize!(macros::run_async_unwrap!(
+        get_user(session.user_email.as_str(), &mut *con,),
+        "an error occurred when tried to get user from database"
+    ));
+
+    let mut totals: HashMap<NaiveDate, (i64, i64)> = HashMap::new();
+
+    let monthly_totals = macros::run_async_unwrap!(
+        controllers::report::get_monthly_totals(
+            user.id,
+            from_utc,
+            to_utc,
+            offset.local_minus_utc(),
+            by_bill_due,
+            &mut *con
+        ),
+        "an error occurred when tried to get the monthly totals from DB"
+    );
+    for (month, income, expense) in monthly_totals {
+        totals.insert(month, (income, expense));
+    }
+
+    if by_bill_due {
+        // A bill is due at most a couple of months after it opens
+        let bills_from = local_midnight(first_month - Months::new(2), offset);
+        let bill_totals = macros::run_async_unwrap!(
+            controllers::report::get_bill_totals(user.id, bills_from, to_utc, &mut *con),
+            "an error occurred when tried to get the bill totals from DB"
+        );
+
+        for (due_date, income, expense) in bill_totals {
+            let due_month = due_date.with_day(1).unwrap();
+            if due_month < first_month || due_month > current_month {
+                continue;
+            }
+
+            let total = totals.entry(due_month).or_insert((0, 0));
+            total.0 += income;
+            total.1 += expense;
+        }
+    }
+
+    let initial_balance = macros::run_async_unwrap!(
+        controllers::report::get_accounts_initial_balance(user.id, &mut *con),
+        "an error occurred when tried to get the accounts initial balance from DB"
+    );
+    let balance_changes = macros::run_async_unwrap!(
+        controllers::report::get_monthly_balance_changes(
+            user.id,
+            to_utc,
+            offset.local_minus_utc(),
+            &mut *con
+        ),
+        "an error occurred when tried to get the account balance changes from DB"
+    );
+
+    let mut opening_balance = initial_balance;
+    let mut changes: HashMap<NaiveDate, i64> = HashMap::new();
+    for (month, change) in balance_changes {
+        if month < first_month {
+            opening_balance += change;
+        } else {
+            changes.insert(month, change);
+        }
+    }
+
+    let res = CashflowMonth::series(&months, &totals, opening_balance, &changes);
+
+    HttpResponse::build(StatusCode::OK)
+        .insert_header(ContentType::json())
+        .body(json!(res).to_string())
+}
@@ -4,7 +4,7 @@ use actix_files::NamedFile;
 use actix_web::{
     http::{header::ContentType, StatusCode},
     web::{self},
-    HttpResponse,
+    HttpRequest, HttpResponse,
 };
 use serde_json::json;
 
@@ -15,8 +15,8 @@ use crate::{
             self, check_reset_password_id, get_reset_password_email_valid_id,
             toggle_reset_password_flag,
         },
-        ses::send_reset_password_email,
     },
+    email::{Email, EmailTemplate},
     jwt::{generate_token_hs256, verify_token_hs256},
     request_types::reset_password::{
         CreateResetPasswordReq, DoResetPasswordReq, ResetPasswordFormReq,
@@ -24,12 +24,27 @@ use crate::{
     state,
 };
 
-use super::{macros, util::build_conflict_response};
+use super::{
+    macros,
+    util::{build_conflict_response, build_too_many_requests, client_ip},
+};
 
 pub async fn create_reset_password_request(
+    http_req: HttpRequest,
     req: web::Json<CreateResetPasswordReq>,
     app_state: web::Data<state::AppState>,
 ) -> HttpResponse {
+    // Throttled before checking the email, so it doesn't tell which exist
+    let retry_at = macros::run_async_unwrap!(
+        app_state
+            .throttle
+            .check_reset_request(req.email.as_str(), client_ip(&http_req).as_str()),
+        "an error occurred when tried to check the reset password requests"
+    );
+    if let Some(retry_at) = retry_at {
+        return build_too_many_requests("too many reset password requests", retry_at);
+    }
+
     let mut con = macros::get_database_connection!(app_state);
     let email_exists = macros::run_async_unwrap!(
         check_email_exists(req.email.as_str(), &mut *con),
@@ -73,7 +88,11 @@ pub async fn create_reset_password_request(
     //TODO - send password reset link
     let reset_password_link = format!("https://192.168.1.19:3000/password/reset?t={}", token);
     macros::run_async_unwrap!(
-        send_reset_password_email(req.email.as_str(), reset_password_link.as_str()),
+        app_state.email_sender.send(&Email::new(
+            req.email.as_str(),
+            EmailTemplate::ResetPassword,
+            reset_password_link.as_str()
+        )),
         "an error occurred when tried to send the reset password link throught email"
     );
 
@@ -3,8 +3,16 @@ use actix_web::{
     web, HttpMessage, HttpRequest, HttpResponse,
 };
 use serde_json::json;
+use uuid::Uuid;
 
-use crate::{controllers::session_mgm::delete_session_by_id, handlers::macros, state};
+use crate::{
+    controllers::session_mgm::{
+        delete_session_by_id, delete_user_session, get_sessions_by_user_email,
+    },
+    handlers::macros,
+    request_types::session_mgm::ListSessionsRes,
+    state,
+};
 
 pub async fn ping(req: HttpRequest) -> HttpResponse {
     let ext = req.extensions();
@@ -35,3 +43,51 @@ pub async fn logout_user(req: HttpRequest, app_state: web::Data<state::AppState>
         .insert_header(ContentType::json())
         .body(json!({"success": true}).to_string())
 }
+
+pub async fn list_sessions(
+    req: HttpRequest,
+    app_state: web::Data<state::AppState>,
+) -> HttpResponse {
+    let ext = req.extensions();
+    let session = macros::get_session!(ext);
+    let mut con = macros::get_database_connection!(app_state);
+
+    let sessions = macros::run_async_unwrap!(
+        get_sessions_by_user_email(&mut *con, session.user_email.as_str()),
+        "an error occurred when tried to get the sessions from DB"
+    );
+
+    let res: Vec<ListSessionsRes> = sessions
+        .into_iter()
+        .map(|s| ListSessionsRes::new(s, &session.id))
+        .collect();
+
+    HttpResponse::build(StatusCode::OK)
+        .insert_header(ContentType::json())
+        .body(json!(res).to_string())
+}
+
+pub async fn revoke_session(
+    req: HttpRequest,
+    path: web::Path<Uuid>,
+    app_state: web::Data<state::AppState>,
+) -> HttpResponse {
+    let ext = req.extensions();
+    let session = macros::get_session!(ext);
+    let mut con = macros::get_database_connection!(app_state);
+
+    let deleted = macros::run_async_unwrap!(
+        delete_user_session(&mut *con, &path, session.user_email.as_str()),
+        "an error occurred when tried to delete session"
+    );
+
+    if !deleted {
+        return HttpResponse::build(StatusCode::NOT_FOUND)
+            .insert_header(ContentType::json())
+            .body(json!({ "success": false, "message": "session not found"}).to_string());
+    }
+
+    HttpResponse::build(StatusCode::OK)
+        .insert_header(ContentType::json())
+        .body(json!({"success": true}).to_string())
+}
@@ -0,0 +1,118 @@
+use actix_web::{
+    http::{header::ContentType, StatusCode},
+    web, HttpMessage, HttpRequest, HttpResponse,
+};
+use chrono::{SecondsFormat, TimeDelta, Utc};
+use serde_json::json;
+
+use crate::{
+    controllers::{self, auth::get_user},
+    handlers::{macros, util::parse_optional_date},
+    request_types::{
+        account::ListAccountsRes,
+        credit_card::{ListCreditCardBillsRes, ListCreditCardsRes},
+        sync::{DeletedRes, SyncReq, SyncRes},
+        transaction::ListTransactionsRes,
+        transfer::ListTransfersRes,
+    },
+    state,
+};
+
+/// The returned cursor lags behind the server clock, so rows written by
+/// requests still in flight when the sync ran are sent again next time
+/// instead of being missed. Clients apply changes by id, so repeats are
+/// harmless.
+const SYNC_CURSOR_LAG_SECS: i64 = 60;
+
+pub async fn sync(
+    req: HttpRequest,
+    query: web::Query<SyncReq>,
+    app_state: web::Data<state::AppState>,
+) -> HttpResponse {
+    let ext = req.extensions();
+    let session = macros::get_session!(ext);
+
+    let since = match parse_optional_date(&query.since) {
+        Ok(s) => s,
+        Err(_) => {
+            return HttpResponse::build(StatusCode::BAD_REQUEST)
+                .insert_header(ContentType::json())
+                .body(json!({ "success": false, "message": "invalid cursor"}).to_string());
+        }
+    };
+    let now = Utc::now();
+    let cursor = now - TimeDelta::seconds(SYNC_CURSOR_LAG_SECS);
+
+    let mut con = macros::get_database_connection!(app_state);
+    let user = macros::unwrap_opt_or_unauthorize!(macros::run_async_unwrap!(
+        get_user(session.user_email.as_str(), &mut *con,),
+        "an error occurred when tried to get user from database"
+    ));
+
+    let categories = macros::run_async_unwrap!(
+        controllers::category::get_categories_by_user_id(user.id, since, &mut *con),
+        "an error occurred when tried to get the categories from DB"
+    );
+    let subcategories = macros::run_async_unwrap!(
+        controllers::category::get_subcategories_by_user_id(user.id, since, &mut *con),
+        "an error occurred when tried to get the subcategories from DB"
+    );
+    let credit_cards = macros::run_async_unwrap!(
+        controllers::credit_card::get_credit_cards_by_user_id(user.id, since, &mut *con),
+        "an error occurred when tried to get the credit cards from DB"
+    );
+    let credit_card_bills = macros::run_async_unwrap!(
+        controllers::credit_card::get_credit_card_bills_by_user_id(user.id, since, &mut *con),
+        "an error occurred when tried to get the credit card bills from DB"
+    );
+    let transactions = macros::run_async_unwrap!(
+        controllers::transaction::get_transactions_by_user_id(
+            user.id, None, None, since, &mut *con
+        ),
+        "an error occurred when tried to get the transactions from DB"
+    );
+    let accounts = macros::run_async_unwrap!(
+        controllers::account::get_accounts_by_user_id(user.id, since, &mut *con),
+        "an error occurred when tried to get the accounts from DB"
+    );
+    let transfers = macros::run_async_unwrap!(
+        controllers::transfer::get_transfers_by_user_id(user.id, since, &mut *con),
+        "an error occurred when tried to get the transfers from DB"
+    );
+
+    let deleted = match since {
+        Some(s) => macros::run_async_unwrap!(
+            controllers::sync::get_deleted_since(user.id, s, &mut *con),
+            "an error occurred when tried to get the deleted items from DB"
+        ),
+        None => Vec::new(),
+    };
+
+    let res = SyncRes {
+        cursor: cursor.to_rfc3339_opts(SecondsFormat::Micros, true),
+        categories,
+        subcategories: subcategories.into_values().flatten().collect(),
+        credit_cards: credit_cards
+            .into_iter()
+            .map(ListCreditCardsRes::from)
+            .collect(),
+        credit_card_bills: credit_card_bills
+            .into_iter()
+            .map(|(bill, due_day)| ListCreditCardBillsRes::new(bill, due_day, now))
+            .collect(),
+        transactions: transactions
+            .into_iter()
+            .map(ListTransactionsRes::from)
+            .collect(),
+        accounts: accounts.into_iter().map(ListAccountsRes::from).collect(),
+        transfers: transfers.into_iter().map(ListTransfersRes::from).collect(),
+        deleted: deleted
+            .into_iter()
+            .map(|(kind, id)| DeletedRes { kind, id })
+            .collect(),
+    };
+
+    HttpResponse::build(StatusCode::OK)
+        .insert_header(ContentType::json())
+        .body(json!(res).to_string())
+}
@@ -0,0 +1,290 @@
+use actix_web::{
+    http::{header::ContentType, StatusCode},
+    web, HttpMessage, HttpRequest, HttpResponse,
+};
+use chrono::{DateTime, Months};
+use serde_json::json;
+use sqlx::Acquire;
+use uuid::Uuid;
+
+use crate::{
+    controllers::{self, auth::get_user},
+    handlers::{
+        macros,
+        util::{build_status_code_for_multiple_input, parse_optional_date},
+    },
+    model::transaction::{Transaction, MAX_INSTALLMENTS},
+    request_types::transaction::{
+        DeleteTransactionReq, ListTransactionsReq, ListTransactionsRes, UpsertTransactionReq,
+    },
+    state,
+};
+
+pub async fn upsert_transaction(
+    req: HttpRequest,
+    body: web::Json<Vec<UpsertTransactionReq>>,
+    app_state: web::Data<state::AppState>,
+) -> HttpResponse {
+    let ext = req.extensions();
+    let session = macros::get_session!(ext);
+    let mut con = macros::get_database_connection!(app_state);
+    let user = macros::unwrap_opt_or_unauthorize!(macros::run_async_unwrap!(
+        get_user(session.user_email.as_str(), &mut *con,),
+        "an error occurred when tried to get user from database"
+    ));
+
+    let mut tx = macros::begin_transaction!(con);
+    let mut not_created: Vec<Uuid> = Vec::new();
+
+    'requests: for transaction_req in body.iter() {
+        let date = match DateTime::parse_from_rfc3339(transaction_req.date.as_str()) {
+            Ok(d) => d,
+            Err(_) => {
+                not_created.push(transaction_req.id);
+                continue;
+            }
+        };
+
+        let installment_count = transaction_req.installments.unwrap_or(1);
+        let mut transaction = Transaction {
+            id: transaction_req.id,
+            user_id: user.id,
+            kind: transaction_req.kind.clone(),
+            amount: transaction_req.amount,
+            description: transaction_req.description.clone(),
+            transaction_date: date.to_utc(),
+            category_id: transaction_req.category_id,
+            subcategory_id: transaction_req.subcategory_id,
+            credit_card_id: transaction_req.credit_card_id,
+            credit_card_bill_id: None,
+            account_id: transaction_req.account_id,
+            parent_transaction_id: None,
+            installment_number: 1,
+            installment_count,
+            recurring_transaction_id: None,
+            occurrence_number: None,
+        };
+
+        if transaction.amount <= 0
+            || transaction.description.is_empty()
+            || !(1..=MAX_INSTALLMENTS).contains(&installment_count)
+            || transaction.amount < installment_count as i64
+            || (installment_count > 1 && transaction.credit_card_id.is_none())
+            || (transaction.credit_card_id.is_some() && transaction.account_id.is_some())
+        {
+            not_created.push(transaction.id);
+            continue;
+        }
+
+        // A failed write aborts the transaction, so each item gets a
+        // savepoint and only its own writes are rolled back
+        let mut sp = macros::begin_transaction!(tx);
+
+        let references_valid = macros::run_async_or!(
+            controllers::transaction::check_transaction_references(&transaction, &mut *sp),
+            false
+        );
+        if !references_valid {
+            not_created.push(transaction.id);
+            continue;
+        }
+
+        let mut installments: Vec<Transaction> = Vec::new();
+        if let Some(credit_card_id) = transaction.credit_card_id {
+            let card = macros::run_async_or!(
+                controllers::credit_card::get_credit_card_by_id(&credit_card_id, user.id, &mut *sp),
+                None
+            );
+            let card = match card {
+                Some(c) => c,
+                None => {
+                    not_created.push(transaction.id);
+                    continue;
+                }
+            };
+
+            let amounts = Transaction::split_installments(transaction.amount, installment_count);
+            let mut bill_date = date.date_naive();
+            for (i, amount) in amounts.into_iter().enumerate() {
+                let installment_date = match date.checked_add_months(Months::new(i as u32)) {
+                    Some(d) => d,
+                    None => {
+                        not_created.push(transaction.id);
+                        continue 'requests;
+                    }
+                };
+
+                let bill = macros::run_async_or!(
+                    controllers::credit_card::get_or_create_bill_of_date(
+                        &credit_card_id,
+                        card.closing_day,
+                        bill_date,
+                        *installment_date.offset(),
+                        &mut *sp,
+                    ),
+                    {
+                        not_created.push(transaction.id);
+                        continue 'requests;
+                    }
+                );
+                // Each installment goes to the bill after the previous one's
+                bill_date = bill.next_window_date();
+
+                if installment_count == 1 {
+                    transaction.credit_card_bill_id = Some(bill.id);
+                } else {
+                    installments.push(transaction.installment_of(
+                        i as i16 + 1,
+                        amount,
+                        installment_date.to_utc(),
+                        bill.id,
+                    ));
+                }
+            }
+        }
+
+        macros::run_async_or!(
+            controllers::transaction::upsert_transaction(&transaction, &mut *sp),
+            {
+                not_created.push(transaction.id);
+                continue;
+            }
+        );
+
+        for installment in installments.iter() {
+            macros::run_async_or!(
+                controllers::transaction::upsert_installment(installment, &mut *sp),
+                {
+                    not_created.push(transaction.id);
+                    continue 'requests;
+                }
+            );
+        }
+
+        macros::run_async_or!(
+            controllers::transaction::delete_installments_after(
+                &transaction.id,
+                user.id,
+                installments.len() as i16,
+                &mut *sp
+            ),
+            {
+                not_created.push(transaction.id);
+                continue;
+            }
+        );
+
+        macros::commit_transaction!(sp);
+    }
+
+    macros::commit_transaction!(tx);
+
+    let mut success = true;
+    let mut message = String::from("ok");
+    let status_code = build_status_code_for_multiple_input(
+        body.len(),
+        not_created.len(),
+        &mut message,
+        &mut success,
+    );
+
+    HttpResponse::build(status_code)
+        .insert_header(ContentType::json())
+        .body(
+            json!({
+                "success": success,
+                "message": message,
+                "errors": not_created
+            })
+            .to_string(),
+        )
+}
+
+pub async fn delete_transaction(
+    req: HttpRequest,
+    body: web::Json<Vec<DeleteTransactionReq>>,
+    app_state: web::Data<state::AppState>,
+) -> HttpResponse {
+    let ext = req.extensions();
+    let session = macros::get_session!(ext);
+    let mut con = macros::get_database_connection!(app_state);
+    let user = macros::unwrap_opt_or_unauthorize!(macros::run_async_unwrap!(
+        get_user(session.user_email.as_str(), &mut *con,),
+        "an error occurred when tried to get user from database"
+    ));
+
+    let mut tx = macros::begin_transaction!(con);
+    let mut not_deleted: Vec<Uuid> = Vec::new();
+
+    for req in body.iter() {
+        macros::run_async_or!(
+            controllers::transaction::delete_transaction(&req.transaction_id, user.id, &mut *tx),
+            {
+                not_deleted.push(req.transaction_id);
+            }
+        )
+    }
+
+    macros::commit_transaction!(tx);
+
+    let mut success = true;
+    let mut message = String::from("ok");
+    let status_code = build_status_code_for_multiple_input(
+        body.len(),
+        not_deleted.len(),
+        &mut message,
+        &mut success,
+    );
+
+    HttpResponse::build(status_code)
+        .insert_header(ContentType::json())
+        .body(
+            json!({
+                "success": success,
+                "message": message,
+                "errors": not_deleted
+            })
+            .to_string(),
+        )
+}
+
+pub async fn list_transaction(
+    req: HttpRequest,
+    query: web::Query<ListTransactionsReq>,
+    app_state: web::Data<state::AppState>,
+) -> HttpResponse {
+    let ext = req.extensions();
+    let session = macros::get_session!(ext);
+
+    let (from, to) = match (
+        parse_optional_date(&query.from),
+        parse_optional_date(&query.to),
+    ) {
+        (Ok(f), Ok(t)) => (f, t),
+        _ => {
+            return HttpResponse::build(StatusCode::BAD_REQUEST)
+                .insert_header(ContentType::json())
+                .body(json!({ "success": false, "message": "invalid date"}).to_string());
+        }
+    };
+
+    let mut con = macros::get_database_connection!(app_state);
+    let user = macros::unwrap_opt_or_unauthorize!(macros::run_async_unwrap!(
+        get_user(session.user_email.as_str(), &mut *con,),
+        "an error occurred when tried to get user from database"
+    ));
+
+    let transactions = macros::run_async_unwrap!(
+        controllers::transaction::get_transactions_by_user_id(user.id, from, to, None, &mut *con),
+        "an error occurred when tried to get the transactions from DB"
+    );
+
+    let mut res: Vec<ListTransactionsRes> = Vec::new();
+    for transaction in transactions {
+        res.push(ListTransactionsRes::from(transaction));
+    }
+
+    HttpResponse::build(StatusCode::OK)
+        .insert_header(ContentType::json())
+        .body(json!(res).to_string())
+}
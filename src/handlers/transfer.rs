@@ -0,0 +1,170 @@
+use actix_web::{
+    http::{header::ContentType, StatusCode},
+    web, HttpMessage, HttpRequest, HttpResponse,
+};
+use chrono::DateTime;
+use serde_json::json;
+use sqlx::Acquire;
+use uuid::Uuid;
+
+use crate::{
+    controllers::{self, auth::get_user},
+    handlers::{macros, util::build_status_code_for_multiple_input},
+    model::transfer::Transfer,
+    request_types::transfer::{CreateTransferReq, DeleteTransferReq, ListTransfersRes},
+    state,
+};
+
+pub async fn create_transfer(
+    req: HttpRequest,
+    body: web::Json<CreateTransferReq>,
+    app_state: web::Data<state::AppState>,
+) -> HttpResponse {
+    let ext = req.extensions();
+    let session = macros::get_session!(ext);
+
+    let date = match DateTime::parse_from_rfc3339(body.date.as_str()) {
+        Ok(d) => d,
+        Err(_) => {
+            return HttpResponse::build(StatusCode::BAD_REQUEST)
+                .insert_header(ContentType::json())
+                .body(json!({ "success": false, "message": "invalid date"}).to_string());
+        }
+    };
+
+    if body.amount <= 0 || body.from_account_id == body.to_account_id {
+        return HttpResponse::build(StatusCode::BAD_REQUEST)
+            .insert_header(ContentType::json())
+            .body(json!({ "success": false, "message": "invalid transfer"}).to_string());
+    }
+
+    let mut con = macros::get_database_connection!(app_state);
+    let user = macros::unwrap_opt_or_unauthorize!(macros::run_async_unwrap!(
+        get_user(session.user_email.as_str(), &mut *con,),
+        "an error occurred when tried to get user from database"
+    ));
+
+    let transfer = Transfer {
+        id: body.id,
+        user_id: user.id,
+        from_account_id: body.from_account_id,
+        to_account_id: Some(body.to_account_id),
+        credit_card_bill_id: None,
+        amount: body.amount,
+        description: body.description.clone(),
+        transfer_date: date.to_utc(),
+    };
+
+    let mut tx = macros::begin_transaction!(con);
+
+    let accounts_valid = macros::run_async_unwrap!(
+        controllers::transfer::check_transfer_accounts(&transfer, &mut *tx),
+        "an error occurred when tried to check the transfer accounts"
+    );
+    if !accounts_valid {
+        return HttpResponse::build(StatusCode::NOT_FOUND)
+            .insert_header(ContentType::json())
+            .body(json!({ "success": false, "message": "account not found"}).to_string());
+    }
+
+    macros::run_async_unwrap!(
+        controllers::transfer::create_transfer(&transfer, &mut *tx),
+        "an error occurred when tried to create the transfer"
+    );
+
+    macros::commit_transaction!(tx);
+
+    HttpResponse::build(StatusCode::CREATED)
+        .insert_header(ContentType::json())
+        .body(json!({"success": true, "message": "ok"}).to_string())
+}
+
+pub async fn delete_transfer(
+    req: HttpRequest,
+    body: web::Json<Vec<DeleteTransferReq>>,
+    app_state: web::Data<state::AppState>,
+) -> HttpResponse {
+    let ext = req.extensions();
+    let session = macros::get_session!(ext);
+    let mut con = macros::get_database_connection!(app_state);
+    let user = macros::unwrap_opt_or_unauthorize!(macros::run_async_unwrap!(
+        get_user(session.user_email.as_str(), &mut *con,),
+        "an error occurred when tried to get user from database"
+    ));
+
+    let mut tx = macros::begin_transaction!(con);
+    let mut not_deleted: Vec<Uuid> = Vec::new();
+
+    for req in body.iter() {
+        let paid_bill = macros::run_async_or!(
+            controllers::transfer::delete_transfer(&req.transfer_id, user.id, &mut *tx),
+            {
+                not_deleted.push(req.transfer_id);
+                continue;
+            }
+        );
+
+        if let Some((credit_card_bill_id, amount)) = paid_bill {
+            macros::run_async_or!(
+                controllers::credit_card::reopen_credit_card_bill(
+                    &credit_card_bill_id,
+                    amount,
+                    user.id,
+                    &mut *tx
+                ),
+                {
+                    not_deleted.push(req.transfer_id);
+                }
+            );
+        }
+    }
+
+    macros::commit_transaction!(tx);
+
+    let mut success = true;
+    let mut message = String::from("ok");
+    let status_code = build_status_code_for_multiple_input(
+        body.len(),
+        not_deleted.len(),
+        &mut message,
+        &mut success,
+    );
+
+    HttpResponse::build(status_code)
+        .insert_header(ContentType::json())
+        .body(
+            json!({
+                "success": success,
+                "message": message,
+                "errors": not_deleted
+            })
+            .to_string(),
+        )
+}
+
+pub async fn list_transfer(
+    req: HttpRequest,
+    app_state: web::Data<state::AppState>,
+) -> HttpResponse {
+    let ext = req.extensions();
+    let session = macros::get_session!(ext);
+    let mut con = macros::get_database_connection!(app_state);
+    let user = macros::unwrap_opt_or_unauthorize!(macros::run_async_unwrap!(
+        get_user(session.user_email.as_str(), &mut *con,),
+        "an error occurred when tried to get user from database"
+    ));
+
+    let transfers = macros::run_async_unwrap!(
+        controllers::transfer::get_transfers_by_user_id(user.id, None, &mut *con),
+        "an error occurred when tried to get the transfers from DB"
+    );
+
+    let mut res: Vec<ListTransfersRes> = Vec::new();
+    for transfer in transfers {
+        res.push(ListTransfersRes::from(transfer));
+    }
+
+    HttpResponse::build(StatusCode::OK)
+        .insert_header(ContentType::json())
+        .body(json!(res).to_string())
+}
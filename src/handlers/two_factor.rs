@@ -0,0 +1,244 @@
+use actix_web::{
+    http::{header::ContentType, StatusCode},
+    web, HttpMessage, HttpRequest, HttpResponse,
+};
+use chrono::Utc;
+use serde_json::json;
+use sqlx::Acquire;
+
+use crate::{
+    controllers::{
+        auth::get_user,
+        two_factor::{
+            delete_totp, get_totp, replace_recovery_codes, upsert_totp_enrollment,
+            use_recovery_code, use_totp_step,
+        },
+    },
+    handlers::{
+        macros,
+        util::{
+            build_conflict_response, build_too_many_requests, build_unauthorized_response,
+            client_ip,
+        },
+    },
+    request_types::two_factor::TwoFactorCodeReq,
+    state,
+    totp::{
+        generate_recovery_codes, generate_secret, hash_recovery_code, otpauth_uri, verify_code,
+    },
+};
+
+/// Starts the TOTP enrollment, returning the secret to be added to an
+/// authenticator app. 2FA is only enabled after `confirm_totp`, and then
+/// asked on every login method.
+pub async fn enroll_totp(req: HttpRequest, app_state: web::Data<state::AppState>) -> HttpResponse {
+    let ext = req.extensions();
+    let session = macros::get_session!(ext);
+    let mut con = macros::get_database_connection!(app_state);
+    let user = macros::unwrap_opt_or_unauthorize!(macros::run_async_unwrap!(
+        get_user(session.user_email.as_str(), &mut *con,),
+        "an error occurred when tried to get user from database"
+    ));
+
+    let secret = macros::unwrap_res_or_error!(
+        generate_secret(),
+        "an error occurred while generating the TOTP secret"
+    );
+    let is_enrolled = macros::run_async_unwrap!(
+        upsert_totp_enrollment(user.id, secret.as_str(), &mut *con),
+        "an error occurred when tried to save the TOTP enrollment"
+    );
+    if !is_enrolled {
+        return build_conflict_response(Some(String::from("2FA is already enabled")));
+    }
+
+    HttpResponse::build(StatusCode::OK)
+        .insert_header(ContentType::json())
+        .body(
+            json!({
+                "success": true,
+                "otpauth_uri": otpauth_uri(secret.as_str(), user.email.as_str()),
+                "secret": secret,
+            })
+            .to_string(),
+        )
+}
+
+/// Enables 2FA once the user proves the authenticator app works, returning
+/// the recovery codes. They are stored hashed, so this is the only time they
+/// can be shown.
+pub async fn confirm_totp(
+    req: HttpRequest,
+    body: web::Json<TwoFactorCodeReq>,
+    app_state: web::Data<state::AppState>,
+) -> HttpResponse {
+    let ext = req.extensions();
+    let session = macros::get_session!(ext);
+    let mut con = macros::get_database_connection!(app_state);
+    let user = macros::unwrap_opt_or_unauthorize!(macros::run_async_unwrap!(
+        get_user(session.user_email.as_str(), &mut *con,),
+        "an error occurred when tried to get user from database"
+    ));
+
+    let totp = match macros::run_async_unwrap!(
+        get_totp(user.id, &mut *con),
+        "an error occurred when tried to get the user's 2FA enrollment"
+    ) {
+        Some(t) if t.is_confirmed => {
+            return build_conflict_response(Some(String::from("2FA is already enabled")));
+        }
+        Some(t) => t,
+        None => {
+            return HttpResponse::build(StatusCode::BAD_REQUEST)
+                .insert_header(ContentType::json())
+                .body(
+                    json!({ "success": false, "message": "2FA enrollment not started"}).to_string(),
+                );
+        }
+    };
+
+    let ip = client_ip(&req);
+    let retry_at = macros::run_async_unwrap!(
+        app_state
+            .throttle
+            .check_two_factor_code(user.id, ip.as_str()),
+        "an error occurred when tried to check the 2FA code attempts"
+    );
+    if let Some(retry_at) = retry_at {
+        log::warn!("2FA code throttled for user {} from {}", user.id, ip);
+        return build_too_many_requests("too many 2FA code attempts", retry_at);
+    }
+
+    let step = macros::unwrap_res_or_error!(
+        verify_code(
+            totp.secret.as_str(),
+            body.code.as_str(),
+            Utc::now(),
+            totp.last_used_step
+        ),
+        "an error occurred while verifying the 2FA code"
+    );
+    let step = match step {
+        Some(s) => s,
+        None => return build_unauthorized_response(Some(String::from("invalid code"))),
+    };
+
+    let codes = macros::unwrap_res_or_error!(
+        generate_recovery_codes(),
+        "an error occurred while generating the recovery codes"
+    );
+    let mut code_hashes = Vec::new();
+    for code in codes.iter() {
+        code_hashes.push(macros::unwrap_res_or_error!(
+            hash_recovery_code(code.as_str()),
+            "an error occurred while hashing the recovery code"
+        ));
+    }
+
+    let mut tx = macros::begin_transaction!(con);
+
+    let is_confirmed = macros::run_async_unwrap!(
+        use_totp_step(user.id, step, &mut *tx),
+        "an error occurred when tried to confirm the TOTP enrollment"
+    );
+    if !is_confirmed {
+        return build_unauthorized_response(Some(String::from("invalid code")));
+    }
+
+    macros::run_async_unwrap!(
+        replace_recovery_codes(user.id, &code_hashes, &mut tx),
+        "an error occurred when tried to save the recovery codes"
+    );
+
+    macros::commit_transaction!(tx);
+
+    HttpResponse::build(StatusCode::OK)
+        .insert_header(ContentType::json())
+        .body(json!({ "success": true, "recovery_codes": codes }).to_string())
+}
+
+/// Turns 2FA off. Needs a current code, or a recovery code when the
+/// authenticator app was lost.
+pub async fn disable_totp(
+    req: HttpRequest,
+    body: web::Json<TwoFactorCodeReq>,
+    app_state: web::Data<state::AppState>,
+) -> HttpResponse {
+    let ext = req.extensions();
+    let session = macros::get_session!(ext);
+    let mut con = macros::get_database_connection!(app_state);
+    let user = macros::unwrap_opt_or_unauthorize!(macros::run_async_unwrap!(
+        get_user(session.user_email.as_str(), &mut *con,),
+        "an error occurred when tried to get user from database"
+    ));
+
+    let totp = match macros::run_async_unwrap!(
+        get_totp(user.id, &mut *con),
+        "an error occurred when tried to get the user's 2FA enrollment"
+    ) {
+        Some(t) => t,
+        None => {
+            return HttpResponse::build(StatusCode::BAD_REQUEST)
+                .insert_header(ContentType::json())
+                .body(json!({ "success": false, "message": "2FA is not enabled"}).to_string());
+        }
+    };
+
+    // An unconfirmed enrollment can be dropped without a code
+    if totp.is_confirmed {
+        let ip = client_ip(&req);
+        let retry_at = macros::run_async_unwrap!(
+            app_state
+                .throttle
+                .check_two_factor_code(user.id, ip.as_str()),
+            "an error occurred when tried to check the 2FA code attempts"
+        );
+        if let Some(retry_at) = retry_at {
+            log::warn!("2FA code throttled for user {} from {}", user.id, ip);
+            return build_too_many_requests("too many 2FA code attempts", retry_at);
+        }
+
+        let step = macros::unwrap_res_or_error!(
+            verify_code(
+                totp.secret.as_str(),
+                body.code.as_str(),
+                Utc::now(),
+                totp.last_used_step
+            ),
+            "an error occurred while verifying the 2FA code"
+        );
+        let is_code_valid = match step {
+            Some(step) => macros::run_async_unwrap!(
+                use_totp_step(user.id, step, &mut *con),
+                "an error occurred when tried to mark the 2FA code as used"
+            ),
+            None => {
+                let code_hash = macros::unwrap_res_or_error!(
+                    hash_recovery_code(body.code.as_str()),
+                    "an error occurred while hashing the recovery code"
+                );
+                macros::run_async_unwrap!(
+                    use_recovery_code(user.id, code_hash.as_str(), &mut *con),
+                    "an error occurred when tried to use the recovery code"
+                )
+            }
+        };
+
+        if !is_code_valid {
+            return build_unauthorized_response(Some(String::from("invalid code")));
+        }
+    }
+
+    let mut tx = macros::begin_transaction!(con);
+
+    macros::run_async_unwrap!(
+        delete_totp(user.id, &mut tx),
+        "an error occurred when tried to disable 2FA"
+    );
+
+    macros::commit_transaction!(tx);
+
+    HttpResponse::build(StatusCode::OK)
+        .insert_header(ContentType::json())
+        .body(json!({ "success": true }).to_string())
+}
@@ -0,0 +1,404 @@
+use actix_web::{
+    http::{header::ContentType, StatusCode},
+    web, HttpMessage, HttpRequest, HttpResponse,
+};
+use chrono::Utc;
+use serde_json::json;
+use sqlx::Acquire;
+
+use crate::{
+    controllers::{
+        account_deletion::schedule_user_deletion,
+        auth::{
+            check_email_exists, clear_password, get_google_user_information, get_user,
+            update_password, update_user_name,
+        },
+        identity::{
+            create_identity, delete_identity, get_identities_by_user_id, get_user_by_identity,
+        },
+        session_mgm::{delete_other_sessions, delete_user_sessions},
+    },
+    handlers::{
+        auth::send_verification,
+        macros,
+        util::{
+            build_conflict_response, build_error_response, build_method_not_allowed,
+            build_unauthorized_response,
+        },
+    },
+    model::{identity::Identity, user::AuthType},
+    request_types::{
+        auth::is_email_valid,
+        user::{LinkGoogleReq, LinkPasswordReq, ListIdentitiesRes, UpdateUserReq, UserRes},
+    },
+    state,
+};
+
+pub async fn get_me(req: HttpRequest, app_state: web::Data<state::AppState>) -> HttpResponse {
+    let ext = req.extensions();
+    let session = macros::get_session!(ext);
+    let mut con = macros::get_database_connection!(app_state);
+    let user = macros::unwrap_opt_or_unauthorize!(macros::run_async_unwrap!(
+        get_user(session.user_email.as_str(), &mut *con,),
+        "an error occurred when tried to get user from database"
+    ));
+
+    HttpResponse::build(StatusCode::OK)
+        .insert_header(ContentType::json())
+        .body(json!(UserRes::from(user)).to_string())
+}
+
+/// Updates the logged user's profile. A new password needs the current one
+/// and logs out the other sessions, and a new email is only set after the
+/// link sent to it is opened.
+pub async fn update_me(
+    req: HttpRequest,
+    body: web::Json<UpdateUserReq>,
+    app_state: web::Data<state::AppState>,
+) -> HttpResponse {
+    let ext = req.extensions();
+    let session = macros::get_session!(ext);
+
+    let name = body.name.as_deref().map(str::trim);
+    if name.is_some_and(|n| n.is_empty()) {
+        return HttpResponse::build(StatusCode::BAD_REQUEST)
+            .insert_header(ContentType::json())
+            .body(json!({ "success": false, "message": "invalid name"}).to_string());
+    }
+    if body.new_password.as_deref().is_some_and(|p| p.is_empty()) {
+        return HttpResponse::build(StatusCode::BAD_REQUEST)
+            .insert_header(ContentType::json())
+            .body(json!({ "success": false, "message": "invalid password"}).to_string());
+    }
+
+    let mut con = macros::get_database_connection!(app_state);
+    let mut user = macros::unwrap_opt_or_unauthorize!(macros::run_async_unwrap!(
+        get_user(session.user_email.as_str(), &mut *con,),
+        "an error occurred when tried to get user from database"
+    ));
+
+    if body.new_password.is_some() {
+        let identities = macros::run_async_unwrap!(
+            get_identities_by_user_id(user.id, &mut *con),
+            "an error occurred when tried to get the user identities from DB"
+        );
+        if !identities
+            .iter()
+            .any(|i| i.auth_type == AuthType::UsernamePassword)
+        {
+            return build_method_not_allowed(Some(String::from(
+                "auth method is not username and password",
+            )));
+        }
+
+        let is_pwd_valid = macros::unwrap_res_or_error!(
+            bcrypt::verify(
+                body.current_password.as_deref().unwrap_or_default(),
+                user.password.as_deref().unwrap_or_default()
+            ),
+            "an error occurred while verifying user's password"
+        );
+        if !is_pwd_valid {
+            return build_unauthorized_response(Some(String::from("incorrect password")));
+        }
+    }
+
+    let new_email = body
+        .email
+        .as_deref()
+        .map(str::trim)
+        .filter(|e| *e != user.email);
+    if let Some(email) = new_email {
+        if !is_email_valid(email) {
+            return HttpResponse::build(StatusCode::BAD_REQUEST)
+                .insert_header(ContentType::json())
+                .body(json!({ "success": false, "message": "invalid email"}).to_string());
+        }
+
+        let email_exists = macros::run_async_unwrap!(
+            check_email_exists(email, &mut *con),
+            "an error occurred when tried to check if email exists on the db"
+        );
+        if email_exists {
+            return build_conflict_response(Some("email already in use".to_string()));
+        }
+    }
+
+    let mut tx = macros::begin_transaction!(con);
+
+    if let Some(name) = name {
+        macros::run_async_unwrap!(
+            update_user_name(user.id, name, &mut *tx),
+            "an error occurred when tried to update the user name"
+        );
+        user.name = String::from(name);
+    }
+
+    if let Some(password) = body.new_password.as_deref() {
+        macros::run_async_unwrap!(
+            update_password(user.email.as_str(), password, &mut *tx),
+            "an error occurred when tried to update the user password"
+        );
+        macros::run_async_unwrap!(
+            delete_other_sessions(&mut *tx, user.email.as_str(), &session.id),
+            "an error occurred when tried to delete the other sessions"
+        );
+    }
+
+    macros::commit_transaction!(tx);
+
+    // Sent after the commit, so the link never points to a rolled back row.
+    // The user can ask for the change again if it fails
+    let mut pending_email = None;
+    if let Some(email) = new_email {
+        match send_verification(user.email.as_str(), Some(email), &app_state, &mut con).await {
+            Ok(_) => pending_email = Some(email),
+            Err(e) => log::error!(
+                "an error occurred when tried to send the verification email: {}",
+                e
+            ),
+        }
+    }
+
+    HttpResponse::build(StatusCode::OK)
+        .insert_header(ContentType::json())
+        .body(
+            json!({
+                "success": true,
+                "user": UserRes::from(user),
+                "pending_email": pending_email
+            })
+            .to_string(),
+        )
+}
+
+/// Schedules the logged user's account for deletion after the grace period
+/// and logs out all of their sessions. Logging in again cancels it.
+pub async fn delete_me(req: HttpRequest, app_state: web::Data<state::AppState>) -> HttpResponse {
+    let ext = req.extensions();
+    let session = macros::get_session!(ext);
+    let mut con = macros::get_database_connection!(app_state);
+    let user = macros::unwrap_opt_or_unauthorize!(macros::run_async_unwrap!(
+        get_user(session.user_email.as_str(), &mut *con,),
+        "an error occurred when tried to get user from database"
+    ));
+
+    let delete_at = Utc::now() + app_state.account_deletion_grace_period;
+
+    let mut tx = macros::begin_transaction!(con);
+
+    macros::run_async_unwrap!(
+        schedule_user_deletion(user.id, delete_at, &mut *tx),
+        "an error occurred when tried to schedule the user deletion"
+    );
+    macros::run_async_unwrap!(
+        delete_user_sessions(&mut *tx, user.email.as_str()),
+        "an error occurred when tried to delete the user sessions"
+    );
+
+    macros::commit_transaction!(tx);
+
+    HttpResponse::build(StatusCode::ACCEPTED)
+        .insert_header(ContentType::json())
+        .body(
+            json!({
+                "success": true,
+                "message": "account scheduled for deletion",
+                "deletion_scheduled_at": delete_at.to_rfc3339()
+            })
+            .to_string(),
+        )
+}
+
+pub async fn list_identities(
+    req: HttpRequest,
+    app_state: web::Data<state::AppState>,
+) -> HttpResponse {
+    let ext = req.extensions();
+    let session = macros::get_session!(ext);
+    let mut con = macros::get_database_connection!(app_state);
+    let user = macros::unwrap_opt_or_unauthorize!(macros::run_async_unwrap!(
+        get_user(session.user_email.as_str(), &mut *con,),
+        "an error occurred when tried to get user from database"
+    ));
+
+    let identities = macros::run_async_unwrap!(
+        get_identities_by_user_id(user.id, &mut *con),
+        "an error occurred when tried to get the user identities from DB"
+    );
+
+    let res: Vec<ListIdentitiesRes> = identities
+        .into_iter()
+        .map(ListIdentitiesRes::from)
+        .collect();
+
+    HttpResponse::build(StatusCode::OK)
+        .insert_header(ContentType::json())
+        .body(json!(res).to_string())
+}
+
+/// Links a Google account to the logged user, after verifying its token.
+pub async fn link_google(
+    req: HttpRequest,
+    body: web::Json<LinkGoogleReq>,
+    app_state: web::Data<state::AppState>,
+) -> HttpResponse {
+    let ext = req.extensions();
+    let session = macros::get_session!(ext);
+
+    let oauth_user_data = match get_google_user_information(
+        body.token.as_str(),
+        app_state.google_oauth_client_id.as_str(),
+        &app_state.google_jwks,
+    )
+    .await
+    {
+        Ok(Some(o)) => o,
+        Ok(None) => return build_unauthorized_response(Some("invalid token".to_string())),
+        Err(e) => {
+            log::error!(
+                "An error occurred when tried to get the google oauth user information: {}",
+                e
+            );
+            return build_error_response();
+        }
+    };
+
+    let mut con = macros::get_database_connection!(app_state);
+    let user = macros::unwrap_opt_or_unauthorize!(macros::run_async_unwrap!(
+        get_user(session.user_email.as_str(), &mut *con,),
+        "an error occurred when tried to get user from database"
+    ));
+
+    let owner = macros::run_async_unwrap!(
+        get_user_by_identity(AuthType::Google, oauth_user_data.sub.as_str(), &mut *con),
+        "an error occurred when tried to get the identity owner from DB"
+    );
+    if owner.is_some_and(|o| o.id != user.id) {
+        return build_conflict_response(Some("google account linked to another user".to_string()));
+    }
+
+    let identities = macros::run_async_unwrap!(
+        get_identities_by_user_id(user.id, &mut *con),
+        "an error occurred when tried to get the user identities from DB"
+    );
+    if identities.iter().any(|i| i.auth_type == AuthType::Google) {
+        return build_conflict_response(Some("google account already linked".to_string()));
+    }
+
+    macros::run_async_unwrap!(
+        create_identity(
+            &Identity::new(user.id, AuthType::Google, Some(oauth_user_data.sub)),
+            &mut *con
+        ),
+        "an error occurred when tried to insert the user identity on the database"
+    );
+
+    HttpResponse::build(StatusCode::CREATED)
+        .insert_header(ContentType::json())
+        .body(json!({"success": true, "message": "google account linked"}).to_string())
+}
+
+/// Lets the logged user also log in with their email and a password.
+pub async fn link_password(
+    req: HttpRequest,
+    body: web::Json<LinkPasswordReq>,
+    app_state: web::Data<state::AppState>,
+) -> HttpResponse {
+    let ext = req.extensions();
+    let session = macros::get_session!(ext);
+
+    if body.password.is_empty() {
+        return HttpResponse::build(StatusCode::BAD_REQUEST)
+            .insert_header(ContentType::json())
+            .body(json!({ "success": false, "message": "invalid password"}).to_string());
+    }
+
+    let mut con = macros::get_database_connection!(app_state);
+    let user = macros::unwrap_opt_or_unauthorize!(macros::run_async_unwrap!(
+        get_user(session.user_email.as_str(), &mut *con,),
+        "an error occurred when tried to get user from database"
+    ));
+
+    let identities = macros::run_async_unwrap!(
+        get_identities_by_user_id(user.id, &mut *con),
+        "an error occurred when tried to get the user identities from DB"
+    );
+    if identities
+        .iter()
+        .any(|i| i.auth_type == AuthType::UsernamePassword)
+    {
+        return build_conflict_response(Some("password already set".to_string()));
+    }
+
+    let mut tx = macros::begin_transaction!(con);
+
+    macros::run_async_unwrap!(
+        update_password(user.email.as_str(), body.password.as_str(), &mut *tx),
+        "an error occurred when tried to update the user password"
+    );
+    macros::run_async_unwrap!(
+        create_identity(
+            &Identity::new(user.id, AuthType::UsernamePassword, None),
+            &mut *tx
+        ),
+        "an error occurred when tried to insert the user identity on the database"
+    );
+
+    macros::commit_transaction!(tx);
+
+    HttpResponse::build(StatusCode::CREATED)
+        .insert_header(ContentType::json())
+        .body(json!({"success": true, "message": "password linked"}).to_string())
+}
+
+pub async fn unlink_identity(
+    req: HttpRequest,
+    path: web::Path<AuthType>,
+    app_state: web::Data<state::AppState>,
+) -> HttpResponse {
+    let ext = req.extensions();
+    let session = macros::get_session!(ext);
+    let auth_type = path.into_inner();
+
+    let mut con = macros::get_database_connection!(app_state);
+    let user = macros::unwrap_opt_or_unauthorize!(macros::run_async_unwrap!(
+        get_user(session.user_email.as_str(), &mut *con,),
+        "an error occurred when tried to get user from database"
+    ));
+
+    let mut tx = macros::begin_transaction!(con);
+
+    let identities = macros::run_async_unwrap!(
+        get_identities_by_user_id(user.id, &mut *tx),
+        "an error occurred when tried to get the user identities from DB"
+    );
+
+    if !identities.iter().any(|i| i.auth_type == auth_type) {
+        return HttpResponse::build(StatusCode::NOT_FOUND)
+            .insert_header(ContentType::json())
+            .body(json!({ "success": false, "message": "identity not found"}).to_string());
+    }
+
+    // The user would be left without a way to log in
+    if identities.len() == 1 {
+        return build_conflict_response(Some("can't unlink the only identity".to_string()));
+    }
+
+    macros::run_async_unwrap!(
+        delete_identity(user.id, auth_type.clone(), &mut *tx),
+        "an error occurred when tried to delete the user identity"
+    );
+    if auth_type == AuthType::UsernamePassword {
+        macros::run_async_unwrap!(
+            clear_password(user.id, &mut *tx),
+            "an error occurred when tried to clear the user password"
+        );
+    }
+
+    macros::commit_transaction!(tx);
+
+    HttpResponse::build(StatusCode::OK)
+        .insert_header(ContentType::json())
+        .body(json!({"success": true}).to_string())
+}
@@ -1,9 +1,13 @@
 use std::usize;
 
 use actix_web::{
-    http::{header::ContentType, StatusCode},
-    HttpResponse,
+    http::{
+        header::{self, ContentType},
+        StatusCode,
+    },
+    HttpRequest, HttpResponse,
 };
+use chrono::{DateTime, FixedOffset, NaiveDate, NaiveTime, TimeZone, Utc};
 use serde_json::json;
 
 pub fn build_error_response() -> HttpResponse {
@@ -35,6 +39,48 @@ pub fn build_method_not_allowed(message: Option<String>) -> HttpResponse {
         .body(json!({"success": false, "message": res_msg}).to_string())
 }
 
+pub fn build_too_many_requests(message: &str, retry_at: DateTime<Utc>) -> HttpResponse {
+    let retry_after = (retry_at - Utc::now()).num_seconds().max(1);
+
+    HttpResponse::build(StatusCode::TOO_MANY_REQUESTS)
+        .insert_header(ContentType::json())
+        .insert_header((header::RETRY_AFTER, retry_after.to_string()))
+        .body(
+            json!({
+                "success": false,
+                "message": message,
+                "retry_at": retry_at.to_rfc3339()
+            })
+            .to_string(),
+        )
+}
+
+/// Address of the client, used to throttle requests per IP. The server
+/// faces the clients directly, so forwarding headers aren't trusted.
+pub fn client_ip(req: &HttpRequest) -> String {
+    req.peer_addr()
+        .map(|a| a.ip().to_string())
+        .unwrap_or_default()
+}
+
+/// Renders html/generic_message.html, for the pages opened from email links.
+pub fn build_message_page(status_code: StatusCode, header: &str, message: &str) -> HttpResponse {
+    let page = std::fs::read_to_string("/app/html/generic_message.html").unwrap_or_else(|e| {
+        log::error!(
+            "an error occurred when tried to read the message page: {}",
+            e
+        );
+        String::from("<h3>{header}</h3><p>{message}</p>")
+    });
+
+    HttpResponse::build(status_code)
+        .insert_header(ContentType::html())
+        .body(
+            page.replace("{header}", header)
+                .replace("{message}", message),
+        )
+}
+
 pub fn build_status_code_for_multiple_input(
     in_len: usize,
     error_len: usize,
@@ -64,3 +110,25 @@ pub fn build_status_code_for_multiple_input(
 
     status_code
 }
+
+pub fn parse_optional_date(
+    value: &Option<String>,
+) -> Result<Option<DateTime<Utc>>, chrono::ParseError> {
+    match value {
+        Some(s) => Ok(Some(DateTime::parse_from_rfc3339(s.as_str())?.to_utc())),
+        None => Ok(None),
+    }
+}
+
+/// Parses a "YYYY-MM" month into its first day.
+pub fn parse_month(value: &str) -> Result<NaiveDate, chrono::ParseError> {
+    NaiveDate::parse_from_str(format!("{value}-01").as_str(), "%Y-%m-%d")
+}
+
+/// Start of the given day in the client's offset, as UTC.
+pub fn local_midnight(date: NaiveDate, offset: FixedOffset) -> DateTime<Utc> {
+    offset
+        .from_local_datetime(&date.and_time(NaiveTime::MIN))
+        .unwrap()
+        .to_utc()
+}
@@ -0,0 +1,54 @@
+use std::time::Duration;
+
+use chrono::Utc;
+use sqlx::{Acquire, Pool, Postgres};
+
+use crate::controllers;
+
+/// Periodically erases the accounts whose deletion grace period is over.
+pub async fn run(db: Pool<Postgres>, period: Duration) {
+    let mut interval = actix_rt::time::interval(period);
+
+    loop {
+        interval.tick().await;
+
+        match delete_due_accounts(&db).await {
+            Ok(0) => (),
+            Ok(n) => log::info!("deleted {} accounts", n),
+            Err(e) => log::error!("an error occurred when tried to delete accounts: {}", e),
+        }
+    }
+}
+
+async fn delete_due_accounts(db: &Pool<Postgres>) -> Result<i32, Box<dyn std::error::Error>> {
+    let mut tx = db.begin().await?;
+    let mut deleted = 0;
+
+    let due =
+        controllers::account_deletion::lock_users_due_for_deletion(Utc::now(), &mut *tx).await?;
+
+    for (user_id, email) in due {
+        // An account that fails is rolled back alone and retried on the next run
+        let mut savepoint = tx.begin().await?;
+        match controllers::account_deletion::delete_user_data(
+            user_id,
+            email.as_str(),
+            &mut savepoint,
+        )
+        .await
+        {
+            Ok(()) => {
+                savepoint.commit().await?;
+                deleted += 1;
+            }
+            Err(e) => {
+                log::warn!("can't delete account of user {}: {}", user_id, e);
+                savepoint.rollback().await?;
+            }
+        }
+    }
+
+    tx.commit().await?;
+
+    Ok(deleted)
+}
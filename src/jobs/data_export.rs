@@ -0,0 +1,232 @@
+use std::{
+    collections::HashSet,
+    fs,
+    io::{Cursor, Write},
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use chrono::Utc;
+use serde_json::Value;
+use sqlx::{Acquire, Pool, Postgres};
+use uuid::Uuid;
+use zip::{write::SimpleFileOptions, ZipWriter};
+
+use crate::{controllers, model::data_export::DataExportStatus};
+
+/// How long a built archive can be downloaded before it's deleted.
+pub const DATA_EXPORT_TTL_DAYS: i64 = 7;
+
+/// Periodically builds the requested data exports and deletes the expired
+/// archives.
+pub async fn run(db: Pool<Postgres>, dir: PathBuf, period: Duration) {
+    let mut interval = actix_rt::time::interval(period);
+
+    loop {
+        interval.tick().await;
+
+        if let Err(e) = delete_expired_archives(&db, dir.as_path()).await {
+            log::error!(
+                "an error occurred when tried to delete expired data exports: {}",
+                e
+            );
+        }
+
+        match build_pending_exports(&db, dir.as_path()).await {
+            Ok(0) => (),
+            Ok(n) => log::info!("built {} data exports", n),
+            Err(e) => log::error!("an error occurred when tried to build data exports: {}", e),
+        }
+    }
+}
+
+pub fn archive_path(dir: &Path, export_id: &Uuid) -> PathBuf {
+    dir.join(format!("{}.zip", export_id))
+}
+
+async fn build_pending_exports(
+    db: &Pool<Postgres>,
+    dir: &Path,
+) -> Result<i32, Box<dyn std::error::Error>> {
+    let mut tx = db.begin().await?;
+    let mut built = 0;
+
+    let pending = controllers::data_export::lock_pending_data_exports(&mut *tx).await?;
+
+    for (export_id, user_id) in pending {
+        // A failed query aborts the transaction, so each export runs in a
+        // savepoint that's rolled back before marking it as failed.
+        let mut savepoint = tx.begin().await?;
+        let status = match build_export(&export_id, user_id, dir, &mut savepoint).await {
+            Ok(()) => {
+                savepoint.commit().await?;
+                built += 1;
+                DataExportStatus::Ready
+            }
+            Err(e) => {
+                log::warn!("can't build data export {}: {}", export_id, e);
+                savepoint.rollback().await?;
+                DataExportStatus::Failed
+            }
+        };
+
+        controllers::data_export::complete_data_export(&export_id, status, &mut *tx).await?;
+    }
+
+    tx.commit().await?;
+
+    Ok(built)
+}
+
+async fn build_export(
+    export_id: &Uuid,
+    user_id: i32,
+    dir: &Path,
+    con: &mut sqlx::PgConnection,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let data = controllers::data_export::get_user_data(user_id, con).await?;
+    let archive = build_archive(&data)?;
+
+    fs::create_dir_all(dir)?;
+    fs::write(archive_path(dir, export_id), archive)?;
+
+    Ok(())
+}
+
+async fn delete_expired_archives(
+    db: &Pool<Postgres>,
+    dir: &Path,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut con = db.acquire().await?;
+    let before = Utc::now() - chrono::Duration::days(DATA_EXPORT_TTL_DAYS);
+    let available: HashSet<Uuid> =
+        controllers::data_export::delete_expired_data_exports(before, &mut con)
+            .await?
+            .into_iter()
+            .collect();
+
+    if !dir.exists() {
+        return Ok(());
+    }
+
+    // Also removes the archives of exports deleted along with their user
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        let id = path
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .and_then(|s| Uuid::parse_str(s).ok());
+
+        if id.is_some_and(|id| !available.contains(&id)) {
+            fs::remove_file(path)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Builds a zip archive with a JSON and a CSV file for each group of
+/// records.
+pub fn build_archive(data: &[(&str, Vec<Value>)]) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+    let options = SimpleFileOptions::default();
+
+    for (name, rows) in data {
+        zip.start_file(format!("{}.json", name), options)?;
+        zip.write_all(&serde_json::to_vec_pretty(rows)?)?;
+
+        zip.start_file(format!("{}.csv", name), options)?;
+        zip.write_all(&build_csv(rows)?)?;
+    }
+
+    Ok(zip.finish()?.into_inner())
+}
+
+/// Writes the records as CSV, with a sorted column for every field found in
+/// them.
+/// Nested values are written as JSON.
+fn build_csv(rows: &[Value]) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    let mut columns: Vec<&String> = Vec::new();
+    for row in rows {
+        if let Value::Object(fields) = row {
+            for key in fields.keys() {
+                if !columns.contains(&key) {
+                    columns.push(key);
+                }
+            }
+        }
+    }
+    columns.sort();
+
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    writer.write_record(&columns)?;
+
+    for row in rows {
+        let record = columns.iter().map(|c| match row.get(c.as_str()) {
+            None | Some(Value::Null) => String::new(),
+            Some(Value::String(s)) => s.clone(),
+            Some(v) => v.to_string(),
+        });
+        writer.write_record(record)?;
+    }
+
+    Ok(writer.into_inner()?)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::io::Read;
+
+    use serde_json::json;
+    use zip::ZipArchive;
+
+    use super::*;
+
+    #[test]
+    fn test_build_archive() {
+        let data = vec![
+            (
+                "categories",
+                vec![
+                    json!({"id": "a", "name": "Food, drinks", "icon_name": null}),
+                    json!({"id": "b", "name": "Rent", "color": "#fff"}),
+                ],
+            ),
+            ("budgets", Vec::new()),
+        ];
+
+        let archive = build_archive(&data).unwrap();
+        let mut zip = ZipArchive::new(Cursor::new(archive)).unwrap();
+
+        let mut names: Vec<&str> = zip.file_names().collect();
+        names.sort();
+        assert_eq!(
+            names,
+            vec![
+                "budgets.csv",
+                "budgets.json",
+                "categories.csv",
+                "categories.json"
+            ]
+        );
+
+        let mut csv = String::new();
+        zip.by_name("categories.csv")
+            .unwrap()
+            .read_to_string(&mut csv)
+            .unwrap();
+        assert_eq!(
+            csv,
+            "color,icon_name,id,name\n,,a,\"Food, drinks\"\n#fff,,b,Rent\n"
+        );
+
+        let mut json = String::new();
+        zip.by_name("categories.json")
+            .unwrap()
+            .read_to_string(&mut json)
+            .unwrap();
+        let rows: Vec<Value> = serde_json::from_str(json.as_str()).unwrap();
+        assert_eq!(rows, data[0].1);
+    }
+}
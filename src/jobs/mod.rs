@@ -0,0 +1,3 @@
+pub mod account_deletion;
+pub mod data_export;
+pub mod recurring_transaction;
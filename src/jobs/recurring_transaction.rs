@@ -0,0 +1,76 @@
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use sqlx::{Acquire, PgConnection, Pool, Postgres};
+use uuid::Uuid;
+
+use crate::controllers;
+
+/// Periodically materializes the occurrences of recurring transactions that
+/// came due since the last run.
+pub async fn run(db: Pool<Postgres>, period: Duration) {
+    let mut interval = actix_rt::time::interval(period);
+
+    loop {
+        interval.tick().await;
+
+        match materialize_due_recurring_transactions(&db).await {
+            Ok(0) => (),
+            Ok(n) => log::info!("materialized {} recurring transaction occurrences", n),
+            Err(e) => log::error!(
+                "an error occurred when tried to materialize recurring transactions: {}",
+                e
+            ),
+        }
+    }
+}
+
+async fn materialize_due_recurring_transactions(
+    db: &Pool<Postgres>,
+) -> Result<i32, Box<dyn std::error::Error>> {
+    let now = Utc::now();
+    let mut tx = db.begin().await?;
+    let mut materialized = 0;
+
+    let due =
+        controllers::recurring_transaction::lock_due_recurring_transactions(now, &mut *tx).await?;
+
+    for (rule_id, user_id) in due {
+        // A rule that fails (e.g. its credit card was deleted) is rolled back
+        // alone and retried on the next run.
+        let mut savepoint = tx.begin().await?;
+        match materialize_rule(&rule_id, user_id, now, &mut savepoint).await {
+            Ok(n) => {
+                savepoint.commit().await?;
+                materialized += n;
+            }
+            Err(e) => {
+                log::warn!("can't materialize recurring transaction {}: {}", rule_id, e);
+                savepoint.rollback().await?;
+            }
+        }
+    }
+
+    tx.commit().await?;
+
+    Ok(materialized)
+}
+
+async fn materialize_rule(
+    rule_id: &Uuid,
+    user_id: i32,
+    now: DateTime<Utc>,
+    con: &mut PgConnection,
+) -> Result<i32, Box<dyn std::error::Error>> {
+    let rule = controllers::recurring_transaction::get_recurring_transaction_by_id(
+        rule_id, user_id, &mut *con,
+    )
+    .await?;
+
+    match rule {
+        Some(rule) => {
+            controllers::recurring_transaction::materialize_due_occurrences(&rule, now, con).await
+        }
+        None => Ok(0),
+    }
+}
@@ -0,0 +1,71 @@
+use std::{
+    sync::RwLock,
+    time::{Duration, Instant},
+};
+
+use jsonwebtoken::{jwk::JwkSet, DecodingKey};
+
+/// How long the fetched keys are used before fetching them again.
+const JWKS_TTL: Duration = Duration::from_secs(60 * 60);
+/// Minimum time between fetches triggered by an unknown key id, so tokens
+/// with made up ids can't make us hammer the provider.
+const JWKS_MIN_REFRESH_INTERVAL: Duration = Duration::from_secs(60);
+
+struct CachedKeys {
+    keys: JwkSet,
+    fetched_at: Instant,
+}
+
+/// Public keys of an identity provider, fetched from its JWKS URL and cached.
+pub struct JwksCache {
+    url: String,
+    client: reqwest::Client,
+    cached: RwLock<Option<CachedKeys>>,
+}
+
+impl JwksCache {
+    pub fn new(url: &str) -> Self {
+        JwksCache {
+            url: String::from(url),
+            client: reqwest::Client::new(),
+            cached: RwLock::new(None),
+        }
+    }
+
+    /// Returns the key with the given id. The keys are fetched again when
+    /// they're stale or don't have the id, as providers rotate them.
+    pub async fn get_key(
+        &self,
+        kid: &str,
+    ) -> Result<Option<DecodingKey>, Box<dyn std::error::Error>> {
+        let needs_fetch = match self.cached.read().unwrap().as_ref() {
+            Some(c) => {
+                let age = c.fetched_at.elapsed();
+                age > JWKS_TTL || (c.keys.find(kid).is_none() && age > JWKS_MIN_REFRESH_INTERVAL)
+            }
+            None => true,
+        };
+
+        if needs_fetch {
+            let keys: JwkSet = self
+                .client
+                .get(self.url.as_str())
+                .send()
+                .await?
+                .error_for_status()?
+                .json()
+                .await?;
+
+            *self.cached.write().unwrap() = Some(CachedKeys {
+                keys,
+                fetched_at: Instant::now(),
+            });
+        }
+
+        let cached = self.cached.read().unwrap();
+        match cached.as_ref().and_then(|c| c.keys.find(kid)) {
+            Some(jwk) => Ok(Some(DecodingKey::from_jwk(jwk)?)),
+            None => Ok(None),
+        }
+    }
+}
@@ -1,6 +1,7 @@
 use chrono::{DateTime, Utc};
 use jsonwebtoken::{decode, encode, Algorithm, DecodingKey, EncodingKey, Header, Validation};
 use serde::{Deserialize, Serialize};
+use uuid::Uuid;
 
 const HS256_SECRET: &str = "expanse-skid-reamy-ounce-uranium";
 
@@ -9,6 +10,9 @@ pub struct TokenClaims {
     pub exp: usize,
     pub sub: String,
     pub iat: usize,
+    /// Random id, so tokens issued within the same second still differ.
+    #[serde(default)]
+    pub jti: String,
 }
 
 pub fn generate_token(
@@ -23,6 +27,7 @@ pub fn generate_token(
         exp,
         sub: String::from(subject),
         iat,
+        jti: Uuid::new_v4().to_string(),
     };
 
     let mut header = Header::new(Algorithm::RS256);
@@ -53,6 +58,7 @@ pub fn generate_token_hs256(
         exp,
         sub: String::from(subject),
         iat,
+        jti: Uuid::new_v4().to_string(),
     };
     let encoding_key = EncodingKey::from_secret(HS256_SECRET.as_ref());
 
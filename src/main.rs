@@ -1,15 +1,23 @@
 mod controllers;
 mod database;
+mod email;
 mod handlers;
+mod jobs;
+mod jwks;
 mod jwt;
 mod middleware;
 mod model;
 mod request_types;
 mod routes;
 mod state;
+mod throttle;
+mod totp;
 
 use actix_web::middleware::{Compress, Logger};
 use actix_web::{web, App, HttpServer};
+use email::{
+    file::FileEmailSender, ses::SesEmailSender, smtp::SmtpEmailSender, EmailConfig, EmailSender,
+};
 use jsonwebtoken::DecodingKey;
 use jsonwebtoken::EncodingKey;
 use openssl::ssl::{SslAcceptor, SslFiletype, SslMethod};
@@ -17,6 +25,12 @@ use state::AppState;
 use std::env;
 use std::fs;
 use std::io;
+use std::path::PathBuf;
+use std::time::Duration;
+use throttle::{
+    memory::MemoryAttemptStore, postgres::PostgresAttemptStore, AttemptStore, Limit, Throttle,
+    ThrottleConfig,
+};
 
 #[actix_rt::main]
 async fn main() -> io::Result<()> {
@@ -28,6 +42,18 @@ async fn main() -> io::Result<()> {
     let tls_key_path = env::var("TLS_KEY_PATH").unwrap();
     let tls_cert_path = env::var("TLS_CERT_PATH").unwrap();
     let google_oauth_client_id = env::var("GOOGLE_WEB_CLIENT_ID").unwrap();
+    let google_jwks_url = env::var("GOOGLE_JWKS_URL")
+        .unwrap_or(String::from("https://www.googleapis.com/oauth2/v3/certs"));
+    let apple_client_id = env::var("APPLE_CLIENT_ID").unwrap_or_default();
+    let apple_jwks_url =
+        env::var("APPLE_JWKS_URL").unwrap_or(String::from("https://appleid.apple.com/auth/keys"));
+    let public_base_url = env::var("PUBLIC_BASE_URL").unwrap();
+    let recurring_job_interval: u64 = env_or("RECURRING_JOB_INTERVAL_SECS", 300);
+    let account_deletion_grace_days: i64 = env_or("ACCOUNT_DELETION_GRACE_DAYS", 30);
+    let data_export_dir =
+        PathBuf::from(env::var("DATA_EXPORT_DIR").unwrap_or(String::from("/app/exports")));
+    let data_export_job_interval: u64 = env_or("DATA_EXPORT_JOB_INTERVAL_SECS", 60);
+    let account_deletion_job_interval: u64 = env_or("ACCOUNT_DELETION_JOB_INTERVAL_SECS", 3600);
 
     // Setting Log configuration
     let env = env_logger::Env::default()
@@ -41,6 +67,106 @@ async fn main() -> io::Result<()> {
     let db = database::DbConnection::build(database_path.as_str()).await;
     log::info!("Started connection pool with database");
 
+    actix_rt::spawn(jobs::recurring_transaction::run(
+        db.pool.clone(),
+        Duration::from_secs(recurring_job_interval),
+    ));
+    log::info!("Started recurring transactions job");
+
+    actix_rt::spawn(jobs::account_deletion::run(
+        db.pool.clone(),
+        Duration::from_secs(account_deletion_job_interval),
+    ));
+    log::info!("Started account deletion job");
+
+    actix_rt::spawn(jobs::data_export::run(
+        db.pool.clone(),
+        data_export_dir.clone(),
+        Duration::from_secs(data_export_job_interval),
+    ));
+    log::info!("Started data export job");
+
+    let email_config = EmailConfig {
+        from: env::var("EMAIL_FROM").unwrap_or(String::from("no-reply@dev.finly.digital")),
+        locale: env::var("EMAIL_LOCALE").unwrap_or(String::from("PTBR")),
+        reset_password_template: env::var("EMAIL_RESET_PASSWORD_TEMPLATE")
+            .unwrap_or(String::from("ResetPasswordTemplate")),
+        verify_email_template: env::var("EMAIL_VERIFY_EMAIL_TEMPLATE")
+            .unwrap_or(String::from("VerifyEmailTemplate")),
+    };
+    let email_backend = env::var("EMAIL_BACKEND").unwrap_or(String::from("ses"));
+    let email_sender: Box<dyn EmailSender> = match email_backend.as_str() {
+        "ses" => Box::new(SesEmailSender::new(email_config).await),
+        "smtp" => Box::new(
+            SmtpEmailSender::new(env::var("SMTP_URL").unwrap().as_str(), email_config).unwrap(),
+        ),
+        "file" => Box::new(FileEmailSender::new(
+            env::var("EMAIL_DIR").ok().map(PathBuf::from),
+            email_config,
+        )),
+        other => panic!("unknown EMAIL_BACKEND: {}", other),
+    };
+    log::info!("Sending emails through {}", email_backend);
+
+    let login_lockout = chrono::Duration::minutes(env_or("LOGIN_LOCKOUT_MINUTES", 15));
+    let login_base_delay = chrono::Duration::seconds(env_or("LOGIN_BASE_DELAY_SECS", 1));
+    let login_max_delay = chrono::Duration::seconds(env_or("LOGIN_MAX_DELAY_SECS", 30));
+    let reset_window = chrono::Duration::minutes(env_or("RESET_WINDOW_MINUTES", 60));
+    let throttle_config = ThrottleConfig {
+        login_per_email: Limit {
+            max_attempts: env_or("LOGIN_MAX_FAILURES_PER_EMAIL", 10),
+            free_attempts: env_or("LOGIN_FREE_FAILURES_PER_EMAIL", 3),
+            base_delay: login_base_delay,
+            max_delay: login_max_delay,
+            window: login_lockout,
+        },
+        login_per_ip: Limit {
+            max_attempts: env_or("LOGIN_MAX_FAILURES_PER_IP", 50),
+            free_attempts: env_or("LOGIN_FREE_FAILURES_PER_IP", 10),
+            base_delay: login_base_delay,
+            max_delay: login_max_delay,
+            window: login_lockout,
+        },
+        reset_per_email: Limit {
+            max_attempts: env_or("RESET_MAX_REQUESTS_PER_EMAIL", 3),
+            free_attempts: u32::MAX,
+            base_delay: chrono::Duration::zero(),
+            max_delay: chrono::Duration::zero(),
+            window: reset_window,
+        },
+        reset_per_ip: Limit {
+            max_attempts: env_or("RESET_MAX_REQUESTS_PER_IP", 10),
+            free_attempts: u32::MAX,
+            base_delay: chrono::Duration::zero(),
+            max_delay: chrono::Duration::zero(),
+            window: reset_window,
+        },
+        two_factor_per_user: Limit {
+            max_attempts: env_or("TWO_FACTOR_MAX_ATTEMPTS_PER_USER", 10),
+            free_attempts: env_or("TWO_FACTOR_FREE_ATTEMPTS_PER_USER", 3),
+            base_delay: login_base_delay,
+            max_delay: login_max_delay,
+            window: login_lockout,
+        },
+        two_factor_per_ip: Limit {
+            max_attempts: env_or("TWO_FACTOR_MAX_ATTEMPTS_PER_IP", 50),
+            free_attempts: env_or("TWO_FACTOR_FREE_ATTEMPTS_PER_IP", 10),
+            base_delay: login_base_delay,
+            max_delay: login_max_delay,
+            window: login_lockout,
+        },
+    };
+    let throttle_store = env::var("THROTTLE_STORE").unwrap_or(String::from("postgres"));
+    let attempt_store: Box<dyn AttemptStore> = match throttle_store.as_str() {
+        "postgres" => Box::new(PostgresAttemptStore::new(
+            db.pool.clone(),
+            throttle_config.retention(),
+        )),
+        "memory" => Box::new(MemoryAttemptStore::new(throttle_config.retention())),
+        other => panic!("unknown THROTTLE_STORE: {}", other),
+    };
+    log::info!("Recording login attempts in {}", throttle_store);
+
     let jwt_enc_key = EncodingKey::from_rsa_pem(&fs::read(jwt_encoding_key_path)?).unwrap();
     let jwt_dec_key = DecodingKey::from_rsa_pem(&fs::read(jwt_decoding_key_path)?).unwrap();
 
@@ -49,6 +175,14 @@ async fn main() -> io::Result<()> {
         jwt_encoding_key: jwt_enc_key,
         jwt_decoding_key: jwt_dec_key,
         google_oauth_client_id,
+        google_jwks: jwks::JwksCache::new(google_jwks_url.as_str()),
+        apple_client_id,
+        apple_jwks: jwks::JwksCache::new(apple_jwks_url.as_str()),
+        public_base_url,
+        email_sender,
+        account_deletion_grace_period: chrono::Duration::days(account_deletion_grace_days),
+        data_export_dir,
+        throttle: Throttle::new(attempt_store, throttle_config),
     });
 
     // load TLS keys
@@ -72,6 +206,14 @@ async fn main() -> io::Result<()> {
             .configure(routes::reset_password_routes)
             .configure(routes::category_routes)
             .configure(routes::credit_card_routes)
+            .configure(routes::transaction_routes)
+            .configure(routes::account_routes)
+            .configure(routes::recurring_transaction_routes)
+            .configure(routes::budget_routes)
+            .configure(routes::report_routes)
+            .configure(routes::sync_routes)
+            .configure(routes::user_routes)
+            .configure(routes::data_export_routes)
     };
 
     HttpServer::new(app)
@@ -80,3 +222,12 @@ async fn main() -> io::Result<()> {
         .run()
         .await
 }
+
+/// Parses the environment variable, falling back to `default` when it's
+/// missing or invalid.
+fn env_or<T: std::str::FromStr>(name: &str, default: T) -> T {
+    env::var(name)
+        .ok()
+        .and_then(|v| v.parse::<T>().ok())
+        .unwrap_or(default)
+}
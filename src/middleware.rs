@@ -11,7 +11,11 @@ use serde_json::json;
 use uuid::Uuid;
 
 use crate::{
-    controllers::session_mgm::get_session_by_session_id, jwt::verify_token, state::AppState,
+    controllers::session_mgm::{
+        delete_session_by_id, get_session_by_session_id, is_refresh_token_reused, touch_session,
+    },
+    jwt::verify_token,
+    state::AppState,
 };
 
 pub async fn refresh_token_middleware(
@@ -53,7 +57,65 @@ pub async fn refresh_token_middleware(
         }
     };
 
-    req.extensions_mut().insert(claims);
+    let mut con = match app_data.db.acquire().await {
+        Ok(s) => s,
+        Err(e) => {
+            log::error!(
+                "Error trying to acquire connection to session database: {}",
+                e
+            );
+            return Err(actix_web::error::ErrorInternalServerError(
+                json!({"success": false, "message": "internal server error"}).to_string(),
+            ));
+        }
+    };
+
+    let session_id = match Uuid::parse_str(claims.sub.as_str()) {
+        Ok(s) => s,
+        Err(e) => {
+            log::error!("it wasn't possible to convert uuid from string {}", e);
+            return Err(actix_web::error::ErrorUnauthorized(
+                json!({"success": false, "message": "unauthorized"}).to_string(),
+            ));
+        }
+    };
+
+    let session = match get_session_by_session_id(&mut *con, &session_id)
+        .await
+        .unwrap_or_else(|e| {
+            log::error!("Error trying to get session by id; {}", e);
+            None
+        }) {
+        Some(s) => s,
+        None => {
+            return Err(actix_web::error::ErrorUnauthorized(
+                json!({"success": false, "message": "unauthorized"}).to_string(),
+            ));
+        }
+    };
+
+    if session.refresh_token != token {
+        // An already rotated refresh token means it leaked, so the whole
+        // session is revoked, including the token that replaced it.
+        let reused = is_refresh_token_reused(&mut *con, &session.id, token)
+            .await
+            .unwrap_or_else(|e| {
+                log::error!("Error trying to check refresh token reuse: {}", e);
+                false
+            });
+        if reused {
+            log::warn!("Refresh token reused, revoking session {}", session.id);
+            if let Err(e) = delete_session_by_id(&mut *con, &session.id).await {
+                log::error!("Error trying to revoke session: {}", e);
+            }
+        }
+
+        return Err(actix_web::error::ErrorUnauthorized(
+            json!({"success": false, "message": "unauthorized"}).to_string(),
+        ));
+    }
+
+    req.extensions_mut().insert(session);
 
     next.call(req).await
 }
@@ -140,6 +202,10 @@ pub async fn auth_middleware(
         ));
     }
 
+    if let Err(e) = touch_session(&mut *con, &session.id).await {
+        log::warn!("Error trying to update session last seen time: {}", e);
+    }
+
     req.extensions_mut().insert(session);
 
     next.call(req).await
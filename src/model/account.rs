@@ -0,0 +1,23 @@
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[derive(sqlx::Type, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[sqlx(type_name = "account_type")]
+#[sqlx(rename_all = "UPPERCASE")]
+pub enum AccountType {
+    Checking,
+    Savings,
+    Cash,
+    Wallet,
+}
+
+#[derive(sqlx::FromRow, Serialize)]
+pub struct Account {
+    pub id: Uuid,
+    pub user_id: i32,
+    pub name: String,
+    pub icon_name: String,
+    pub account_type: AccountType,
+    pub initial_balance: i64,
+    pub balance: i64,
+}
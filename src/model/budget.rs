@@ -0,0 +1,75 @@
+use std::collections::HashMap;
+
+use chrono::{Months, NaiveDate};
+use uuid::Uuid;
+
+#[derive(Clone)]
+pub struct Budget {
+    pub id: Uuid,
+    pub user_id: i32,
+    pub category_id: Option<Uuid>,
+    pub subcategory_id: Option<Uuid>,
+    pub amount: i64,
+    pub rollover: bool,
+    /// First day of the first month the budget applies to.
+    pub start_month: NaiveDate,
+}
+
+impl Budget {
+    /// Returns what was left unspent in the months before `month` and carried
+    /// into it. Overspending a month doesn't reduce the following ones.
+    pub fn rolled_over_into(
+        &self,
+        month: NaiveDate,
+        spent_by_month: &HashMap<NaiveDate, i64>,
+    ) -> i64 {
+        if !self.rollover {
+            return 0;
+        }
+
+        let mut carry = 0;
+        let mut current = self.start_month;
+        while current < month {
+            let spent = spent_by_month.get(&current).copied().unwrap_or(0);
+            carry = (self.amount + carry - spent).max(0);
+
+            current = match current.checked_add_months(Months::new(1)) {
+                Some(d) => d,
+                None => break,
+            };
+        }
+
+        carry
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn month(m: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2026, m, 1).unwrap()
+    }
+
+    #[test]
+    fn rollover_carries_unspent_amount() {
+        let mut budget = Budget {
+            id: Uuid::new_v4(),
+            user_id: 1,
+            category_id: Some(Uuid::new_v4()),
+            subcategory_id: None,
+            amount: 1000,
+            rollover: true,
+            start_month: month(1),
+        };
+        let spent = HashMap::from([(month(1), 400), (month(2), 2000), (month(3), 700)]);
+
+        assert_eq!(budget.rolled_over_into(month(1), &spent), 0);
+        assert_eq!(budget.rolled_over_into(month(2), &spent), 600);
+        assert_eq!(budget.rolled_over_into(month(3), &spent), 0);
+        assert_eq!(budget.rolled_over_into(month(4), &spent), 300);
+
+        budget.rollover = false;
+        assert_eq!(budget.rolled_over_into(month(4), &spent), 0);
+    }
+}
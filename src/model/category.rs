@@ -9,5 +9,6 @@ pub struct Category {
     pub name: String,
     pub color: String,
     pub icon_name: String,
+    pub version: i32,
     pub subcategories: Vec<Subcategory>,
 }
@@ -9,4 +9,23 @@ pub struct CreditCard {
     pub icon_name: String,
     pub limit_value: i64,
     pub closing_day: i16,
+    pub due_day: i16,
+    pub used_limit: i64,
+    pub version: i32,
+}
+
+impl CreditCard {
+    /// A week after closing, the same default the due_day migration used.
+    pub fn default_due_day(closing_day: i16) -> i16 {
+        ((closing_day + 6) % 31) + 1
+    }
+
+    /// Cards with a zero limit don't have their limit tracked.
+    pub fn available_limit(&self) -> Option<i64> {
+        if self.limit_value == 0 {
+            return None;
+        }
+
+        Some(self.limit_value - self.used_limit)
+    }
 }
@@ -1,13 +1,26 @@
 use std::cmp::Ordering;
 
 use chrono::{DateTime, Datelike, Days, FixedOffset, Months, NaiveDate, TimeDelta, Utc};
+use serde::Serialize;
 use uuid::Uuid;
 
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub enum BillStatus {
+    Open,
+    Closed,
+    PartiallyPaid,
+    Paid,
+    Overdue,
+}
+
 pub struct CreditCardBill {
     pub id: Uuid,
     pub credit_card_id: Uuid,
     pub start_at: DateTime<Utc>,
     pub end_at: DateTime<Utc>,
+    pub total_amount: i64,
+    pub paid_amount: Option<i64>,
+    pub paid_at: Option<DateTime<Utc>>,
 }
 
 impl CreditCardBill {
@@ -71,6 +84,50 @@ impl CreditCardBill {
             credit_card_id: credit_card_id.clone(),
             start_at: start_at_utc,
             end_at: end_at_utc,
+            total_amount: 0,
+            paid_amount: None,
+            paid_at: None,
+        }
+    }
+
+    /// `end_at` is the closing day at midnight in the client's offset, so
+    /// rounding to the nearest midnight recovers the local closing date.
+    pub fn closing_date(&self) -> NaiveDate {
+        (self.end_at + TimeDelta::hours(12)).date_naive()
+    }
+
+    /// A date in the billing window right after this one. Adding months to a
+    /// purchase date can land twice in the same window when the day is
+    /// clamped to the end of the month, so installments follow the bills.
+    pub fn next_window_date(&self) -> NaiveDate {
+        self.closing_date() + Days::new(1)
+    }
+
+    pub fn due_date(&self, credit_card_due_day: i16) -> NaiveDate {
+        let closing_date = self.closing_date();
+        let mut due_month =
+            NaiveDate::from_ymd_opt(closing_date.year(), closing_date.month(), 1).unwrap();
+        if (credit_card_due_day as u32) <= closing_date.day() {
+            due_month = due_month + Months::new(1);
+        }
+        let last_day_due_month = (due_month + Months::new(1) - TimeDelta::days(1)).day();
+
+        due_month
+            .with_day((credit_card_due_day as u32).min(last_day_due_month))
+            .unwrap()
+    }
+
+    pub fn status(&self, credit_card_due_day: i16, now: DateTime<Utc>) -> BillStatus {
+        if self.paid_at.is_some() {
+            BillStatus::Paid
+        } else if now < self.end_at {
+            BillStatus::Open
+        } else if now.date_naive() > self.due_date(credit_card_due_day) {
+            BillStatus::Overdue
+        } else if self.paid_amount.is_some() {
+            BillStatus::PartiallyPaid
+        } else {
+            BillStatus::Closed
         }
     }
 
@@ -109,4 +166,69 @@ mod tests {
         println!("{}", now.naive_utc());
         println!("{}", now.naive_utc().and_utc().to_rfc3339());
     }
+
+    #[test]
+    fn credit_card_bill_due_date_and_status() {
+        let offset = FixedOffset::west_opt(3 * 3600).unwrap();
+        let card_id = Uuid::new_v4();
+        let dt = NaiveDate::from_ymd_opt(2025, 1, 31).unwrap();
+        let mut bill = CreditCardBill::of_date(&card_id, 20, dt, offset);
+
+        assert_eq!(
+            bill.closing_date(),
+            NaiveDate::from_ymd_opt(2025, 2, 20).unwrap()
+        );
+        assert_eq!(
+            bill.due_date(27),
+            NaiveDate::from_ymd_opt(2025, 2, 27).unwrap()
+        );
+        assert_eq!(
+            bill.due_date(5),
+            NaiveDate::from_ymd_opt(2025, 3, 5).unwrap()
+        );
+
+        let before_closing = bill.end_at - TimeDelta::days(1);
+        let after_closing = bill.end_at + TimeDelta::days(1);
+        let after_due = bill.end_at + TimeDelta::days(10);
+        assert_eq!(bill.status(27, before_closing), BillStatus::Open);
+        assert_eq!(bill.status(27, after_closing), BillStatus::Closed);
+        assert_eq!(bill.status(27, after_due), BillStatus::Overdue);
+
+        bill.paid_amount = Some(100);
+        assert_eq!(bill.status(27, after_closing), BillStatus::PartiallyPaid);
+        assert_eq!(bill.status(27, after_due), BillStatus::Overdue);
+
+        bill.paid_at = Some(after_due);
+        assert_eq!(bill.status(27, after_due), BillStatus::Paid);
+    }
+
+    #[test]
+    fn credit_card_bill_next_window_at_end_of_month() {
+        let offset = FixedOffset::west_opt(3 * 3600).unwrap();
+        let card_id = Uuid::new_v4();
+
+        for purchase_day in 29..=31 {
+            for closing_day in 28..=31 {
+                let dt = NaiveDate::from_ymd_opt(2025, 1, purchase_day).unwrap();
+                let mut bill = CreditCardBill::of_date(&card_id, closing_day, dt, offset);
+                let mut closing_month = bill.closing_date().month();
+
+                for _ in 0..12 {
+                    let next = CreditCardBill::of_date(
+                        &card_id,
+                        closing_day,
+                        bill.next_window_date(),
+                        offset,
+                    );
+
+                    assert_eq!(next.start_at, bill.end_at + TimeDelta::days(1));
+                    assert!(next.end_at > next.start_at);
+                    assert_eq!(next.closing_date().month(), closing_month % 12 + 1);
+
+                    closing_month = next.closing_date().month();
+                    bill = next;
+                }
+            }
+        }
+    }
 }
@@ -0,0 +1,34 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[derive(sqlx::Type, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[sqlx(type_name = "data_export_status")]
+#[sqlx(rename_all = "UPPERCASE")]
+#[serde(rename_all = "UPPERCASE")]
+pub enum DataExportStatus {
+    Pending,
+    Ready,
+    Failed,
+}
+
+#[derive(sqlx::FromRow, Clone)]
+pub struct DataExport {
+    pub id: Uuid,
+    pub user_id: i32,
+    pub status: DataExportStatus,
+    pub requested_at: DateTime<Utc>,
+    pub completed_at: Option<DateTime<Utc>>,
+}
+
+impl DataExport {
+    pub fn new(user_id: i32) -> Self {
+        DataExport {
+            id: Uuid::new_v4(),
+            user_id,
+            status: DataExportStatus::Pending,
+            requested_at: Utc::now(),
+            completed_at: None,
+        }
+    }
+}
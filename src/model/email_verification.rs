@@ -0,0 +1,12 @@
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+#[derive(sqlx::FromRow)]
+pub struct EmailVerification {
+    pub id: Uuid,
+    pub user_email: String,
+    pub sent_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+    pub is_verified: bool,
+    pub new_email: Option<String>,
+}
@@ -0,0 +1,24 @@
+use chrono::{DateTime, Utc};
+
+use super::user::AuthType;
+
+/// A way the user can log in. Password identities have no provider user id,
+/// as they use the user's email and password.
+#[derive(sqlx::FromRow, Clone)]
+pub struct Identity {
+    pub user_id: i32,
+    pub auth_type: AuthType,
+    pub provider_user_id: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl Identity {
+    pub fn new(user_id: i32, auth_type: AuthType, provider_user_id: Option<String>) -> Self {
+        Identity {
+            user_id,
+            auth_type,
+            provider_user_id,
+            created_at: Utc::now(),
+        }
+    }
+}
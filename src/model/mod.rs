@@ -1,7 +1,17 @@
+pub mod account;
+pub mod budget;
 pub mod category;
 pub mod credit_card;
 pub mod credit_card_bill;
+pub mod data_export;
+pub mod email_verification;
+pub mod identity;
+pub mod recurring_transaction;
+pub mod report;
 pub mod reset_password;
 pub mod session;
 pub mod subcategory;
+pub mod transaction;
+pub mod transfer;
+pub mod two_factor;
 pub mod user;
@@ -0,0 +1,168 @@
+use chrono::{DateTime, Days, FixedOffset, Months, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::model::transaction::{Transaction, TransactionKind};
+
+#[derive(sqlx::Type, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[sqlx(type_name = "recurrence_frequency")]
+#[sqlx(rename_all = "UPPERCASE")]
+pub enum RecurrenceFrequency {
+    Daily,
+    Weekly,
+    Monthly,
+    Yearly,
+}
+
+#[derive(Clone)]
+pub struct RecurringTransaction {
+    pub id: Uuid,
+    pub user_id: i32,
+    pub kind: TransactionKind,
+    pub amount: i64,
+    pub description: String,
+    pub category_id: Option<Uuid>,
+    pub subcategory_id: Option<Uuid>,
+    pub credit_card_id: Option<Uuid>,
+    pub account_id: Option<Uuid>,
+    pub frequency: RecurrenceFrequency,
+    pub frequency_interval: i16,
+    /// First occurrence, kept in the user's offset so monthly rules land on
+    /// the same local day.
+    pub start_at: DateTime<FixedOffset>,
+    pub end_at: Option<DateTime<Utc>>,
+    pub occurrence_limit: Option<i32>,
+    pub next_occurrence_number: i32,
+}
+
+impl RecurringTransaction {
+    /// Returns the date of the given occurrence (zero based), or None when the
+    /// rule ends before it. Monthly rules on days the month doesn't have fall
+    /// on its last day.
+    pub fn occurrence_date(&self, occurrence_number: i32) -> Option<DateTime<FixedOffset>> {
+        if occurrence_number < 0 {
+            return None;
+        }
+        if let Some(limit) = self.occurrence_limit {
+            if occurrence_number >= limit {
+                return None;
+            }
+        }
+
+        let steps = (occurrence_number as u32).checked_mul(self.frequency_interval as u32)?;
+        let date = match self.frequency {
+            RecurrenceFrequency::Daily => self.start_at.checked_add_days(Days::new(steps as u64)),
+            RecurrenceFrequency::Weekly => {
+                self.start_at.checked_add_days(Days::new(steps as u64 * 7))
+            }
+            RecurrenceFrequency::Monthly => self.start_at.checked_add_months(Months::new(steps)),
+            RecurrenceFrequency::Yearly => self
+                .start_at
+                .checked_add_months(Months::new(steps.checked_mul(12)?)),
+        }?;
+
+        if let Some(end_at) = self.end_at {
+            if date.to_utc() > end_at {
+                return None;
+            }
+        }
+
+        Some(date)
+    }
+
+    pub fn occurrence(
+        &self,
+        occurrence_number: i32,
+        transaction_date: DateTime<Utc>,
+    ) -> Transaction {
+        Transaction {
+            id: Uuid::new_v4(),
+            user_id: self.user_id,
+            kind: self.kind.clone(),
+            amount: self.amount,
+            description: self.description.clone(),
+            transaction_date,
+            category_id: self.category_id,
+            subcategory_id: self.subcategory_id,
+            credit_card_id: self.credit_card_id,
+            credit_card_bill_id: None,
+            account_id: self.account_id,
+            parent_transaction_id: None,
+            installment_number: 1,
+            installment_count: 1,
+            recurring_transaction_id: Some(self.id),
+            occurrence_number: Some(occurrence_number),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(frequency: RecurrenceFrequency, start: &str) -> RecurringTransaction {
+        RecurringTransaction {
+            id: Uuid::new_v4(),
+            user_id: 1,
+            kind: TransactionKind::Expense,
+            amount: 1000,
+            description: String::from("rent"),
+            category_id: None,
+            subcategory_id: None,
+            credit_card_id: None,
+            account_id: None,
+            frequency,
+            frequency_interval: 1,
+            start_at: DateTime::parse_from_rfc3339(start).unwrap(),
+            end_at: None,
+            occurrence_limit: None,
+            next_occurrence_number: 0,
+        }
+    }
+
+    fn date_of(rule: &RecurringTransaction, occurrence_number: i32) -> Option<String> {
+        rule.occurrence_date(occurrence_number)
+            .map(|d| d.to_rfc3339())
+    }
+
+    #[test]
+    fn monthly_occurrences_clamp_to_month_end() {
+        let r = rule(RecurrenceFrequency::Monthly, "2026-01-31T10:00:00-03:00");
+
+        assert_eq!(date_of(&r, 0).unwrap(), "2026-01-31T10:00:00-03:00");
+        assert_eq!(date_of(&r, 1).unwrap(), "2026-02-28T10:00:00-03:00");
+        assert_eq!(date_of(&r, 2).unwrap(), "2026-03-31T10:00:00-03:00");
+    }
+
+    #[test]
+    fn interval_and_frequency_are_applied() {
+        let mut r = rule(RecurrenceFrequency::Daily, "2026-01-01T00:00:00Z");
+        r.frequency_interval = 10;
+        assert_eq!(date_of(&r, 3).unwrap(), "2026-01-31T00:00:00+00:00");
+
+        r.frequency = RecurrenceFrequency::Weekly;
+        r.frequency_interval = 2;
+        assert_eq!(date_of(&r, 1).unwrap(), "2026-01-15T00:00:00+00:00");
+
+        r.frequency = RecurrenceFrequency::Yearly;
+        r.frequency_interval = 1;
+        assert_eq!(date_of(&r, 2).unwrap(), "2028-01-01T00:00:00+00:00");
+    }
+
+    #[test]
+    fn rule_ends_at_limit_or_end_date() {
+        let mut r = rule(RecurrenceFrequency::Monthly, "2026-01-05T00:00:00Z");
+        r.occurrence_limit = Some(2);
+        assert!(r.occurrence_date(1).is_some());
+        assert!(r.occurrence_date(2).is_none());
+
+        r.occurrence_limit = None;
+        r.end_at = Some(
+            DateTime::parse_from_rfc3339("2026-03-05T00:00:00Z")
+                .unwrap()
+                .to_utc(),
+        );
+        assert!(r.occurrence_date(2).is_some());
+        assert!(r.occurrence_date(3).is_none());
+    }
+}
@@ -0,0 +1,93 @@
+use std::collections::HashMap;
+
+use chrono::NaiveDate;
+use serde::Serialize;
+use uuid::Uuid;
+
+pub const MAX_CASHFLOW_MONTHS: u32 = 120;
+
+/// Income and expense of a category over a period. Transactions without a
+/// category are grouped under a total with no `category_id`.
+#[derive(Clone, Serialize)]
+pub struct CategoryTotal {
+    pub category_id: Option<Uuid>,
+    pub name: Option<String>,
+    pub color: Option<String>,
+    pub icon_name: Option<String>,
+    pub income: i64,
+    pub expense: i64,
+    pub subcategories: Vec<SubcategoryTotal>,
+}
+
+#[derive(Clone, Serialize)]
+pub struct SubcategoryTotal {
+    pub subcategory_id: Uuid,
+    pub name: String,
+    pub color: String,
+    pub icon_name: String,
+    pub income: i64,
+    pub expense: i64,
+}
+
+#[derive(Clone, Serialize)]
+pub struct CashflowMonth {
+    /// Formatted as "YYYY-MM".
+    pub month: String,
+    pub income: i64,
+    pub expense: i64,
+    pub net: i64,
+    /// Balance across all accounts at the end of the month.
+    pub closing_balance: i64,
+}
+
+impl CashflowMonth {
+    /// Builds one entry per month, in order. `totals` holds (income, expense)
+    /// and `balance_changes` how much the accounts' balance moved in each
+    /// month; `opening_balance` is the balance before the first one.
+    pub fn series(
+        months: &[NaiveDate],
+        totals: &HashMap<NaiveDate, (i64, i64)>,
+        opening_balance: i64,
+        balance_changes: &HashMap<NaiveDate, i64>,
+    ) -> Vec<Self> {
+        let mut balance = opening_balance;
+
+        months
+            .iter()
+            .map(|month| {
+                let (income, expense) = totals.get(month).copied().unwrap_or((0, 0));
+                balance += balance_changes.get(month).copied().unwrap_or(0);
+
+                CashflowMonth {
+                    month: month.format("%Y-%m").to_string(),
+                    income,
+                    expense,
+                    net: income - expense,
+                    closing_balance: balance,
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cashflow_series_accumulates_balance() {
+        let month = |m: u32| NaiveDate::from_ymd_opt(2026, m, 1).unwrap();
+        let totals = HashMap::from([(month(1), (5000, 2000)), (month(3), (0, 700))]);
+        let changes = HashMap::from([(month(1), 3000), (month(3), -700)]);
+
+        let series = CashflowMonth::series(&[month(1), month(2), month(3)], &totals, 100, &changes);
+
+        assert_eq!(series[0].month, "2026-01");
+        assert_eq!(series[0].net, 3000);
+        assert_eq!(series[0].closing_balance, 3100);
+        assert_eq!(series[1].net, 0);
+        assert_eq!(series[1].closing_balance, 3100);
+        assert_eq!(series[2].expense, 700);
+        assert_eq!(series[2].closing_balance, 2400);
+    }
+}
@@ -9,10 +9,13 @@ pub struct Session {
     pub refresh_token_expires_at: DateTime<Utc>,
     pub current_access_token: String,
     pub current_access_token_expires_at: DateTime<Utc>,
+    pub device_name: String,
+    pub platform: String,
+    pub last_seen_at: DateTime<Utc>,
 }
 
 impl Session {
-    pub fn build(user_email: &str) -> Self {
+    pub fn build(user_email: &str, device_name: &str, platform: &str) -> Self {
         Session {
             id: Uuid::new_v4(),
             user_email: String::from(user_email),
@@ -21,6 +24,9 @@ impl Session {
             refresh_token_expires_at: Utc::now(),
             current_access_token: String::from(""),
             current_access_token_expires_at: Utc::now(),
+            device_name: String::from(device_name),
+            platform: String::from(platform),
+            last_seen_at: Utc::now(),
         }
     }
 
@@ -8,4 +8,5 @@ pub struct Subcategory {
     pub name: String,
     pub color: String,
     pub icon_name: String,
+    pub version: i32,
 }
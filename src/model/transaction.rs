@@ -0,0 +1,94 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+pub const MAX_INSTALLMENTS: i16 = 72;
+
+#[derive(sqlx::Type, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[sqlx(type_name = "transaction_kind")]
+#[sqlx(rename_all = "UPPERCASE")]
+pub enum TransactionKind {
+    Expense,
+    Income,
+}
+
+#[derive(sqlx::FromRow, Clone)]
+pub struct Transaction {
+    pub id: Uuid,
+    pub user_id: i32,
+    pub kind: TransactionKind,
+    pub amount: i64,
+    pub description: String,
+    pub transaction_date: DateTime<Utc>,
+    pub category_id: Option<Uuid>,
+    pub subcategory_id: Option<Uuid>,
+    pub credit_card_id: Option<Uuid>,
+    pub credit_card_bill_id: Option<Uuid>,
+    pub account_id: Option<Uuid>,
+    pub parent_transaction_id: Option<Uuid>,
+    pub installment_number: i16,
+    pub installment_count: i16,
+    pub recurring_transaction_id: Option<Uuid>,
+    pub occurrence_number: Option<i32>,
+}
+
+impl Transaction {
+    /// Splits the amount in cents across the installments. The remainder
+    /// goes one cent at a time to the first installments.
+    pub fn split_installments(amount: i64, installment_count: i16) -> Vec<i64> {
+        let count = installment_count.max(1) as i64;
+        let base = amount / count;
+        let remainder = amount % count;
+
+        (0..count)
+            .map(|i| if i < remainder { base + 1 } else { base })
+            .collect()
+    }
+
+    pub fn installment_of(
+        &self,
+        installment_number: i16,
+        amount: i64,
+        transaction_date: DateTime<Utc>,
+        credit_card_bill_id: Uuid,
+    ) -> Self {
+        Transaction {
+            id: Uuid::new_v4(),
+            user_id: self.user_id,
+            kind: self.kind.clone(),
+            amount,
+            description: self.description.clone(),
+            transaction_date,
+            category_id: self.category_id,
+            subcategory_id: self.subcategory_id,
+            credit_card_id: self.credit_card_id,
+            credit_card_bill_id: Some(credit_card_bill_id),
+            account_id: self.account_id,
+            parent_transaction_id: Some(self.id),
+            installment_number,
+            installment_count: self.installment_count,
+            recurring_transaction_id: None,
+            occurrence_number: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn split_installments_spreads_remainder() {
+        assert_eq!(
+            Transaction::split_installments(1000, 3),
+            vec![334, 333, 333]
+        );
+        assert_eq!(
+            Transaction::split_installments(1001, 3),
+            vec![334, 334, 333]
+        );
+        assert_eq!(Transaction::split_installments(900, 3), vec![300, 300, 300]);
+        assert_eq!(Transaction::split_installments(2, 3), vec![1, 1, 0]);
+        assert_eq!(Transaction::split_installments(500, 1), vec![500]);
+    }
+}
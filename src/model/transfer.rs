@@ -0,0 +1,14 @@
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+#[derive(sqlx::FromRow, Clone)]
+pub struct Transfer {
+    pub id: Uuid,
+    pub user_id: i32,
+    pub from_account_id: Uuid,
+    pub to_account_id: Option<Uuid>,
+    pub credit_card_bill_id: Option<Uuid>,
+    pub amount: i64,
+    pub description: String,
+    pub transfer_date: DateTime<Utc>,
+}
@@ -0,0 +1,46 @@
+use chrono::{DateTime, Duration, Utc};
+use uuid::Uuid;
+
+use super::user::AuthType;
+
+#[derive(sqlx::FromRow, Clone)]
+pub struct Totp {
+    /// Base32 encoded, as given to authenticator apps
+    pub secret: String,
+    pub is_confirmed: bool,
+    pub last_used_step: Option<i64>,
+}
+
+/// A login that passed the password check and waits for the second factor.
+#[derive(sqlx::FromRow, Clone)]
+pub struct TwoFactorChallenge {
+    pub id: Uuid,
+    pub user_id: i32,
+    pub auth_type: AuthType,
+    pub device_name: String,
+    pub platform: String,
+    pub expires_at: DateTime<Utc>,
+    pub attempts: i32,
+    pub is_used: bool,
+}
+
+impl TwoFactorChallenge {
+    pub fn build(
+        user_id: i32,
+        auth_type: AuthType,
+        device_name: &str,
+        platform: &str,
+        ttl: Duration,
+    ) -> Self {
+        TwoFactorChallenge {
+            id: Uuid::new_v4(),
+            user_id,
+            auth_type,
+            device_name: String::from(device_name),
+            platform: String::from(platform),
+            expires_at: Utc::now() + ttl,
+            attempts: 0,
+            is_used: false,
+        }
+    }
+}
@@ -2,7 +2,10 @@ use bcrypt::BcryptError;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
-use crate::{controllers::auth::GoogleOauthUserInformation, request_types::auth::CreateUserReq};
+use crate::{
+    controllers::auth::{AppleUserInformation, GoogleOauthUserInformation},
+    request_types::auth::CreateUserReq,
+};
 
 #[derive(sqlx::Type, Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[sqlx(type_name = "auth_type")]
@@ -10,6 +13,7 @@ use crate::{controllers::auth::GoogleOauthUserInformation, request_types::auth::
 pub enum AuthType {
     UsernamePassword,
     Google,
+    Apple,
 }
 
 #[derive(sqlx::FromRow, Clone)]
@@ -19,22 +23,32 @@ pub struct User {
     pub name: String,
     pub password: Option<String>,
     pub created_at: DateTime<Utc>,
-    pub auth_type: AuthType,
-    pub google_user_id: Option<String>,
     pub is_email_verified: bool,
     pub is_premium: bool,
 }
 
 impl User {
-    pub fn from_google(data: GoogleOauthUserInformation) -> Self {
+    pub fn from_google(data: &GoogleOauthUserInformation) -> Self {
         User {
             id: -1,
-            email: data.email,
-            name: data.name,
+            email: data.email.clone(),
+            name: data.name.clone(),
+            password: None,
+            created_at: Utc::now(),
+            is_email_verified: data.email_verified,
+            is_premium: false,
+        }
+    }
+
+    /// Apple only sends the user's name on the first sign in, and through
+    /// the app rather than the token, so it's given separately.
+    pub fn from_apple(data: &AppleUserInformation, name: &str) -> Self {
+        User {
+            id: -1,
+            email: data.email.clone(),
+            name: String::from(name),
             password: None,
             created_at: Utc::now(),
-            auth_type: AuthType::Google,
-            google_user_id: Some(data.sub),
             is_email_verified: data.email_verified,
             is_premium: false,
         }
@@ -44,8 +58,7 @@ impl User {
         let password = bcrypt::hash(data.password, bcrypt::DEFAULT_COST)?;
         let utc_now: DateTime<Utc> = Utc::now().into();
         let created_at = utc_now;
-        let auth_type = AuthType::UsernamePassword;
-        let is_email_verified = true;
+        let is_email_verified = false;
         let is_premium = true;
 
         Ok(User {
@@ -54,8 +67,6 @@ impl User {
             name: data.name,
             password: Some(password),
             created_at,
-            auth_type,
-            google_user_id: None,
             is_email_verified,
             is_premium,
         })
@@ -0,0 +1,62 @@
+use actix_web::web;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::model::account::{Account, AccountType};
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct UpsertAccountReq {
+    pub id: Uuid,
+    pub name: String,
+    pub icon_name: String,
+    pub account_type: AccountType,
+    pub initial_balance: i64,
+}
+
+impl From<web::Json<UpsertAccountReq>> for UpsertAccountReq {
+    fn from(value: web::Json<UpsertAccountReq>) -> Self {
+        UpsertAccountReq {
+            id: value.id,
+            name: value.name.clone(),
+            icon_name: value.icon_name.clone(),
+            account_type: value.account_type.clone(),
+            initial_balance: value.initial_balance,
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct DeleteAccountReq {
+    pub account_id: Uuid,
+}
+
+impl From<web::Json<DeleteAccountReq>> for DeleteAccountReq {
+    fn from(value: web::Json<DeleteAccountReq>) -> Self {
+        DeleteAccountReq {
+            account_id: value.account_id,
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct ListAccountsRes {
+    pub id: Uuid,
+    pub name: String,
+    pub icon_name: String,
+    pub account_type: AccountType,
+    pub initial_balance: i64,
+    pub balance: i64,
+}
+
+impl From<Account> for ListAccountsRes {
+    fn from(value: Account) -> Self {
+        ListAccountsRes {
+            id: value.id,
+            name: value.name,
+            icon_name: value.icon_name,
+            account_type: value.account_type,
+            initial_balance: value.initial_balance,
+            balance: value.balance,
+        }
+    }
+}
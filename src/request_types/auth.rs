@@ -1,6 +1,37 @@
 use actix_web::web;
 use serde::{Deserialize, Serialize};
 
+pub const MAX_EMAIL_LENGTH: usize = 320;
+pub const MAX_DEVICE_NAME_LENGTH: usize = 200;
+pub const MAX_PLATFORM_LENGTH: usize = 50;
+
+/// Only catches obvious typos, the verification link is what proves the
+/// address works.
+pub fn is_email_valid(email: &str) -> bool {
+    let (local, domain) = match email.split_once('@') {
+        Some(parts) => parts,
+        None => return false,
+    };
+
+    email.len() <= MAX_EMAIL_LENGTH
+        && !email.chars().any(char::is_whitespace)
+        && !local.is_empty()
+        && !domain.contains('@')
+        && domain.contains('.')
+        && !domain.starts_with('.')
+        && !domain.ends_with('.')
+}
+
+/// The device sent on login is stored with the session, whose columns are
+/// limited to these lengths. Both are required, as a new login replaces the
+/// session of the same device.
+pub fn is_device_info_valid(device_name: Option<&str>, platform: Option<&str>) -> bool {
+    let is_set = |v: &str, max: usize| !v.trim().is_empty() && v.chars().count() <= max;
+
+    device_name.is_some_and(|d| is_set(d, MAX_DEVICE_NAME_LENGTH))
+        && platform.is_some_and(|p| is_set(p, MAX_PLATFORM_LENGTH))
+}
+
 #[derive(Deserialize, Serialize, Debug, Clone)]
 pub struct CreateUserReq {
     pub email: String,
@@ -22,6 +53,8 @@ impl From<web::Json<CreateUserReq>> for CreateUserReq {
 pub struct LoginUserReq {
     pub email: String,
     pub password: String,
+    pub device_name: Option<String>,
+    pub platform: Option<String>,
 }
 
 impl From<web::Json<LoginUserReq>> for LoginUserReq {
@@ -29,6 +62,8 @@ impl From<web::Json<LoginUserReq>> for LoginUserReq {
         LoginUserReq {
             email: u.email.clone(),
             password: u.password.clone(),
+            device_name: u.device_name.clone(),
+            platform: u.platform.clone(),
         }
     }
 }
@@ -36,12 +71,46 @@ impl From<web::Json<LoginUserReq>> for LoginUserReq {
 #[derive(Deserialize, Serialize, Debug, Clone)]
 pub struct GoogleSignInReq {
     pub token: String,
+    pub device_name: Option<String>,
+    pub platform: Option<String>,
 }
 
 impl From<web::Json<GoogleSignInReq>> for GoogleSignInReq {
     fn from(payload: web::Json<GoogleSignInReq>) -> Self {
         GoogleSignInReq {
             token: payload.token.clone(),
+            device_name: payload.device_name.clone(),
+            platform: payload.platform.clone(),
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct AppleSignInReq {
+    pub token: String,
+    /// Only sent by Apple to the app on the first sign in
+    pub name: Option<String>,
+    pub device_name: Option<String>,
+    pub platform: Option<String>,
+}
+
+impl From<web::Json<AppleSignInReq>> for AppleSignInReq {
+    fn from(payload: web::Json<AppleSignInReq>) -> Self {
+        AppleSignInReq {
+            token: payload.token.clone(),
+            name: payload.name.clone(),
+            device_name: payload.device_name.clone(),
+            platform: payload.platform.clone(),
         }
     }
 }
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct ResendVerificationEmailReq {
+    pub email: String,
+}
+
+#[derive(Deserialize)]
+pub struct VerifyEmailReq {
+    pub t: String,
+}
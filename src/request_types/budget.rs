@@ -0,0 +1,60 @@
+use actix_web::web;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct UpsertBudgetReq {
+    pub id: Uuid,
+    pub category_id: Option<Uuid>,
+    pub subcategory_id: Option<Uuid>,
+    pub amount: i64,
+    pub rollover: Option<bool>,
+    pub start_month: Option<String>,
+}
+
+impl From<web::Json<UpsertBudgetReq>> for UpsertBudgetReq {
+    fn from(value: web::Json<UpsertBudgetReq>) -> Self {
+        UpsertBudgetReq {
+            id: value.id,
+            category_id: value.category_id,
+            subcategory_id: value.subcategory_id,
+            amount: value.amount,
+            rollover: value.rollover,
+            start_month: value.start_month.clone(),
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct DeleteBudgetReq {
+    pub budget_id: Uuid,
+}
+
+impl From<web::Json<DeleteBudgetReq>> for DeleteBudgetReq {
+    fn from(value: web::Json<DeleteBudgetReq>) -> Self {
+        DeleteBudgetReq {
+            budget_id: value.budget_id,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct ListBudgetsReq {
+    pub month: Option<String>,
+    /// User's offset from UTC in seconds, used to tell which month a
+    /// transaction belongs to.
+    pub utc_offset: Option<i32>,
+}
+
+#[derive(Serialize)]
+pub struct ListBudgetsRes {
+    pub id: Uuid,
+    pub category_id: Option<Uuid>,
+    pub subcategory_id: Option<Uuid>,
+    pub start_month: String,
+    pub rollover: bool,
+    pub limit: i64,
+    pub rolled_over: i64,
+    pub spent: i64,
+    pub remaining: i64,
+}
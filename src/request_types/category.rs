@@ -8,6 +8,7 @@ pub struct UpsertCategoryReq {
     pub name: String,
     pub color: String,
     pub icon_name: String,
+    pub version: Option<i32>,
 }
 
 impl From<web::Json<UpsertCategoryReq>> for UpsertCategoryReq {
@@ -17,6 +18,7 @@ impl From<web::Json<UpsertCategoryReq>> for UpsertCategoryReq {
             name: cat.name.clone(),
             color: cat.color.clone(),
             icon_name: cat.icon_name.clone(),
+            version: cat.version,
         };
     }
 }
@@ -41,6 +43,7 @@ pub struct UpsertSubcategoryReq {
     pub name: String,
     pub color: String,
     pub icon_name: String,
+    pub version: Option<i32>,
 }
 
 impl From<web::Json<UpsertSubcategoryReq>> for UpsertSubcategoryReq {
@@ -51,6 +54,7 @@ impl From<web::Json<UpsertSubcategoryReq>> for UpsertSubcategoryReq {
             name: sub.name.clone(),
             color: sub.color.clone(),
             icon_name: sub.icon_name.clone(),
+            version: sub.version,
         };
     }
 }
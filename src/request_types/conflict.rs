@@ -0,0 +1,10 @@
+use serde::Serialize;
+use uuid::Uuid;
+
+/// A write rejected because it was based on a stale version. `server` holds
+/// the current copy, or nothing when the record has been deleted.
+#[derive(Serialize)]
+pub struct ConflictRes<T> {
+    pub id: Uuid,
+    pub server: Option<T>,
+}
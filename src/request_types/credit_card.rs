@@ -1,7 +1,13 @@
 use actix_web::web;
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
+use crate::model::{
+    credit_card::CreditCard,
+    credit_card_bill::{BillStatus, CreditCardBill},
+};
+
 #[derive(Deserialize, Serialize, Debug, Clone)]
 pub struct UpsertCreditCardReq {
     pub id: Uuid,
@@ -9,6 +15,9 @@ pub struct UpsertCreditCardReq {
     pub icon_name: String,
     pub limit_value: i64,
     pub closing_day: i16,
+    /// Older clients don't send it, see `CreditCard::default_due_day`
+    pub due_day: Option<i16>,
+    pub version: Option<i32>,
 }
 
 impl From<web::Json<UpsertCreditCardReq>> for UpsertCreditCardReq {
@@ -19,6 +28,8 @@ impl From<web::Json<UpsertCreditCardReq>> for UpsertCreditCardReq {
             icon_name: value.icon_name.clone(),
             limit_value: value.limit_value,
             closing_day: value.closing_day,
+            due_day: value.due_day,
+            version: value.version,
         };
     }
 }
@@ -43,6 +54,33 @@ pub struct ListCreditCardsRes {
     pub icon_name: String,
     pub limit_value: i64,
     pub closing_day: i16,
+    pub due_day: i16,
+    pub used_limit: i64,
+    pub available_limit: Option<i64>,
+    pub version: i32,
+}
+
+impl From<CreditCard> for ListCreditCardsRes {
+    fn from(value: CreditCard) -> Self {
+        let available_limit = value.available_limit();
+
+        ListCreditCardsRes {
+            id: value.id,
+            name: value.name,
+            icon_name: value.icon_name,
+            limit_value: value.limit_value,
+            closing_day: value.closing_day,
+            due_day: value.due_day,
+            used_limit: value.used_limit,
+            available_limit,
+            version: value.version,
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct GetCreditCardReq {
+    pub credit_card_id: Uuid,
 }
 
 #[derive(Deserialize, Serialize, Debug, Clone)]
@@ -80,3 +118,51 @@ impl From<web::Json<ListCreditCardBillsReq>> for ListCreditCardBillsReq {
         }
     }
 }
+
+#[derive(Serialize)]
+pub struct ListCreditCardBillsRes {
+    pub id: Uuid,
+    pub credit_card_id: Uuid,
+    pub start_at: String,
+    pub end_at: String,
+    pub due_date: String,
+    pub total_amount: i64,
+    pub status: BillStatus,
+    pub paid_amount: Option<i64>,
+    pub paid_at: Option<String>,
+}
+
+impl ListCreditCardBillsRes {
+    pub fn new(bill: CreditCardBill, credit_card_due_day: i16, now: DateTime<Utc>) -> Self {
+        ListCreditCardBillsRes {
+            id: bill.id,
+            credit_card_id: bill.credit_card_id,
+            start_at: bill.start_at.to_rfc3339(),
+            end_at: bill.end_at.to_rfc3339(),
+            due_date: bill.due_date(credit_card_due_day).to_string(),
+            total_amount: bill.total_amount,
+            status: bill.status(credit_card_due_day, now),
+            paid_amount: bill.paid_amount,
+            paid_at: bill.paid_at.map(|d| d.to_rfc3339()),
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct PayCreditCardBillReq {
+    pub credit_card_bill_id: Uuid,
+    pub amount: i64,
+    pub date: String,
+    pub account_id: Option<Uuid>,
+}
+
+impl From<web::Json<PayCreditCardBillReq>> for PayCreditCardBillReq {
+    fn from(value: web::Json<PayCreditCardBillReq>) -> Self {
+        PayCreditCardBillReq {
+            credit_card_bill_id: value.credit_card_bill_id,
+            amount: value.amount,
+            date: value.date.clone(),
+            account_id: value.account_id,
+        }
+    }
+}
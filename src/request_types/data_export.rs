@@ -0,0 +1,6 @@
+use serde::Deserialize;
+
+#[derive(Deserialize)]
+pub struct DownloadDataExportReq {
+    pub t: String,
+}
@@ -1,4 +1,16 @@
+pub mod account;
 pub mod auth;
+pub mod budget;
 pub mod category;
+pub mod conflict;
 pub mod credit_card;
+pub mod data_export;
+pub mod recurring_transaction;
+pub mod report;
 pub mod reset_password;
+pub mod session_mgm;
+pub mod sync;
+pub mod transaction;
+pub mod transfer;
+pub mod two_factor;
+pub mod user;
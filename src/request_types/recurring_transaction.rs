@@ -0,0 +1,89 @@
+use actix_web::web;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::model::{recurring_transaction::RecurrenceFrequency, transaction::TransactionKind};
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct UpsertRecurringTransactionReq {
+    pub id: Uuid,
+    pub kind: TransactionKind,
+    pub amount: i64,
+    pub description: String,
+    pub category_id: Option<Uuid>,
+    pub subcategory_id: Option<Uuid>,
+    pub credit_card_id: Option<Uuid>,
+    pub account_id: Option<Uuid>,
+    pub frequency: RecurrenceFrequency,
+    pub interval: Option<i16>,
+    pub start_date: String,
+    pub end_date: Option<String>,
+    pub occurrence_limit: Option<i32>,
+}
+
+impl From<web::Json<UpsertRecurringTransactionReq>> for UpsertRecurringTransactionReq {
+    fn from(value: web::Json<UpsertRecurringTransactionReq>) -> Self {
+        UpsertRecurringTransactionReq {
+            id: value.id,
+            kind: value.kind.clone(),
+            amount: value.amount,
+            description: value.description.clone(),
+            category_id: value.category_id,
+            subcategory_id: value.subcategory_id,
+            credit_card_id: value.credit_card_id,
+            account_id: value.account_id,
+            frequency: value.frequency.clone(),
+            interval: value.interval,
+            start_date: value.start_date.clone(),
+            end_date: value.end_date.clone(),
+            occurrence_limit: value.occurrence_limit,
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct DeleteRecurringTransactionReq {
+    pub recurring_transaction_id: Uuid,
+}
+
+impl From<web::Json<DeleteRecurringTransactionReq>> for DeleteRecurringTransactionReq {
+    fn from(value: web::Json<DeleteRecurringTransactionReq>) -> Self {
+        DeleteRecurringTransactionReq {
+            recurring_transaction_id: value.recurring_transaction_id,
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct RecurringOccurrenceReq {
+    pub recurring_transaction_id: Uuid,
+    pub occurrence_number: i32,
+}
+
+impl From<web::Json<RecurringOccurrenceReq>> for RecurringOccurrenceReq {
+    fn from(value: web::Json<RecurringOccurrenceReq>) -> Self {
+        RecurringOccurrenceReq {
+            recurring_transaction_id: value.recurring_transaction_id,
+            occurrence_number: value.occurrence_number,
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct ListRecurringTransactionsRes {
+    pub id: Uuid,
+    pub kind: TransactionKind,
+    pub amount: i64,
+    pub description: String,
+    pub category_id: Option<Uuid>,
+    pub subcategory_id: Option<Uuid>,
+    pub credit_card_id: Option<Uuid>,
+    pub account_id: Option<Uuid>,
+    pub frequency: RecurrenceFrequency,
+    pub interval: i16,
+    pub start_date: String,
+    pub end_date: Option<String>,
+    pub occurrence_limit: Option<i32>,
+    pub next_occurrence_number: i32,
+    pub next_occurrence_date: Option<String>,
+}
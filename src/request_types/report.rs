@@ -0,0 +1,21 @@
+use serde::Deserialize;
+
+/// Both dates are RFC 3339 and inclusive: the report covers from the start of
+/// `from`'s day to the end of `to`'s day, in the offset each one carries.
+#[derive(Deserialize)]
+pub struct ReportByCategoryReq {
+    pub from: String,
+    pub to: String,
+}
+
+#[derive(Deserialize)]
+pub struct CashflowReq {
+    /// How many months to return, ending at the current one.
+    pub months: Option<u32>,
+    /// User's offset from UTC in seconds, used to tell which month a
+    /// transaction belongs to.
+    pub utc_offset: Option<i32>,
+    /// Puts credit card purchases in the month their bill is due instead of
+    /// the month they were made.
+    pub by_bill_due: Option<bool>,
+}
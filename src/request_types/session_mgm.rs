@@ -0,0 +1,27 @@
+use serde::Serialize;
+use uuid::Uuid;
+
+use crate::model::session::Session;
+
+#[derive(Serialize)]
+pub struct ListSessionsRes {
+    pub id: Uuid,
+    pub device_name: String,
+    pub platform: String,
+    pub created_at: String,
+    pub last_seen_at: String,
+    pub is_current: bool,
+}
+
+impl ListSessionsRes {
+    pub fn new(session: Session, current_session_id: &Uuid) -> Self {
+        ListSessionsRes {
+            is_current: session.id == *current_session_id,
+            id: session.id,
+            device_name: session.device_name,
+            platform: session.platform,
+            created_at: session.created_at.to_rfc3339(),
+            last_seen_at: session.last_seen_at.to_rfc3339(),
+        }
+    }
+}
@@ -0,0 +1,38 @@
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{
+    model::{category::Category, subcategory::Subcategory},
+    request_types::{
+        account::ListAccountsRes,
+        credit_card::{ListCreditCardBillsRes, ListCreditCardsRes},
+        transaction::ListTransactionsRes,
+        transfer::ListTransfersRes,
+    },
+};
+
+#[derive(Deserialize)]
+pub struct SyncReq {
+    /// Cursor returned by the previous sync. Without it everything is sent.
+    pub since: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct DeletedRes {
+    pub kind: String,
+    pub id: Uuid,
+}
+
+/// Subcategories come in their own list, so `categories` carry none.
+#[derive(Serialize)]
+pub struct SyncRes {
+    pub cursor: String,
+    pub categories: Vec<Category>,
+    pub subcategories: Vec<Subcategory>,
+    pub credit_cards: Vec<ListCreditCardsRes>,
+    pub credit_card_bills: Vec<ListCreditCardBillsRes>,
+    pub transactions: Vec<ListTransactionsRes>,
+    pub accounts: Vec<ListAccountsRes>,
+    pub transfers: Vec<ListTransfersRes>,
+    pub deleted: Vec<DeletedRes>,
+}
@@ -0,0 +1,96 @@
+use actix_web::web;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::model::transaction::{Transaction, TransactionKind};
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct UpsertTransactionReq {
+    pub id: Uuid,
+    pub kind: TransactionKind,
+    pub amount: i64,
+    pub description: String,
+    pub date: String,
+    pub category_id: Option<Uuid>,
+    pub subcategory_id: Option<Uuid>,
+    pub credit_card_id: Option<Uuid>,
+    pub account_id: Option<Uuid>,
+    pub installments: Option<i16>,
+}
+
+impl From<web::Json<UpsertTransactionReq>> for UpsertTransactionReq {
+    fn from(value: web::Json<UpsertTransactionReq>) -> Self {
+        UpsertTransactionReq {
+            id: value.id,
+            kind: value.kind.clone(),
+            amount: value.amount,
+            description: value.description.clone(),
+            date: value.date.clone(),
+            category_id: value.category_id,
+            subcategory_id: value.subcategory_id,
+            credit_card_id: value.credit_card_id,
+            account_id: value.account_id,
+            installments: value.installments,
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct DeleteTransactionReq {
+    pub transaction_id: Uuid,
+}
+
+impl From<web::Json<DeleteTransactionReq>> for DeleteTransactionReq {
+    fn from(value: web::Json<DeleteTransactionReq>) -> Self {
+        DeleteTransactionReq {
+            transaction_id: value.transaction_id,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct ListTransactionsReq {
+    pub from: Option<String>,
+    pub to: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct ListTransactionsRes {
+    pub id: Uuid,
+    pub kind: TransactionKind,
+    pub amount: i64,
+    pub description: String,
+    pub date: String,
+    pub category_id: Option<Uuid>,
+    pub subcategory_id: Option<Uuid>,
+    pub credit_card_id: Option<Uuid>,
+    pub credit_card_bill_id: Option<Uuid>,
+    pub account_id: Option<Uuid>,
+    pub parent_transaction_id: Option<Uuid>,
+    pub installment_number: i16,
+    pub installment_count: i16,
+    pub recurring_transaction_id: Option<Uuid>,
+    pub occurrence_number: Option<i32>,
+}
+
+impl From<Transaction> for ListTransactionsRes {
+    fn from(value: Transaction) -> Self {
+        ListTransactionsRes {
+            id: value.id,
+            kind: value.kind,
+            amount: value.amount,
+            description: value.description,
+            date: value.transaction_date.to_rfc3339(),
+            category_id: value.category_id,
+            subcategory_id: value.subcategory_id,
+            credit_card_id: value.credit_card_id,
+            credit_card_bill_id: value.credit_card_bill_id,
+            account_id: value.account_id,
+            parent_transaction_id: value.parent_transaction_id,
+            installment_number: value.installment_number,
+            installment_count: value.installment_count,
+            recurring_transaction_id: value.recurring_transaction_id,
+            occurrence_number: value.occurrence_number,
+        }
+    }
+}
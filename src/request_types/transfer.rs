@@ -0,0 +1,66 @@
+use actix_web::web;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::model::transfer::Transfer;
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct CreateTransferReq {
+    pub id: Uuid,
+    pub from_account_id: Uuid,
+    pub to_account_id: Uuid,
+    pub amount: i64,
+    pub description: String,
+    pub date: String,
+}
+
+impl From<web::Json<CreateTransferReq>> for CreateTransferReq {
+    fn from(value: web::Json<CreateTransferReq>) -> Self {
+        CreateTransferReq {
+            id: value.id,
+            from_account_id: value.from_account_id,
+            to_account_id: value.to_account_id,
+            amount: value.amount,
+            description: value.description.clone(),
+            date: value.date.clone(),
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct DeleteTransferReq {
+    pub transfer_id: Uuid,
+}
+
+impl From<web::Json<DeleteTransferReq>> for DeleteTransferReq {
+    fn from(value: web::Json<DeleteTransferReq>) -> Self {
+        DeleteTransferReq {
+            transfer_id: value.transfer_id,
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct ListTransfersRes {
+    pub id: Uuid,
+    pub from_account_id: Uuid,
+    pub to_account_id: Option<Uuid>,
+    pub credit_card_bill_id: Option<Uuid>,
+    pub amount: i64,
+    pub description: String,
+    pub date: String,
+}
+
+impl From<Transfer> for ListTransfersRes {
+    fn from(value: Transfer) -> Self {
+        ListTransfersRes {
+            id: value.id,
+            from_account_id: value.from_account_id,
+            to_account_id: value.to_account_id,
+            credit_card_bill_id: value.credit_card_bill_id,
+            amount: value.amount,
+            description: value.description,
+            date: value.transfer_date.to_rfc3339(),
+        }
+    }
+}
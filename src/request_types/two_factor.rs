@@ -0,0 +1,13 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct TwoFactorCodeReq {
+    /// Authenticator code or, when it isn't available, a recovery code
+    pub code: String,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct LoginTwoFactorReq {
+    pub challenge_token: String,
+    pub code: String,
+}
@@ -0,0 +1,63 @@
+use serde::{Deserialize, Serialize};
+
+use crate::model::{
+    identity::Identity,
+    user::{AuthType, User},
+};
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct LinkGoogleReq {
+    pub token: String,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct LinkPasswordReq {
+    pub password: String,
+}
+
+#[derive(Serialize)]
+pub struct ListIdentitiesRes {
+    pub auth_type: AuthType,
+    pub created_at: String,
+}
+
+impl From<Identity> for ListIdentitiesRes {
+    fn from(value: Identity) -> Self {
+        ListIdentitiesRes {
+            auth_type: value.auth_type,
+            created_at: value.created_at.to_rfc3339(),
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct UpdateUserReq {
+    pub name: Option<String>,
+    /// Only changed once the new address is verified
+    pub email: Option<String>,
+    pub current_password: Option<String>,
+    pub new_password: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct UserRes {
+    pub id: i32,
+    pub name: String,
+    pub email: String,
+    pub created_at: String,
+    pub is_email_verified: bool,
+    pub is_premium: bool,
+}
+
+impl From<User> for UserRes {
+    fn from(value: User) -> Self {
+        UserRes {
+            id: value.id,
+            name: value.name,
+            email: value.email,
+            created_at: value.created_at.to_rfc3339(),
+            is_email_verified: value.is_email_verified,
+            is_premium: value.is_premium,
+        }
+    }
+}
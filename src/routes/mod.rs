@@ -3,18 +3,35 @@ use actix_web::{middleware::from_fn, web};
 
 use crate::{
     handlers::{
+        account::{delete_account, list_account, upsert_account},
         auth::*,
+        budget::{delete_budget, list_budget, upsert_budget},
         category::{
             delete_category, delete_subcategory, list_category, upsert_category, upsert_subcategory,
         },
         credit_card::{
-            create_bill_of_date, delete_credit_card, list_credit_card, list_credit_card_bills,
-            upsert_credit_card,
+            create_bill_of_date, delete_credit_card, get_credit_card, list_credit_card,
+            list_credit_card_bills, pay_credit_card_bill, upsert_credit_card,
         },
+        data_export::{download_data_export, request_data_export},
         html::terms_of_use,
+        recurring_transaction::{
+            delete_recurring_transaction, list_recurring_transaction,
+            materialize_recurring_occurrence, skip_recurring_occurrence,
+            upsert_recurring_transaction,
+        },
+        report::{report_by_category, report_cashflow},
         reset_password::{create_reset_password_request, do_reset_password, reset_password_form},
-        session_mgm::{logout_user, ping},
+        session_mgm::{list_sessions, logout_user, ping, revoke_session},
         static_content::file_list_handler,
+        sync::sync,
+        transaction::{delete_transaction, list_transaction, upsert_transaction},
+        transfer::{create_transfer, delete_transfer, list_transfer},
+        two_factor::{confirm_totp, disable_totp, enroll_totp},
+        user::{
+            delete_me, get_me, link_google, link_password, list_identities, unlink_identity,
+            update_me,
+        },
     },
     middleware::{auth_middleware, refresh_token_middleware},
 };
@@ -24,7 +41,14 @@ pub fn auth_routes(cfg: &mut web::ServiceConfig) {
         web::scope("/auth")
             .route("/create_user", web::post().to(post_new_user))
             .route("/login", web::post().to(login_user))
-            .route("/google_signin", web::post().to(google_signin)),
+            .route("/login/2fa", web::post().to(login_two_factor))
+            .route("/google_signin", web::post().to(google_signin))
+            .route("/apple_signin", web::post().to(apple_signin))
+            .route("/verify_email", web::get().to(verify_email))
+            .route(
+                "/resend_verification_email",
+                web::post().to(resend_verification_email),
+            ),
     );
 }
 
@@ -40,6 +64,8 @@ pub fn session_mgm_routes(cfg: &mut web::ServiceConfig) {
     cfg.service(
         web::scope("/session")
             .wrap(from_fn(auth_middleware))
+            .route("", web::get().to(list_sessions))
+            .route("/{id}", web::delete().to(revoke_session))
             .route("/ping", web::get().to(ping))
             .route("/logout", web::post().to(logout_user)),
     );
@@ -85,7 +111,9 @@ pub fn credit_card_routes(cfg: &mut web::ServiceConfig) {
             .route("", web::post().to(upsert_credit_card))
             .route("", web::delete().to(delete_credit_card))
             .route("", web::get().to(list_credit_card))
+            .route("/limit", web::get().to(get_credit_card))
             .route("/bill", web::get().to(list_credit_card_bills))
+            .route("/bill/pay", web::post().to(pay_credit_card_bill))
             .route("bill_of_date", web::post().to(create_bill_of_date))
             .service(
                 fs::Files::new("/icons", "./card_icons")
@@ -94,3 +122,93 @@ pub fn credit_card_routes(cfg: &mut web::ServiceConfig) {
             ),
     );
 }
+
+pub fn transaction_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::scope("/transaction")
+            .wrap(from_fn(auth_middleware))
+            .route("", web::post().to(upsert_transaction))
+            .route("", web::delete().to(delete_transaction))
+            .route("", web::get().to(list_transaction)),
+    );
+}
+
+pub fn account_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::scope("/account")
+            .wrap(from_fn(auth_middleware))
+            .route("", web::post().to(upsert_account))
+            .route("", web::delete().to(delete_account))
+            .route("", web::get().to(list_account))
+            .route("/transfer", web::post().to(create_transfer))
+            .route("/transfer", web::delete().to(delete_transfer))
+            .route("/transfer", web::get().to(list_transfer)),
+    );
+}
+
+pub fn recurring_transaction_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::scope("/recurring")
+            .wrap(from_fn(auth_middleware))
+            .route("", web::post().to(upsert_recurring_transaction))
+            .route("", web::delete().to(delete_recurring_transaction))
+            .route("", web::get().to(list_recurring_transaction))
+            .route(
+                "/occurrence",
+                web::post().to(materialize_recurring_occurrence),
+            )
+            .route(
+                "/occurrence/skip",
+                web::post().to(skip_recurring_occurrence),
+            ),
+    );
+}
+
+pub fn budget_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::scope("/budget")
+            .wrap(from_fn(auth_middleware))
+            .route("", web::post().to(upsert_budget))
+            .route("", web::delete().to(delete_budget))
+            .route("", web::get().to(list_budget)),
+    );
+}
+
+pub fn report_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::scope("/report")
+            .wrap(from_fn(auth_middleware))
+            .route("/by_category", web::get().to(report_by_category))
+            .route("/cashflow", web::get().to(report_cashflow)),
+    );
+}
+
+pub fn sync_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::scope("/sync")
+            .wrap(from_fn(auth_middleware))
+            .route("", web::get().to(sync)),
+    );
+}
+
+pub fn user_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::scope("/user")
+            .wrap(from_fn(auth_middleware))
+            .route("/me", web::get().to(get_me))
+            .route("/me", web::patch().to(update_me))
+            .route("/me", web::delete().to(delete_me))
+            .route("/me/export", web::get().to(request_data_export))
+            .route("/2fa/totp", web::post().to(enroll_totp))
+            .route("/2fa/totp", web::delete().to(disable_totp))
+            .route("/2fa/totp/confirm", web::post().to(confirm_totp))
+            .route("/identity", web::get().to(list_identities))
+            .route("/identity/google", web::post().to(link_google))
+            .route("/identity/password", web::post().to(link_password))
+            .route("/identity/{auth_type}", web::delete().to(unlink_identity)),
+    );
+}
+
+pub fn data_export_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(web::scope("/export").route("/download", web::get().to(download_data_export)));
+}
@@ -1,9 +1,21 @@
+use std::path::PathBuf;
+
 use jsonwebtoken::{DecodingKey, EncodingKey};
 use sqlx::{Pool, Postgres};
 
+use crate::{email::EmailSender, jwks::JwksCache, throttle::Throttle};
+
 pub struct AppState {
     pub db: Pool<Postgres>,
     pub jwt_encoding_key: EncodingKey,
     pub jwt_decoding_key: DecodingKey,
     pub google_oauth_client_id: String,
+    pub google_jwks: JwksCache,
+    pub apple_client_id: String,
+    pub apple_jwks: JwksCache,
+    pub public_base_url: String,
+    pub email_sender: Box<dyn EmailSender>,
+    pub account_deletion_grace_period: chrono::Duration,
+    pub data_export_dir: PathBuf,
+    pub throttle: Throttle,
 }
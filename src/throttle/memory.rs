@@ -0,0 +1,120 @@
+use std::{collections::HashMap, sync::Mutex};
+
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, Utc};
+
+use super::{AttemptStore, Attempts, ThrottleError};
+
+/// Keeps attempts in the process memory. They're lost on restarts and not
+/// shared between instances, so it's meant for development and single
+/// instance deployments.
+pub struct MemoryAttemptStore {
+    retention: Duration,
+    attempts: Mutex<HashMap<String, Vec<DateTime<Utc>>>>,
+}
+
+impl MemoryAttemptStore {
+    pub fn new(retention: Duration) -> Self {
+        MemoryAttemptStore {
+            retention,
+            attempts: Mutex::new(HashMap::new()),
+        }
+    }
+}
+
+#[async_trait]
+impl AttemptStore for MemoryAttemptStore {
+    async fn record(
+        &self,
+        key: &str,
+        at: DateTime<Utc>,
+        since: DateTime<Utc>,
+    ) -> Result<Attempts, ThrottleError> {
+        let expired_before = at - self.retention;
+        let mut attempts = self.attempts.lock().unwrap();
+
+        attempts.retain(|_, a| {
+            a.retain(|t| *t >= expired_before);
+            !a.is_empty()
+        });
+
+        let key_attempts = attempts.entry(String::from(key)).or_default();
+        let recent: Vec<_> = key_attempts.iter().filter(|t| **t >= since).collect();
+        let res = Attempts {
+            count: recent.len() as u32,
+            last: recent.into_iter().max().copied(),
+        };
+        key_attempts.push(at);
+
+        Ok(res)
+    }
+
+    async fn remove(&self, key: &str, at: DateTime<Utc>) -> Result<(), ThrottleError> {
+        let mut attempts = self.attempts.lock().unwrap();
+        if let Some(a) = attempts.get_mut(key) {
+            if let Some(i) = a.iter().position(|t| *t == at) {
+                a.remove(i);
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn clear(&self, key: &str) -> Result<(), ThrottleError> {
+        self.attempts.lock().unwrap().remove(key);
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[actix_rt::test]
+    async fn test_memory_attempt_store() {
+        let store = MemoryAttemptStore::new(Duration::minutes(15));
+        let now = Utc::now();
+        let since = now - Duration::minutes(15);
+
+        store
+            .record("a", now - Duration::minutes(20), since)
+            .await
+            .unwrap();
+        store
+            .record("a", now - Duration::minutes(10), since)
+            .await
+            .unwrap();
+        store.record("b", now, since).await.unwrap();
+
+        // The expired attempt isn't counted, nor the one being recorded
+        assert_eq!(
+            store.record("a", now, since).await.unwrap(),
+            Attempts {
+                count: 1,
+                last: Some(now - Duration::minutes(10))
+            }
+        );
+
+        store.remove("a", now).await.unwrap();
+        assert_eq!(
+            store
+                .record("a", now + Duration::seconds(1), since)
+                .await
+                .unwrap()
+                .count,
+            1
+        );
+
+        store.clear("a").await.unwrap();
+        assert_eq!(
+            store.record("a", now, since).await.unwrap(),
+            Attempts {
+                count: 0,
+                last: None
+            }
+        );
+        assert_eq!(store.record("b", now, since).await.unwrap().count, 1);
+    }
+}
@@ -0,0 +1,285 @@
+pub mod memory;
+pub mod postgres;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, Utc};
+
+pub type ThrottleError = Box<dyn std::error::Error + Send + Sync>;
+
+/// Attempts recorded for a key since some time.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Attempts {
+    pub count: u32,
+    pub last: Option<DateTime<Utc>>,
+}
+
+/// Where attempts are recorded. Stores drop attempts older than their
+/// retention, which must cover the longest window of the limits.
+#[async_trait]
+pub trait AttemptStore: Send + Sync {
+    /// Records an attempt and returns the other ones since `since`. The
+    /// attempt is stored before counting, so parallel attempts see each
+    /// other instead of all passing the limit.
+    async fn record(
+        &self,
+        key: &str,
+        at: DateTime<Utc>,
+        since: DateTime<Utc>,
+    ) -> Result<Attempts, ThrottleError>;
+    /// Removes the attempt recorded at `at`.
+    async fn remove(&self, key: &str, at: DateTime<Utc>) -> Result<(), ThrottleError>;
+    async fn clear(&self, key: &str) -> Result<(), ThrottleError>;
+}
+
+pub struct Limit {
+    /// Attempts within the window after which the key is locked until the
+    /// window passes since the last attempt.
+    pub max_attempts: u32,
+    /// Attempts allowed without any delay. Each one after it doubles the
+    /// delay, starting at `base_delay` and up to `max_delay`.
+    pub free_attempts: u32,
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+    pub window: Duration,
+}
+
+impl Limit {
+    /// Returns when the next attempt is allowed, or None when it's allowed
+    /// right away.
+    pub fn retry_at(&self, attempts: &Attempts) -> Option<DateTime<Utc>> {
+        let last = attempts.last?;
+
+        if attempts.count >= self.max_attempts {
+            return Some(last + self.window);
+        }
+        if attempts.count <= self.free_attempts {
+            return None;
+        }
+
+        let exponent = (attempts.count - self.free_attempts - 1).min(20);
+        let delay = self.base_delay * 2_i32.pow(exponent);
+        Some(last + delay.min(self.max_delay))
+    }
+}
+
+pub struct ThrottleConfig {
+    pub login_per_email: Limit,
+    pub login_per_ip: Limit,
+    pub reset_per_email: Limit,
+    pub reset_per_ip: Limit,
+    pub two_factor_per_user: Limit,
+    pub two_factor_per_ip: Limit,
+}
+
+impl ThrottleConfig {
+    /// How long stores must keep attempts.
+    pub fn retention(&self) -> Duration {
+        [
+            &self.login_per_email,
+            &self.login_per_ip,
+            &self.reset_per_email,
+            &self.reset_per_ip,
+            &self.two_factor_per_user,
+            &self.two_factor_per_ip,
+        ]
+        .iter()
+        .map(|l| l.window)
+        .max()
+        .unwrap()
+    }
+}
+
+/// A login attempt, counted as failed until `Throttle::clear_login`.
+pub struct LoginAttempt {
+    email_key: String,
+    ip_key: String,
+    at: DateTime<Utc>,
+    /// When the next attempt is allowed, if this one isn't
+    pub retry_at: Option<DateTime<Utc>>,
+}
+
+/// Slows down password and 2FA code guessing and reset email flooding, per
+/// account and per IP.
+pub struct Throttle {
+    store: Box<dyn AttemptStore>,
+    config: ThrottleConfig,
+}
+
+impl Throttle {
+    pub fn new(store: Box<dyn AttemptStore>, config: ThrottleConfig) -> Self {
+        Throttle { store, config }
+    }
+
+    /// Records the attempt for every key, returning when the next one is
+    /// allowed if this one isn't. Refused attempts are removed, so waiting
+    /// for the delay isn't extended by them.
+    async fn record(
+        &self,
+        checks: [(&Limit, &str); 2],
+        now: DateTime<Utc>,
+    ) -> Result<Option<DateTime<Utc>>, ThrottleError> {
+        let mut res: Option<DateTime<Utc>> = None;
+
+        for (limit, key) in checks {
+            let attempts = self.store.record(key, now, now - limit.window).await?;
+            let retry_at = limit.retry_at(&attempts).filter(|r| *r > now);
+            res = res.max(retry_at);
+        }
+
+        if res.is_some() {
+            for (_, key) in checks {
+                self.store.remove(key, now).await?;
+            }
+        }
+
+        Ok(res)
+    }
+
+    /// Starts a login for the email from the IP. Unless `retry_at` is set,
+    /// the credentials may be checked, and the attempt is cleared once the
+    /// login completes.
+    pub async fn check_login(&self, email: &str, ip: &str) -> Result<LoginAttempt, ThrottleError> {
+        let email_key = login_email_key(email);
+        let ip_key = login_ip_key(ip);
+        let at = Utc::now();
+
+        let retry_at = self
+            .record(
+                [
+                    (&self.config.login_per_email, email_key.as_str()),
+                    (&self.config.login_per_ip, ip_key.as_str()),
+                ],
+                at,
+            )
+            .await?;
+
+        Ok(LoginAttempt {
+            email_key,
+            ip_key,
+            at,
+            retry_at,
+        })
+    }
+
+    /// Forgets the account's failures after a successful login. The IP's
+    /// other attempts are kept, as it may be guessing other accounts.
+    pub async fn clear_login(&self, attempt: &LoginAttempt) -> Result<(), ThrottleError> {
+        self.store.clear(attempt.email_key.as_str()).await?;
+        self.store.remove(attempt.ip_key.as_str(), attempt.at).await
+    }
+
+    /// Records a reset request, returning when the next one is allowed if
+    /// this one isn't.
+    pub async fn check_reset_request(
+        &self,
+        email: &str,
+        ip: &str,
+    ) -> Result<Option<DateTime<Utc>>, ThrottleError> {
+        self.record(
+            [
+                (
+                    &self.config.reset_per_email,
+                    reset_email_key(email).as_str(),
+                ),
+                (&self.config.reset_per_ip, reset_ip_key(ip).as_str()),
+            ],
+            Utc::now(),
+        )
+        .await
+    }
+
+    /// Records a 2FA code sent to change the user's 2FA settings, returning
+    /// when the next one is allowed if this one isn't. Valid codes count
+    /// too, as they are only sent a few times.
+    pub async fn check_two_factor_code(
+        &self,
+        user_id: i32,
+        ip: &str,
+    ) -> Result<Option<DateTime<Utc>>, ThrottleError> {
+        self.record(
+            [
+                (
+                    &self.config.two_factor_per_user,
+                    two_factor_user_key(user_id).as_str(),
+                ),
+                (
+                    &self.config.two_factor_per_ip,
+                    two_factor_ip_key(ip).as_str(),
+                ),
+            ],
+            Utc::now(),
+        )
+        .await
+    }
+}
+
+pub(crate) fn login_email_key(email: &str) -> String {
+    format!("login:email:{}", email.trim().to_lowercase())
+}
+
+fn login_ip_key(ip: &str) -> String {
+    format!("login:ip:{}", ip)
+}
+
+pub(crate) fn reset_email_key(email: &str) -> String {
+    format!("reset:email:{}", email.trim().to_lowercase())
+}
+
+fn reset_ip_key(ip: &str) -> String {
+    format!("reset:ip:{}", ip)
+}
+
+fn two_factor_user_key(user_id: i32) -> String {
+    format!("2fa:user:{}", user_id)
+}
+
+fn two_factor_ip_key(ip: &str) -> String {
+    format!("2fa:ip:{}", ip)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_limit_retry_at() {
+        let limit = Limit {
+            max_attempts: 6,
+            free_attempts: 2,
+            base_delay: Duration::seconds(1),
+            max_delay: Duration::seconds(5),
+            window: Duration::minutes(15),
+        };
+        let last = Utc::now();
+        let attempts = |count| Attempts {
+            count,
+            last: Some(last),
+        };
+
+        assert_eq!(
+            limit.retry_at(&Attempts {
+                count: 0,
+                last: None
+            }),
+            None
+        );
+        assert_eq!(limit.retry_at(&attempts(2)), None);
+        assert_eq!(
+            limit.retry_at(&attempts(3)),
+            Some(last + Duration::seconds(1))
+        );
+        assert_eq!(
+            limit.retry_at(&attempts(4)),
+            Some(last + Duration::seconds(2))
+        );
+        assert_eq!(
+            limit.retry_at(&attempts(5)),
+            Some(last + Duration::seconds(4))
+        );
+        assert_eq!(
+            limit.retry_at(&attempts(6)),
+            Some(last + Duration::minutes(15))
+        );
+    }
+}
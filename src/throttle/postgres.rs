@@ -0,0 +1,102 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use sqlx::{Pool, Postgres};
+use uuid::Uuid;
+
+use super::{AttemptStore, Attempts, ThrottleError};
+
+/// Keeps attempts in the login_attempt table, shared by every instance.
+pub struct PostgresAttemptStore {
+    db: Pool<Postgres>,
+    retention: Duration,
+}
+
+impl PostgresAttemptStore {
+    pub fn new(db: Pool<Postgres>, retention: Duration) -> Self {
+        PostgresAttemptStore { db, retention }
+    }
+}
+
+#[async_trait]
+impl AttemptStore for PostgresAttemptStore {
+    async fn record(
+        &self,
+        key: &str,
+        at: DateTime<Utc>,
+        since: DateTime<Utc>,
+    ) -> Result<Attempts, ThrottleError> {
+        let _ = sqlx::query!(
+            r#"
+                DELETE FROM login_attempt WHERE attempted_at < $1
+            "#,
+            (at - self.retention).naive_utc()
+        )
+        .execute(&self.db)
+        .await?;
+
+        // Committed before counting, so a parallel attempt counting after
+        // this insert sees it
+        let id = Uuid::new_v4();
+        let _ = sqlx::query!(
+            r#"
+                INSERT INTO login_attempt (id, key, attempted_at)
+                VALUES ($1, $2, $3)
+            "#,
+            id,
+            key,
+            at.naive_utc()
+        )
+        .execute(&self.db)
+        .await?;
+
+        let res = sqlx::query!(
+            r#"
+                SELECT COUNT(1) AS "count!", MAX(attempted_at) AS last
+                FROM login_attempt
+                WHERE key = $1 AND attempted_at >= $2 AND id <> $3
+            "#,
+            key,
+            since.naive_utc(),
+            id
+        )
+        .fetch_one(&self.db)
+        .await?;
+
+        Ok(Attempts {
+            count: res.count as u32,
+            last: res.last.map(|l| Utc.from_utc_datetime(&l)),
+        })
+    }
+
+    async fn remove(&self, key: &str, at: DateTime<Utc>) -> Result<(), ThrottleError> {
+        let _ = sqlx::query!(
+            r#"
+                DELETE FROM login_attempt
+                WHERE id = (
+                    SELECT id FROM login_attempt
+                    WHERE key = $1 AND attempted_at = $2
+                    LIMIT 1
+                )
+            "#,
+            key,
+            at.naive_utc()
+        )
+        .execute(&self.db)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn clear(&self, key: &str) -> Result<(), ThrottleError> {
+        let _ = sqlx::query!(
+            r#"
+                DELETE FROM login_attempt WHERE key = $1
+            "#,
+            key
+        )
+        .execute(&self.db)
+        .await?;
+
+        Ok(())
+    }
+}